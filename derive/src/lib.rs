@@ -0,0 +1,182 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+//! Derive macros for the shareable crate, use them through the derive feature of shareable
+//! instead of depending on this crate directly.
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use syn::{Data, DeriveInput, Error, Field, Fields};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::token::Comma;
+
+//*************************************************************************************************
+/// Derives shareable::FromConfig for a struct with named fields.  Each field is looked up in the
+/// config by its name and parsed with its FromStr implementation.
+#[proc_macro_derive(FromConfig)]
+pub fn derive_from_config(input : TokenStream) -> TokenStream
+{
+    let input = syn::parse_macro_input!(input as DeriveInput);
+
+    match from_config(&input)
+    {
+        Ok(tokens) => tokens.into(),
+        Err(err)   => compile_error(&err).into()
+    }
+}
+
+//*************************************************************************************************
+/// Derives dup() and Debug for a struct with named fields that are all shareable objects.
+///
+/// The generated `dup(&mut self) -> Self` calls dup() on every field, so each field is shared on
+/// its own and reading one field never clones the others.  Debug prints each field with its own
+/// Debug implementation.
+///
+/// ```
+/// # extern crate shareable;
+/// use shareable::{Shareable, SharedObject, SharedU32};
+///
+/// #[derive(Shareable)]
+/// struct Settings
+/// {
+///     threads : SharedU32,
+///     name    : SharedObject<String>
+/// }
+///
+/// let mut settings = Settings {
+///     threads : SharedU32::new(4),
+///     name    : SharedObject::new(String::from("main"))
+/// };
+/// let mut copy     = settings.dup();
+///
+/// copy.threads.set(8);
+///
+/// assert_eq!(settings.threads.get(), 8);
+/// ```
+#[proc_macro_derive(Shareable)]
+pub fn derive_shareable(input : TokenStream) -> TokenStream
+{
+    let input = syn::parse_macro_input!(input as DeriveInput);
+
+    match shareable(&input)
+    {
+        Ok(tokens) => tokens.into(),
+        Err(err)   => compile_error(&err).into()
+    }
+}
+
+//*************************************************************************************************
+/// Returns a compile_error!() reporting err.  Error::to_compile_error() names the macro through
+/// ::core, which doesn't resolve in 2015 edition crates.
+fn compile_error(err : &Error) -> proc_macro2::TokenStream
+{
+    let message = err.to_string();
+
+    quote_spanned!(err.span()=> compile_error!(#message);)
+}
+
+//*************************************************************************************************
+/// Returns the named fields of the struct, or an error naming the derive if it isn't a struct
+/// with named fields.
+fn named_fields<'a>(
+    input  : &'a DeriveInput,
+    derive : &str
+    ) -> Result<&'a Punctuated<Field, Comma>, Error>
+{
+    match input.data
+    {
+        Data::Struct(ref data) => match data.fields
+        {
+            Fields::Named(ref fields) => Ok(&fields.named),
+            _                         => {
+                Err(Error::new_spanned(&input.ident, format!("{} needs named fields", derive)))
+            }
+        },
+        _ => Err(Error::new_spanned(&input.ident, format!("{} needs a struct", derive)))
+    }
+}
+
+//*************************************************************************************************
+/// Generates the FromConfig implementation.
+fn from_config(input : &DeriveInput) -> Result<proc_macro2::TokenStream, Error>
+{
+    let fields = named_fields(input, "FromConfig")?;
+
+    let name                       = &input.ident;
+    let (impl_gen, ty_gen, where_) = input.generics.split_for_impl();
+    let idents                     = fields.iter().map(|field| field.ident.as_ref().unwrap());
+    let keys                       = fields.iter().map(|field| {
+        field.ident.as_ref().unwrap().to_string().trim_start_matches("r#").to_string()
+    });
+
+    Ok(quote! {
+        impl #impl_gen ::shareable::FromConfig for #name #ty_gen #where_
+        {
+            fn from_config(
+                config : &::std::collections::HashMap<::std::string::String, ::std::string::String>
+                ) -> ::std::result::Result<Self, ::shareable::ConfigError>
+            {
+                ::std::result::Result::Ok(#name {
+                    #( #idents : ::shareable::parse_config_value(config, #keys)?, )*
+                })
+            }
+        }
+    })
+}
+
+//*************************************************************************************************
+/// Generates dup() and the Debug implementation.
+fn shareable(input : &DeriveInput) -> Result<proc_macro2::TokenStream, Error>
+{
+    let fields = named_fields(input, "Shareable")?;
+
+    let name                       = &input.ident;
+    let label                      = name.to_string();
+    let (impl_gen, ty_gen, where_) = input.generics.split_for_impl();
+    let idents                     = fields.iter().map(|field| field.ident.as_ref().unwrap());
+    let dups                       = fields.iter().map(|field| {
+        let ident = field.ident.as_ref().unwrap();
+
+        quote_spanned!(field.ty.span()=> #ident : self.#ident.dup())
+    });
+    let labels                     = fields.iter().map(|field| {
+        field.ident.as_ref().unwrap().to_string().trim_start_matches("r#").to_string()
+    });
+
+    Ok(quote! {
+        impl #impl_gen #name #ty_gen #where_
+        {
+            /// Creates a new instance sharing every field with this one, see the dup() of the
+            /// field types.
+            pub fn dup(&mut self) -> Self
+            {
+                #name {
+                    #( #dups, )*
+                }
+            }
+        }
+
+        impl #impl_gen ::std::fmt::Debug for #name #ty_gen #where_
+        {
+            fn fmt(
+                &self,
+                f : &mut ::std::fmt::Formatter
+                ) -> ::std::fmt::Result
+            {
+                f.debug_struct(#label)
+                    #( .field(#labels, &self.#idents) )*
+                    .finish()
+            }
+        }
+    })
+}
@@ -0,0 +1,22 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Tests that check derives which must not compile.
+extern crate trybuild;
+
+//*************************************************************************************************
+/// Run all the files in tests/compile_fail, each one must fail to compile with the error saved
+/// in the matching .stderr file.
+#[test]
+fn compile_fail()
+{
+    let tests = trybuild::TestCases::new();
+
+    tests.compile_fail("tests/compile_fail/*.rs");
+}
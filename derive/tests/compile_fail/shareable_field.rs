@@ -0,0 +1,14 @@
+extern crate shareable;
+
+use shareable::{Shareable, SharedU32};
+
+#[derive(Shareable)]
+struct Settings
+{
+    threads : SharedU32,
+    limit   : u32
+}
+
+fn main()
+{
+}
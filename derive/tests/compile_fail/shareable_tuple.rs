@@ -0,0 +1,10 @@
+extern crate shareable;
+
+use shareable::{Shareable, SharedU32};
+
+#[derive(Shareable)]
+struct Settings(SharedU32);
+
+fn main()
+{
+}
@@ -0,0 +1,82 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+extern crate shareable;
+
+use std::collections::HashMap;
+use shareable::{ConfigError, FromConfig, SharedF64, SharedObject, SharedU32};
+
+//*************************************************************************************************
+/// Settings populated from the config.
+#[derive(FromConfig)]
+struct Settings
+{
+    //---------------------------------------------------------------------------------------------
+    /// Number of worker threads.
+    threads : SharedU32,
+
+    //---------------------------------------------------------------------------------------------
+    /// Fraction of the work to sample.
+    ratio   : SharedF64,
+
+    //---------------------------------------------------------------------------------------------
+    /// Name of the instance.
+    name    : SharedObject<String>
+}
+
+//*************************************************************************************************
+/// Builds a config from key value pairs.
+fn config(pairs : &[(&str, &str)]) -> HashMap<String, String>
+{
+    pairs.iter().map(|&(key, value)| (String::from(key), String::from(value))).collect()
+}
+
+//*************************************************************************************************
+/// Test populating the fields from a map.
+#[test]
+fn populate()
+{
+    let config   = config(&[("threads", "8"), ("ratio", "0.25"), ("name", "main")]);
+    let settings = Settings::from_config(&config).unwrap();
+
+    assert_eq!(settings.threads.get(), 8);
+    assert_eq!(settings.ratio.get(), 0.25);
+    assert_eq!(*settings.name.get(), "main");
+}
+
+//*************************************************************************************************
+/// Test that the fields can still be shared after they are populated.
+#[test]
+fn populate_shared()
+{
+    let config       = config(&[("threads", "8"), ("ratio", "0.25"), ("name", "main")]);
+    let mut settings = Settings::from_config(&config).unwrap();
+    let threads      = settings.threads.dup();
+
+    settings.threads.set(2);
+
+    assert_eq!(threads.get(), 2);
+}
+
+//*************************************************************************************************
+/// Test the errors for missing and invalid values.
+#[test]
+fn errors()
+{
+    let missing = config(&[("threads", "8"), ("name", "main")]);
+    let invalid = config(&[("threads", "eight"), ("ratio", "0.25"), ("name", "main")]);
+
+    assert_eq!(
+        Settings::from_config(&missing).err(),
+        Some(ConfigError::Missing(String::from("ratio")))
+        );
+    assert_eq!(
+        Settings::from_config(&invalid).err(),
+        Some(ConfigError::Invalid { key : String::from("threads"), value : String::from("eight") })
+        );
+}
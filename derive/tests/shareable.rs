@@ -0,0 +1,90 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+extern crate shareable;
+
+use shareable::{Shareable, SharedBool, SharedObject, SharedU32};
+
+//*************************************************************************************************
+/// Settings shared field by field.
+#[derive(Shareable)]
+struct Settings
+{
+    //---------------------------------------------------------------------------------------------
+    /// Number of worker threads.
+    threads : SharedU32,
+
+    //---------------------------------------------------------------------------------------------
+    /// True while the workers should run.
+    running : SharedBool,
+
+    //---------------------------------------------------------------------------------------------
+    /// Name of the instance.
+    name    : SharedObject<String>
+}
+
+//*************************************************************************************************
+/// Builds the settings used by the tests.
+fn settings() -> Settings
+{
+    Settings {
+        threads : SharedU32::new(4),
+        running : SharedBool::new(true),
+        name    : SharedObject::new(String::from("main"))
+    }
+}
+
+//*************************************************************************************************
+/// Test that setting a field through the duplicate is seen by the original.
+#[test]
+fn dup()
+{
+    let mut settings = settings();
+    let mut copy     = settings.dup();
+
+    copy.threads.set(8);
+    copy.name.set(String::from("copy"));
+
+    assert_eq!(settings.threads.get(), 8);
+    assert_eq!(*settings.name.get(), "copy");
+
+    settings.running.set(false);
+
+    assert!(!copy.running.get());
+}
+
+//*************************************************************************************************
+/// Test that every field is shared.
+#[test]
+fn dup_shares_fields()
+{
+    let mut settings = settings();
+    let _copy        = settings.dup();
+
+    assert!(settings.threads.is_shared());
+    assert!(settings.running.is_shared());
+    assert!(settings.name.is_shared());
+}
+
+//*************************************************************************************************
+/// Test that Debug prints each field.
+#[test]
+fn debug()
+{
+    let settings = settings();
+
+    assert_eq!(
+        format!("{:?}", settings),
+        format!(
+            "Settings {{ threads: {:?}, running: {:?}, name: {:?} }}",
+            settings.threads,
+            settings.running,
+            settings.name
+            )
+        );
+}
@@ -0,0 +1,489 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+use core::sync::atomic::Ordering;
+
+pub(crate) mod sealed
+{
+    //*********************************************************************************************
+    /// Keeps AtomicRepr from being implemented outside of the crate.
+    pub trait Sealed {}
+}
+
+//*************************************************************************************************
+/// A primitive integer that can be saved in a Shared, maps the type to the storage used for it
+/// and the operations on that storage.
+///
+/// The storage is the matching std atomic, except for the 64 bit types on 32 bit targets which
+/// use a Mutex.  The trait is sealed, it is implemented for every primitive integer type.
+pub trait AtomicRepr : Copy + Default + Ord + Send + Sync + sealed::Sealed
+{
+    //*********************************************************************************************
+    /// The storage holding the value, shared between the objects after dup().
+    type Storage : Send + Sync;
+
+    //*********************************************************************************************
+    /// True if the storage is an atomic, false if it is a Mutex.
+    const LOCK_FREE : bool;
+
+    //*********************************************************************************************
+    /// Returns new storage holding val.
+    fn new_storage(val : Self) -> Self::Storage;
+
+    //*********************************************************************************************
+    /// Returns the value of storage that is not shared.
+    fn get_mut(storage : &mut Self::Storage) -> &mut Self;
+
+    //*********************************************************************************************
+    /// Returns the value, like AtomicU32::load().
+    fn load(storage : &Self::Storage, order : Ordering) -> Self;
+
+    //*********************************************************************************************
+    /// Sets the value, like AtomicU32::store().
+    fn store(storage : &Self::Storage, val : Self, order : Ordering);
+
+    //*********************************************************************************************
+    /// Sets the value and returns the previous value, like AtomicU32::swap().
+    fn swap(storage : &Self::Storage, val : Self, order : Ordering) -> Self;
+
+    //*********************************************************************************************
+    /// Sets the value to new if it is equal to current, like AtomicU32::compare_exchange().
+    fn compare_exchange(
+        storage : &Self::Storage,
+        current : Self,
+        new     : Self,
+        success : Ordering,
+        failure : Ordering
+        ) -> Result<Self, Self>;
+
+    //*********************************************************************************************
+    /// Stores f(current) if it returns Some, like AtomicU32::fetch_update().
+    fn fetch_update<F : FnMut(Self) -> Option<Self>>(
+        storage     : &Self::Storage,
+        set_order   : Ordering,
+        fetch_order : Ordering,
+        f           : F
+        ) -> Result<Self, Self>;
+
+    //*********************************************************************************************
+    /// Adds to the value wrapping around, like AtomicU32::fetch_add().
+    fn fetch_add(storage : &Self::Storage, val : Self, order : Ordering) -> Self;
+
+    //*********************************************************************************************
+    /// Subtracts from the value wrapping around, like AtomicU32::fetch_sub().
+    fn fetch_sub(storage : &Self::Storage, val : Self, order : Ordering) -> Self;
+
+    //*********************************************************************************************
+    /// Bitwise and with the value, like AtomicU32::fetch_and().
+    fn fetch_and(storage : &Self::Storage, val : Self, order : Ordering) -> Self;
+
+    //*********************************************************************************************
+    /// Bitwise or with the value, like AtomicU32::fetch_or().
+    fn fetch_or(storage : &Self::Storage, val : Self, order : Ordering) -> Self;
+
+    //*********************************************************************************************
+    /// Bitwise xor with the value, like AtomicU32::fetch_xor().
+    fn fetch_xor(storage : &Self::Storage, val : Self, order : Ordering) -> Self;
+
+    //*********************************************************************************************
+    /// Maximum of the value and val, like AtomicU32::fetch_max().
+    fn fetch_max(storage : &Self::Storage, val : Self, order : Ordering) -> Self;
+
+    //*********************************************************************************************
+    /// Minimum of the value and val, like AtomicU32::fetch_min().
+    fn fetch_min(storage : &Self::Storage, val : Self, order : Ordering) -> Self;
+
+    //*********************************************************************************************
+    /// The primitive wrapping_add().
+    fn wrapping_add(self, val : Self) -> Self;
+
+    //*********************************************************************************************
+    /// The primitive wrapping_sub().
+    fn wrapping_sub(self, val : Self) -> Self;
+
+    //*********************************************************************************************
+    /// The primitive saturating_add().
+    fn saturating_add(self, val : Self) -> Self;
+
+    //*********************************************************************************************
+    /// The primitive saturating_sub().
+    fn saturating_sub(self, val : Self) -> Self;
+
+    //*********************************************************************************************
+    /// The primitive checked_add().
+    fn checked_add(self, val : Self) -> Option<Self>;
+
+    //*********************************************************************************************
+    /// The primitive checked_sub().
+    fn checked_sub(self, val : Self) -> Option<Self>;
+}
+
+//*************************************************************************************************
+/// Forwards the arithmetic of AtomicRepr to the primitive methods.
+macro_rules! repr_arithmetic {
+    ($t:ty) => {
+        fn wrapping_add(self, val : $t) -> $t { <$t>::wrapping_add(self, val) }
+        fn wrapping_sub(self, val : $t) -> $t { <$t>::wrapping_sub(self, val) }
+        fn saturating_add(self, val : $t) -> $t { <$t>::saturating_add(self, val) }
+        fn saturating_sub(self, val : $t) -> $t { <$t>::saturating_sub(self, val) }
+        fn checked_add(self, val : $t) -> Option<$t> { <$t>::checked_add(self, val) }
+        fn checked_sub(self, val : $t) -> Option<$t> { <$t>::checked_sub(self, val) }
+    }
+}
+
+//*************************************************************************************************
+/// Adds get_be(), set_be(), get_le() and set_le() to the Shared type of a primitive.  Only the
+/// fixed size integers have them, usize and isize don't.
+macro_rules! byte_order {
+    ($t:ty) => {
+        impl $crate::Shared<$t>
+        {
+            //************************************************************************************
+            /// Returns the value, reading the stored bytes as big endian.  Use this with set_be()
+            /// when the stored bytes are also read by code that may run with a different byte
+            /// order, like another process mapping the same memory.  On big endian targets this
+            /// is the same as get().
+            pub fn get_be(&self) -> $t
+            {
+                <$t>::from_be(self.get())
+            }
+
+            //************************************************************************************
+            /// Set the value, storing its bytes in big endian order.
+            pub fn set_be(
+                &mut self,
+                val : $t
+                )
+            {
+                self.set(val.to_be());
+            }
+
+            //************************************************************************************
+            /// Returns the value, reading the stored bytes as little endian.  Use this with
+            /// set_le() when the stored bytes are also read by code that may run with a different
+            /// byte order.  On little endian targets this is the same as get().
+            pub fn get_le(&self) -> $t
+            {
+                <$t>::from_le(self.get())
+            }
+
+            //************************************************************************************
+            /// Set the value, storing its bytes in little endian order.
+            pub fn set_le(
+                &mut self,
+                val : $t
+                )
+            {
+                self.set(val.to_le());
+            }
+        }
+    }
+}
+
+//*************************************************************************************************
+/// Implements AtomicRepr for a primitive saved in its std atomic, along with as_atomic() and the
+/// conversions between the Shared type and the atomic.  The atomic and Ordering have to be in
+/// scope where it is used.
+macro_rules! lock_free {
+    ($t:ty, $atomic:ident) => {
+        impl $crate::atomic_repr::sealed::Sealed for $t {}
+
+        impl $crate::AtomicRepr for $t
+        {
+            type Storage = $atomic;
+
+            const LOCK_FREE : bool = true;
+
+            fn new_storage(val : $t) -> $atomic { $atomic::new(val) }
+            fn get_mut(storage : &mut $atomic) -> &mut $t { storage.get_mut() }
+            fn load(storage : &$atomic, order : Ordering) -> $t { storage.load(order) }
+            fn store(storage : &$atomic, val : $t, order : Ordering) { storage.store(val, order) }
+
+            fn swap(storage : &$atomic, val : $t, order : Ordering) -> $t
+            {
+                storage.swap(val, order)
+            }
+
+            fn compare_exchange(
+                storage : &$atomic,
+                current : $t,
+                new     : $t,
+                success : Ordering,
+                failure : Ordering
+                ) -> Result<$t, $t>
+            {
+                storage.compare_exchange(current, new, success, failure)
+            }
+
+            fn fetch_update<F : FnMut($t) -> Option<$t>>(
+                storage     : &$atomic,
+                set_order   : Ordering,
+                fetch_order : Ordering,
+                f           : F
+                ) -> Result<$t, $t>
+            {
+                storage.fetch_update(set_order, fetch_order, f)
+            }
+
+            fn fetch_add(storage : &$atomic, val : $t, order : Ordering) -> $t
+            {
+                storage.fetch_add(val, order)
+            }
+
+            fn fetch_sub(storage : &$atomic, val : $t, order : Ordering) -> $t
+            {
+                storage.fetch_sub(val, order)
+            }
+
+            fn fetch_and(storage : &$atomic, val : $t, order : Ordering) -> $t
+            {
+                storage.fetch_and(val, order)
+            }
+
+            fn fetch_or(storage : &$atomic, val : $t, order : Ordering) -> $t
+            {
+                storage.fetch_or(val, order)
+            }
+
+            fn fetch_xor(storage : &$atomic, val : $t, order : Ordering) -> $t
+            {
+                storage.fetch_xor(val, order)
+            }
+
+            fn fetch_max(storage : &$atomic, val : $t, order : Ordering) -> $t
+            {
+                storage.fetch_max(val, order)
+            }
+
+            fn fetch_min(storage : &$atomic, val : $t, order : Ordering) -> $t
+            {
+                storage.fetch_min(val, order)
+            }
+
+            repr_arithmetic!($t);
+        }
+
+        impl $crate::Shared<$t>
+        {
+            //************************************************************************************
+            /// Returns the atomic holding the value while the object is shared, or None while the
+            /// value is stored in the object itself.  The atomic is the one every object sharing
+            /// the data uses.
+            pub fn as_atomic(&self) -> Option<&$atomic>
+            {
+                self.shared_storage()
+            }
+        }
+
+        impl From<$atomic> for $crate::Shared<$t>
+        {
+            //*************************************************************************************
+            /// Construct a new, unshared instance of the object from the value of an atomic.
+            fn from(
+                value : $atomic
+                ) -> $crate::Shared<$t>
+            {
+                $crate::Shared::new(value.into_inner())
+            }
+        }
+
+        impl From<$crate::Shared<$t>> for $atomic
+        {
+            //*************************************************************************************
+            /// Construct a new atomic holding the current value of the object.
+            fn from(
+                value : $crate::Shared<$t>
+                ) -> $atomic
+            {
+                $atomic::new(value.get())
+            }
+        }
+    }
+}
+
+//*************************************************************************************************
+/// Implements AtomicRepr for a primitive saved in a Mutex, used for the 64 bit types on targets
+/// without 64 bit atomics.  The orderings are ignored, the Mutex already orders the accesses,
+/// and a poisoned Mutex is used anyway since the value is always replaced as a whole.  Taking the
+/// lock panics inside a no block zone in debug builds, see no_block.  Mutex, PoisonError,
+/// Ordering and mem have to be in scope where it is used.
+#[cfg(all(feature = "std", target_pointer_width = "32"))]
+macro_rules! locked {
+    ($t:ty) => {
+        impl $crate::atomic_repr::sealed::Sealed for $t {}
+
+        impl $crate::AtomicRepr for $t
+        {
+            type Storage = Mutex<$t>;
+
+            const LOCK_FREE : bool = false;
+
+            fn new_storage(val : $t) -> Mutex<$t> { Mutex::new(val) }
+
+            fn get_mut(storage : &mut Mutex<$t>) -> &mut $t
+            {
+                storage.get_mut().unwrap_or_else(PoisonError::into_inner)
+            }
+
+            fn load(storage : &Mutex<$t>, _order : Ordering) -> $t
+            {
+                $crate::no_block::check();
+
+                *storage.lock().unwrap_or_else(PoisonError::into_inner)
+            }
+
+            fn store(storage : &Mutex<$t>, val : $t, _order : Ordering)
+            {
+                $crate::no_block::check();
+
+                *storage.lock().unwrap_or_else(PoisonError::into_inner) = val;
+            }
+
+            fn swap(storage : &Mutex<$t>, val : $t, _order : Ordering) -> $t
+            {
+                $crate::no_block::check();
+
+                let mut data = storage.lock().unwrap_or_else(PoisonError::into_inner);
+
+                mem::replace(&mut *data, val)
+            }
+
+            fn compare_exchange(
+                storage  : &Mutex<$t>,
+                current  : $t,
+                new      : $t,
+                _success : Ordering,
+                _failure : Ordering
+                ) -> Result<$t, $t>
+            {
+                $crate::no_block::check();
+
+                let mut data = storage.lock().unwrap_or_else(PoisonError::into_inner);
+                let old      = *data;
+
+                if old != current
+                {
+                    return Err(old);
+                }
+
+                *data = new;
+
+                Ok(old)
+            }
+
+            fn fetch_update<F : FnMut($t) -> Option<$t>>(
+                storage      : &Mutex<$t>,
+                _set_order   : Ordering,
+                _fetch_order : Ordering,
+                mut f        : F
+                ) -> Result<$t, $t>
+            {
+                $crate::no_block::check();
+
+                let mut data = storage.lock().unwrap_or_else(PoisonError::into_inner);
+                let old      = *data;
+
+                match f(old)
+                {
+                    Some(new) => {
+                        *data = new;
+
+                        Ok(old)
+                    },
+                    None      => Err(old)
+                }
+            }
+
+            fn fetch_add(storage : &Mutex<$t>, val : $t, _order : Ordering) -> $t
+            {
+                $crate::no_block::check();
+
+                let mut data = storage.lock().unwrap_or_else(PoisonError::into_inner);
+                let old      = *data;
+
+                *data = old.wrapping_add(val);
+
+                old
+            }
+
+            fn fetch_sub(storage : &Mutex<$t>, val : $t, _order : Ordering) -> $t
+            {
+                $crate::no_block::check();
+
+                let mut data = storage.lock().unwrap_or_else(PoisonError::into_inner);
+                let old      = *data;
+
+                *data = old.wrapping_sub(val);
+
+                old
+            }
+
+            fn fetch_and(storage : &Mutex<$t>, val : $t, _order : Ordering) -> $t
+            {
+                $crate::no_block::check();
+
+                let mut data = storage.lock().unwrap_or_else(PoisonError::into_inner);
+                let old      = *data;
+
+                *data = old & val;
+
+                old
+            }
+
+            fn fetch_or(storage : &Mutex<$t>, val : $t, _order : Ordering) -> $t
+            {
+                $crate::no_block::check();
+
+                let mut data = storage.lock().unwrap_or_else(PoisonError::into_inner);
+                let old      = *data;
+
+                *data = old | val;
+
+                old
+            }
+
+            fn fetch_xor(storage : &Mutex<$t>, val : $t, _order : Ordering) -> $t
+            {
+                $crate::no_block::check();
+
+                let mut data = storage.lock().unwrap_or_else(PoisonError::into_inner);
+                let old      = *data;
+
+                *data = old ^ val;
+
+                old
+            }
+
+            fn fetch_max(storage : &Mutex<$t>, val : $t, _order : Ordering) -> $t
+            {
+                $crate::no_block::check();
+
+                let mut data = storage.lock().unwrap_or_else(PoisonError::into_inner);
+                let old      = *data;
+
+                *data = old.max(val);
+
+                old
+            }
+
+            fn fetch_min(storage : &Mutex<$t>, val : $t, _order : Ordering) -> $t
+            {
+                $crate::no_block::check();
+
+                let mut data = storage.lock().unwrap_or_else(PoisonError::into_inner);
+                let old      = *data;
+
+                *data = old.min(val);
+
+                old
+            }
+
+            repr_arithmetic!($t);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+//*************************************************************************************************
+/// Maximum number of changes kept in an audit log, the oldest change is dropped when a new one
+/// is recorded and the log is full.
+pub const AUDIT_CAPACITY : usize = 256;
+
+//*************************************************************************************************
+/// A change made to a SharedObject by set(), recorded when the audit feature is enabled.
+#[derive(Debug)]
+pub struct Change<T : ?Sized>
+{
+    //---------------------------------------------------------------------------------------------
+    /// When the value was set.
+    pub timestamp : SystemTime,
+
+    //---------------------------------------------------------------------------------------------
+    /// The value before the change.
+    pub old : Arc<T>,
+
+    //---------------------------------------------------------------------------------------------
+    /// The value after the change.
+    pub new : Arc<T>
+}
+
+impl<T : ?Sized> Clone for Change<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Clone.
+    fn clone(&self) -> Change<T>
+    {
+        Change {
+            timestamp : self.timestamp,
+            old       : self.old.clone(),
+            new       : self.new.clone()
+        }
+    }
+}
+
+//*************************************************************************************************
+/// Log of the changes made to a SharedObject, shared by all the instances sharing the data.  The
+/// log isn't allocated until the first change is recorded or the object is shared.
+pub(crate) struct AuditLog<T : ?Sized>
+{
+    //---------------------------------------------------------------------------------------------
+    /// The recorded changes, oldest first.
+    entries : OnceLock<Arc<Mutex<VecDeque<Change<T>>>>>
+}
+
+impl<T : ?Sized> AuditLog<T>
+{
+    //********************************************************************************************
+    /// Construct a new empty log.
+    pub(crate) fn new() -> AuditLog<T>
+    {
+        AuditLog {
+            entries : OnceLock::new()
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns the log to use for an object sharing the data with this one.
+    pub(crate) fn share(&self) -> AuditLog<T>
+    {
+        AuditLog {
+            entries : OnceLock::from(self.storage().clone())
+        }
+    }
+
+    //********************************************************************************************
+    /// Allocates the storage of the log now instead of when it is first needed.
+    pub(crate) fn prepare(&self)
+    {
+        self.storage();
+    }
+
+    //********************************************************************************************
+    /// Records a change.
+    pub(crate) fn record(
+        &self,
+        old : Arc<T>,
+        new : Arc<T>
+        )
+    {
+        let mut entries = self.storage().lock().unwrap();
+
+        if entries.len() == AUDIT_CAPACITY
+        {
+            entries.pop_front();
+        }
+
+        entries.push_back(Change { timestamp : SystemTime::now(), old, new });
+    }
+
+    //********************************************************************************************
+    /// Returns the recorded changes, oldest first.
+    pub(crate) fn entries(&self) -> Vec<Change<T>>
+    {
+        match self.entries.get()
+        {
+            Some(entries) => entries.lock().unwrap().iter().cloned().collect(),
+            None          => Vec::new()
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns the storage of the log, allocating it if needed.
+    fn storage(&self) -> &Arc<Mutex<VecDeque<Change<T>>>>
+    {
+        self.entries.get_or_init(|| Arc::new(Mutex::new(VecDeque::new())))
+    }
+}
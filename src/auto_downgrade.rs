@@ -0,0 +1,84 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//*************************************************************************************************
+/// Number of calls between the checks for unique ownership.
+pub(crate) const CHECK_INTERVAL : u32 = 64;
+
+//*************************************************************************************************
+/// Counts the calls made in auto downgrade mode, so the numeric types only check whether they
+/// can leave the shared storage every CHECK_INTERVAL calls.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct AutoDowngrade(Option<u32>);
+
+impl AutoDowngrade
+{
+    //********************************************************************************************
+    /// Construct a new instance of the object with the mode off.
+    pub(crate) fn off() -> AutoDowngrade
+    {
+        AutoDowngrade(None)
+    }
+
+    //********************************************************************************************
+    /// Turns the mode on or off, turning it on restarts the count.
+    pub(crate) fn enable(
+        &mut self,
+        on : bool
+        )
+    {
+        self.0 = if on { Some(0) } else { None };
+    }
+
+    //********************************************************************************************
+    /// Counts a call, returns true if it is time to check for unique ownership.
+    pub(crate) fn tick(&mut self) -> bool
+    {
+        match self.0
+        {
+            Some(ref mut count) => {
+                *count += 1;
+
+                if *count < CHECK_INTERVAL
+                {
+                    return false;
+                }
+
+                *count = 0;
+
+                true
+            },
+            None                => false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    //*********************************************************************************************
+    /// Test that tick() only returns true every CHECK_INTERVAL calls while the mode is on.
+    #[test]
+    fn test_tick()
+    {
+        let mut test = super::AutoDowngrade::off();
+
+        assert!((0..super::CHECK_INTERVAL * 2).all(|_| !test.tick()));
+
+        test.enable(true);
+
+        let checks = (0..super::CHECK_INTERVAL * 3).filter(|_| test.tick()).count();
+
+        assert_eq!(checks, 3);
+
+        test.enable(false);
+
+        assert!((0..super::CHECK_INTERVAL * 2).all(|_| !test.tick()));
+    }
+}
@@ -0,0 +1,219 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+use std::cell::Cell;
+
+use {SharedF32, SharedF64, SharedI8, SharedI16, SharedI32, SharedI64, SharedIsize};
+use {SharedU8, SharedU16, SharedU32, SharedU64, SharedUsize};
+
+//*************************************************************************************************
+/// Shareable elements whose value can be cached by Cached.
+pub trait Cacheable
+{
+    //*********************************************************************************************
+    /// The type of the value.
+    type Value : Copy;
+
+    //*********************************************************************************************
+    /// Reads the current value from the element.
+    fn load(&self) -> Self::Value;
+}
+
+macro_rules! cacheable {
+    ($($name:ident => $t:ty),*) => {$(
+        impl Cacheable for $name
+        {
+            type Value = $t;
+
+            fn load(&self) -> $t { self.get() }
+        }
+    )*}
+}
+
+cacheable!(
+    SharedF32 => f32, SharedF64 => f64,
+    SharedI8 => i8, SharedI16 => i16, SharedI32 => i32, SharedI64 => i64, SharedIsize => isize,
+    SharedU8 => u8, SharedU16 => u16, SharedU32 => u32, SharedU64 => u64, SharedUsize => usize
+);
+
+//*************************************************************************************************
+/// Local copy of the value of a shareable element.
+///
+/// get() returns the copy without touching the shared data, the copy is only updated when
+/// refresh() is called, or every N reads if the object was created with with_interval().  This
+/// trades staleness for speed in hot loops reading a value that rarely changes.
+///
+/// The object is meant to be used by a single thread, it can be sent to another thread but not
+/// shared between threads.
+///
+/// # Examples
+///
+/// ```
+/// use shareable::{Cached, SharedU64};
+///
+/// let mut limit = SharedU64::new(10);
+/// let cached    = Cached::new(limit.dup());
+///
+/// limit.set(20);
+/// assert_eq!(cached.get(), 10);
+///
+/// cached.refresh();
+/// assert_eq!(cached.get(), 20);
+/// ```
+pub struct Cached<S : Cacheable>
+{
+    //---------------------------------------------------------------------------------------------
+    /// The element being cached.
+    source : S,
+
+    //---------------------------------------------------------------------------------------------
+    /// The local copy of the value.
+    value : Cell<S::Value>,
+
+    //---------------------------------------------------------------------------------------------
+    /// Number of reads between automatic refreshes, 0 if the value is only refreshed manually.
+    interval : usize,
+
+    //---------------------------------------------------------------------------------------------
+    /// Number of reads since the last refresh.
+    reads : Cell<usize>
+}
+
+impl<S : Cacheable> Cached<S>
+{
+    //********************************************************************************************
+    /// Construct a new instance of the object, the value is only refreshed by refresh().
+    pub fn new(
+        source : S
+        ) -> Cached<S>
+    {
+        Cached::with_interval(source, 0)
+    }
+
+    //********************************************************************************************
+    /// Construct a new instance of the object that also refreshes the value every interval
+    /// reads.  An interval of 0 disables the automatic refresh.
+    pub fn with_interval(
+        source   : S,
+        interval : usize
+        ) -> Cached<S>
+    {
+        Cached {
+            value : Cell::new(source.load()),
+            source,
+            interval,
+            reads : Cell::new(0)
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns the cached value.
+    pub fn get(&self) -> S::Value
+    {
+        if self.interval != 0
+        {
+            let reads = self.reads.get() + 1;
+
+            if reads >= self.interval
+            {
+                self.reads.set(0);
+
+                return self.refresh();
+            }
+
+            self.reads.set(reads);
+        }
+
+        self.value.get()
+    }
+
+    //********************************************************************************************
+    /// Reads the value from the element and saves it in the cache, returns the new value.
+    pub fn refresh(&self) -> S::Value
+    {
+        let value = self.source.load();
+
+        self.value.set(value);
+
+        value
+    }
+
+    //********************************************************************************************
+    /// Returns the element being cached.
+    pub fn source(&self) -> &S
+    {
+        &self.source
+    }
+
+    //********************************************************************************************
+    /// Consumes the object and returns the element being cached.
+    pub fn into_inner(self) -> S
+    {
+        self.source
+    }
+}
+
+use std::fmt::{Debug, Formatter, Error};
+
+impl<S : Cacheable> Debug for Cached<S>
+    where S::Value : Debug
+{
+    //*********************************************************************************************
+    /// Implementation of Debug.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{:?}", self.value.get())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use SharedU64;
+
+    //*********************************************************************************************
+    /// Test that get() returns the stale value until refresh() is called.
+    #[test]
+    fn test_refresh()
+    {
+        let mut test = SharedU64::new(1);
+        let cached   = super::Cached::new(test.dup());
+
+        test.set(2);
+
+        assert_eq!(cached.get(), 1);
+        assert_eq!(cached.get(), 1);
+        assert_eq!(cached.refresh(), 2);
+        assert_eq!(cached.get(), 2);
+    }
+
+    //*********************************************************************************************
+    /// Test that the value is refreshed every interval reads.
+    #[test]
+    fn test_interval()
+    {
+        let mut test = SharedU64::new(1);
+        let cached   = super::Cached::with_interval(test.dup(), 3);
+
+        test.set(2);
+
+        assert_eq!(cached.get(), 1);
+        assert_eq!(cached.get(), 1);
+        assert_eq!(cached.get(), 2);
+
+        test.set(3);
+
+        assert_eq!(cached.get(), 2);
+        assert_eq!(cached.get(), 2);
+        assert_eq!(cached.get(), 3);
+        assert_eq!(cached.into_inner().get(), 3);
+    }
+}
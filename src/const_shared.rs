@@ -0,0 +1,255 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+use core::sync::atomic::{AtomicBool, AtomicI8, AtomicI16, AtomicI32, AtomicIsize};
+use core::sync::atomic::{AtomicU8, AtomicU16, AtomicU32, AtomicUsize, Ordering};
+#[cfg(target_has_atomic = "64")]
+use core::sync::atomic::{AtomicI64, AtomicU64};
+
+mod sealed
+{
+    //*********************************************************************************************
+    /// Prevents ConstBackable from being implemented outside of the crate.
+    pub trait Sealed {}
+}
+
+//*************************************************************************************************
+/// Copy types that can be saved in a ConstShared, each in the atomic of its own size.
+///
+/// The trait is sealed, it is implemented for the primitive types and bool.  The 64 bit types
+/// are only available on targets with 64 bit atomics.
+pub trait ConstBackable : sealed::Sealed + Copy
+{
+    //*********************************************************************************************
+    /// The atomic the value is saved in.
+    #[doc(hidden)]
+    type Atomic;
+
+    //*********************************************************************************************
+    /// Loads the value from the atomic.
+    #[doc(hidden)]
+    fn load(atomic : &Self::Atomic) -> Self;
+
+    //*********************************************************************************************
+    /// Stores the value in the atomic.
+    #[doc(hidden)]
+    fn store(atomic : &Self::Atomic, val : Self);
+
+    //*********************************************************************************************
+    /// Stores the value in the atomic and returns the previous value.
+    #[doc(hidden)]
+    fn swap(atomic : &Self::Atomic, val : Self) -> Self;
+}
+
+macro_rules! const_backable {
+    ($($t:ty => $atomic:ty),*) => {$(
+        impl sealed::Sealed for $t {}
+
+        impl ConstBackable for $t
+        {
+            type Atomic = $atomic;
+
+            fn load(atomic : &$atomic) -> $t { atomic.load(Ordering::Relaxed) }
+            fn store(atomic : &$atomic, val : $t) { atomic.store(val, Ordering::Relaxed) }
+            fn swap(atomic : &$atomic, val : $t) -> $t { atomic.swap(val, Ordering::Relaxed) }
+        }
+
+        impl ConstShared<$t>
+        {
+            //*************************************************************************************
+            /// Construct a new instance of the object, usable to initialize a static.
+            pub const fn new(
+                value : $t
+                ) -> ConstShared<$t>
+            {
+                ConstShared {
+                    data : <$atomic>::new(value)
+                }
+            }
+        }
+    )*}
+}
+
+const_backable!(bool => AtomicBool, i8 => AtomicI8, i16 => AtomicI16, i32 => AtomicI32,
+                isize => AtomicIsize, u8 => AtomicU8, u16 => AtomicU16, u32 => AtomicU32,
+                usize => AtomicUsize);
+
+#[cfg(target_has_atomic = "64")]
+const_backable!(i64 => AtomicI64, u64 => AtomicU64);
+
+macro_rules! const_backable_float {
+    ($($t:ty => $atomic:ty),*) => {$(
+        impl sealed::Sealed for $t {}
+
+        impl ConstBackable for $t
+        {
+            type Atomic = $atomic;
+
+            fn load(atomic : &$atomic) -> $t { <$t>::from_bits(atomic.load(Ordering::Relaxed)) }
+            fn store(atomic : &$atomic, val : $t)
+            {
+                atomic.store(val.to_bits(), Ordering::Relaxed)
+            }
+            fn swap(atomic : &$atomic, val : $t) -> $t
+            {
+                <$t>::from_bits(atomic.swap(val.to_bits(), Ordering::Relaxed))
+            }
+        }
+
+        impl ConstShared<$t>
+        {
+            //*************************************************************************************
+            /// Construct a new instance of the object, usable to initialize a static.
+            pub const fn new(
+                value : $t
+                ) -> ConstShared<$t>
+            {
+                ConstShared {
+                    data : <$atomic>::new(value.to_bits())
+                }
+            }
+        }
+    )*}
+}
+
+const_backable_float!(f32 => AtomicU32);
+
+#[cfg(target_has_atomic = "64")]
+const_backable_float!(f64 => AtomicU64);
+
+//*************************************************************************************************
+/// Shareable value for statics, always saved in an atomic sized for the value.
+///
+/// Unlike the other shareable elements there is no unshared state that has to be moved into an
+/// Arc.  new() is a const fn and the object is Sync, so it can be a static that every thread reads
+/// and sets directly, like global configuration.  Like the other shareable elements the accesses
+/// are Relaxed.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use shareable::ConstShared;
+///
+/// static VERBOSE : ConstShared<bool> = ConstShared::<bool>::new(false);
+///
+/// thread::spawn(|| VERBOSE.set(true)).join().unwrap();
+///
+/// assert!(VERBOSE.get());
+/// ```
+pub struct ConstShared<T : ConstBackable>
+{
+    //---------------------------------------------------------------------------------------------
+    /// The atomic holding the value.
+    data : T::Atomic
+}
+
+impl<T : ConstBackable> ConstShared<T>
+{
+    //********************************************************************************************
+    /// Returns the value of the object.
+    pub fn get(&self) -> T
+    {
+        T::load(&self.data)
+    }
+
+    //********************************************************************************************
+    /// Set the value of the object.  This only needs a shared reference, so a static can be set.
+    pub fn set(
+        &self,
+        val : T
+        )
+    {
+        T::store(&self.data, val);
+    }
+
+    //********************************************************************************************
+    /// Set the value and return the previous value in a single operation.
+    pub fn swap(
+        &self,
+        val : T
+        ) -> T
+    {
+        T::swap(&self.data, val)
+    }
+}
+
+use core::fmt::{Debug, Display, Formatter, Error};
+
+impl<T : ConstBackable + Debug> Debug for ConstShared<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Debug.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{:?}", self.get())
+    }
+}
+
+impl<T : ConstBackable + Display> Display for ConstShared<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Display.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{}", self.get())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::thread;
+    use super::ConstShared;
+
+    //*********************************************************************************************
+    /// Shared by the threads in test_static().
+    static CONFIG : ConstShared<u32> = ConstShared::<u32>::new(7);
+
+    //*********************************************************************************************
+    /// Test that a static can be set from several threads.
+    #[test]
+    fn test_static()
+    {
+        assert_eq!(CONFIG.get(), 7);
+
+        let threads : Vec<_> = (0..4).map(|i| thread::spawn(move || {
+            for j in 0..100
+            {
+                CONFIG.set(i * 100 + j);
+            }
+        })).collect();
+
+        for thread in threads
+        {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(CONFIG.get() % 100, 99);
+        assert!(CONFIG.swap(5) < 400);
+        assert_eq!(CONFIG.get(), 5);
+    }
+
+    //*********************************************************************************************
+    /// Test the floats, which are saved as their bits.
+    #[test]
+    fn test_float()
+    {
+        let test = ConstShared::<f32>::new(1.5);
+
+        assert_eq!(test.swap(-2.5), 1.5);
+        assert_eq!(test.get(), -2.5);
+        assert_eq!(format!("{:?} {}", test, test), "-2.5 -2.5");
+    }
+}
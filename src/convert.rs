@@ -0,0 +1,49 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+use core::convert::TryFrom;
+
+//*************************************************************************************************
+/// Conversion between integer types that clamps the value to the range of the target type
+/// instead of failing or wrapping.
+pub trait SaturatingFrom<T>
+{
+    //---------------------------------------------------------------------------------------------
+    /// Converts the value, returning the target's MIN or MAX if the value is out of range.
+    fn saturating_from(val : T) -> Self;
+}
+
+macro_rules! saturating_from {
+    ($to:ty, $($from:ty),*) => {
+        $(
+            impl SaturatingFrom<$from> for $to
+            {
+                fn saturating_from(val : $from) -> $to
+                {
+                    match <$to>::try_from(val)
+                    {
+                        Ok(val)                     => val,
+                        Err(_) if (val as i128) < 0 => <$to>::MIN,
+                        Err(_)                      => <$to>::MAX
+                    }
+                }
+            }
+        )*
+    }
+}
+
+saturating_from!(i8, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+saturating_from!(i16, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+saturating_from!(i32, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+saturating_from!(i64, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+saturating_from!(isize, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+saturating_from!(u8, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+saturating_from!(u16, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+saturating_from!(u32, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+saturating_from!(u64, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+saturating_from!(usize, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
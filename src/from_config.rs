@@ -0,0 +1,149 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+use std::collections::HashMap;
+use std::error;
+use std::fmt::{Display, Formatter, Error};
+use std::str::FromStr;
+
+//*************************************************************************************************
+/// Builds a struct from the string values in a config map, normally implemented with
+/// `#[derive(FromConfig)]` from the derive feature.
+///
+/// The derived implementation looks up each field by its name and parses the value with the
+/// field's FromStr, so fields can be any of the Shared types, SharedObject<T> where T is FromStr,
+/// or plain types like String.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "derive")]
+/// # {
+/// use std::collections::HashMap;
+/// use shareable::{FromConfig, SharedF64, SharedObject, SharedU32};
+///
+/// #[derive(FromConfig)]
+/// struct Settings
+/// {
+///     threads : SharedU32,
+///     ratio   : SharedF64,
+///     name    : SharedObject<String>
+/// }
+///
+/// let mut config = HashMap::new();
+///
+/// config.insert(String::from("threads"), String::from("4"));
+/// config.insert(String::from("ratio"), String::from("0.5"));
+/// config.insert(String::from("name"), String::from("main"));
+///
+/// let settings = Settings::from_config(&config).unwrap();
+///
+/// assert_eq!(settings.threads.get(), 4);
+/// assert_eq!(settings.ratio.get(), 0.5);
+/// assert_eq!(*settings.name.get(), "main");
+/// # }
+/// ```
+pub trait FromConfig : Sized
+{
+    //********************************************************************************************
+    /// Builds the object from the config.
+    fn from_config(config : &HashMap<String, String>) -> Result<Self, ConfigError>;
+}
+
+//*************************************************************************************************
+/// Error returned by FromConfig::from_config().
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConfigError
+{
+    //---------------------------------------------------------------------------------------------
+    /// The config has no value for the key.
+    Missing(String),
+
+    //---------------------------------------------------------------------------------------------
+    /// The value for the key couldn't be parsed.
+    Invalid
+    {
+        //-----------------------------------------------------------------------------------------
+        /// The key of the value.
+        key   : String,
+
+        //-----------------------------------------------------------------------------------------
+        /// The value that couldn't be parsed.
+        value : String
+    }
+}
+
+impl Display for ConfigError
+{
+    //*********************************************************************************************
+    /// Implementation of Display.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        match *self
+        {
+            ConfigError::Missing(ref key) => write!(f, "missing config value {}", key),
+            ConfigError::Invalid { ref key, ref value } => {
+                write!(f, "invalid config value {} for {}", value, key)
+            }
+        }
+    }
+}
+
+impl error::Error for ConfigError {}
+
+//*************************************************************************************************
+/// Looks up and parses one value, used by the code generated by `#[derive(FromConfig)]`.
+#[doc(hidden)]
+pub fn parse_config_value<T : FromStr>(
+    config : &HashMap<String, String>,
+    key    : &str
+    ) -> Result<T, ConfigError>
+{
+    let value = match config.get(key)
+    {
+        Some(value) => value,
+        None        => return Err(ConfigError::Missing(String::from(key)))
+    };
+
+    value.parse().map_err(|_| ConfigError::Invalid {
+        key   : String::from(key),
+        value : value.clone()
+    })
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::collections::HashMap;
+    use super::ConfigError;
+    use SharedU32;
+
+    //*********************************************************************************************
+    /// Test the errors returned by parse_config_value().
+    #[test]
+    fn test_parse_config_value()
+    {
+        let mut config = HashMap::new();
+
+        config.insert(String::from("good"), String::from("5"));
+        config.insert(String::from("bad"), String::from("x"));
+
+        assert_eq!(super::parse_config_value::<SharedU32>(&config, "good").unwrap().get(), 5);
+        assert_eq!(
+            super::parse_config_value::<SharedU32>(&config, "bad").unwrap_err(),
+            ConfigError::Invalid { key : String::from("bad"), value : String::from("x") }
+            );
+        assert_eq!(
+            super::parse_config_value::<SharedU32>(&config, "none").unwrap_err(),
+            ConfigError::Missing(String::from("none"))
+            );
+    }
+}
@@ -0,0 +1,159 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+use core::cmp::Ordering;
+
+//*************************************************************************************************
+/// Frozen copy of the value of a SharedF64.
+///
+/// The value never changes, so unlike SharedF64 it can be compared and sorted.  The comparisons
+/// use the total ordering of f64::total_cmp(), -0.0 sorts before +0.0 and NaN sorts after
+/// infinity (or before negative infinity if the sign bit is set).  Two NaN values are only equal
+/// if they have the same bits.
+///
+/// # Examples
+///
+/// ```
+/// use shareable::SharedF64;
+///
+/// let mut values = vec![
+///     SharedF64::new(2.0).freeze(),
+///     SharedF64::new(f64::NAN).freeze(),
+///     SharedF64::new(-1.0).freeze()
+/// ];
+///
+/// values.sort();
+///
+/// assert_eq!(values[0].get(), -1.0);
+/// assert_eq!(values[1].get(), 2.0);
+/// assert!(values[2].get().is_nan());
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct FrozenSharedF64
+{
+    //---------------------------------------------------------------------------------------------
+    /// The frozen value.
+    value : f64
+}
+
+impl FrozenSharedF64
+{
+    //********************************************************************************************
+    /// Construct a new instance of the object.
+    pub fn new(
+        value : f64
+        ) -> FrozenSharedF64
+    {
+        FrozenSharedF64 { value }
+    }
+
+    //********************************************************************************************
+    /// Returns the frozen value.
+    pub fn get(&self) -> f64
+    {
+        self.value
+    }
+}
+
+impl PartialEq for FrozenSharedF64
+{
+    //*********************************************************************************************
+    /// Implementation of PartialEq using the total ordering.
+    fn eq(
+        &self,
+        other : &FrozenSharedF64
+        ) -> bool
+    {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for FrozenSharedF64 {}
+
+impl PartialOrd for FrozenSharedF64
+{
+    //*********************************************************************************************
+    /// Implementation of PartialOrd using the total ordering.
+    fn partial_cmp(
+        &self,
+        other : &FrozenSharedF64
+        ) -> Option<Ordering>
+    {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for FrozenSharedF64
+{
+    //*********************************************************************************************
+    /// Implementation of Ord using f64::total_cmp().
+    fn cmp(
+        &self,
+        other : &FrozenSharedF64
+        ) -> Ordering
+    {
+        self.value.total_cmp(&other.value)
+    }
+}
+
+use core::fmt::{Display, Formatter, Error};
+
+impl Display for FrozenSharedF64
+{
+    //*********************************************************************************************
+    /// Implementation of Display.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{}", self.value)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::FrozenSharedF64;
+
+    //*********************************************************************************************
+    /// Test the ordering of the special values.
+    #[test]
+    fn test_ordering()
+    {
+        let mut values = [
+            FrozenSharedF64::new(f64::NAN),
+            FrozenSharedF64::new(0.0),
+            FrozenSharedF64::new(f64::INFINITY),
+            FrozenSharedF64::new(-f64::NAN),
+            FrozenSharedF64::new(-0.0),
+            FrozenSharedF64::new(f64::NEG_INFINITY),
+            FrozenSharedF64::new(1.5)
+        ];
+
+        values.sort();
+
+        assert!(values[0].get().is_nan() && values[0].get().is_sign_negative());
+        assert_eq!(values[1].get(), f64::NEG_INFINITY);
+        assert!(values[2].get() == 0.0 && values[2].get().is_sign_negative());
+        assert!(values[3].get() == 0.0 && values[3].get().is_sign_positive());
+        assert_eq!(values[4].get(), 1.5);
+        assert_eq!(values[5].get(), f64::INFINITY);
+        assert!(values[6].get().is_nan() && values[6].get().is_sign_positive());
+    }
+
+    //*********************************************************************************************
+    /// Test equality follows the total ordering.
+    #[test]
+    fn test_eq()
+    {
+        assert_eq!(FrozenSharedF64::new(f64::NAN), FrozenSharedF64::new(f64::NAN));
+        assert_ne!(FrozenSharedF64::new(0.0), FrozenSharedF64::new(-0.0));
+        assert_eq!(FrozenSharedF64::new(2.5), FrozenSharedF64::new(2.5));
+    }
+}
@@ -32,6 +32,8 @@
 //! ```
 //!
 //! ```
+//! # #[cfg(feature = "std")]
+//! # {
 //! use std::sync::mpsc;
 //! use std::thread;
 //! use shareable::SharedObject;
@@ -53,49 +55,197 @@
 //!
 //! tx.send(());
 //! thread.join().unwrap();
+//! # }
 //! ```
+//!
+//! # Sharing vs snapshots
+//!
+//! There are two ways to get a second object from an existing one:
+//!
+//! * `dup()` / `share()` / `clone()` link the two objects, setting the value on one is seen by
+//!   the other.  This is what enables the synchronization.  `clone()` works through a shared
+//!   reference, the others need the object to be mutable.
+//! * `snapshot()` returns an independent object holding the current value, setting the value on
+//!   one is not seen by the other.  No synchronization is enabled.
+//!
+//! ```
+//! use shareable::SharedU32;
+//!
+//! let mut value1 = SharedU32::new(1);
+//! let value2     = value1.share();
+//! let value3     = value1.snapshot();
+//!
+//! value1.set(2);
+//!
+//! assert_eq!(value2.get(), 2);
+//! assert_eq!(value3.get(), 1);
+//! ```
+//!
+//! # no_std
+//!
+//! The std feature is on by default.  Without it the crate is no_std and only needs an allocator,
+//! through the alloc crate.  The types available without std are the ones backed by atomics:
+//!
+//! * `Shared<T>` and SharedI8, SharedI16, SharedI32, SharedIsize, SharedU8, SharedU16, SharedU32
+//!   and SharedUsize.
+//! * SharedI64, SharedU64 and SharedF64 on 64 bit targets.  On 32 bit targets they use a Mutex, so
+//!   they need std.
+//! * SharedBool, SharedF32 and ConstShared.
+//! * FrozenSharedF64, Overflow, Poisoned, SaturatingFrom and UpdateResult.
+//!
+//! The other types, SharedObject among them, need std.  So do the audit, derive, read_preferring,
+//! strict-ordering, test-hooks and thread-tracking features.
+//!
+//! ```toml
+//! [dependencies]
+//! shareable = { version = "0.0.1", default-features = false }
+//! ```
+#![cfg_attr(all(not(feature = "std"), not(test)), no_std)]
+
+extern crate alloc;
+#[cfg(any(feature = "std", test))]
+extern crate core;
+extern crate once_cell;
+#[cfg(feature = "read_preferring")]
+extern crate parking_lot;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "derive")]
+extern crate shareable_derive;
+
+#[macro_use]
+mod atomic_repr;
+#[cfg(feature = "audit")]
+mod audit;
+mod auto_downgrade;
+#[cfg(feature = "std")]
+mod cached;
+mod const_shared;
+mod convert;
+#[cfg(feature = "std")]
+mod from_config;
+mod frozen_shared_f64;
+#[cfg(feature = "std")]
+mod local_object;
+#[cfg(feature = "std")]
+pub mod no_block;
+mod overflow;
+mod poisoned;
+mod promoted;
+#[cfg(feature = "read_preferring")]
+mod read_preferring_object;
+mod scope_guard;
+mod shared;
+#[cfg(feature = "std")]
+mod shared_append_log;
+#[cfg(all(feature = "std", target_has_atomic = "64"))]
+mod shared_atomic_object;
+#[cfg(all(feature = "std", target_has_atomic = "64"))]
+mod shared_bitset64;
+mod shared_bool;
+#[cfg(feature = "std")]
+mod shared_counter_set;
 mod shared_f32;
-#[cfg(target_pointer_width = "32")]
+#[cfg(all(feature = "std", target_pointer_width = "32"))]
 mod shared_f64_x32;
 #[cfg(not(target_pointer_width = "32"))]
 mod shared_f64_x64;
 mod shared_i8;
 mod shared_i16;
 mod shared_i32;
-#[cfg(target_pointer_width = "32")]
+#[cfg(all(feature = "std", target_pointer_width = "32"))]
 mod shared_i64_x32;
 #[cfg(not(target_pointer_width = "32"))]
 mod shared_i64_x64;
 mod shared_isize;
+#[cfg(feature = "std")]
 mod shared_object;
+#[cfg(feature = "std")]
+mod shared_peak;
+#[cfg(feature = "std")]
+mod shared_stamped;
+#[cfg(feature = "std")]
+mod shared_sum;
 mod shared_u8;
 mod shared_u16;
 mod shared_u32;
-#[cfg(target_pointer_width = "32")]
+#[cfg(all(feature = "std", target_has_atomic = "64"))]
+mod shared_u64_ref;
+#[cfg(all(feature = "std", target_pointer_width = "32"))]
 mod shared_u64_x32;
 #[cfg(not(target_pointer_width = "32"))]
 mod shared_u64_x64;
 mod shared_usize;
+#[cfg(all(feature = "strict-ordering", debug_assertions))]
+mod strict_ordering;
+#[cfg(feature = "test-hooks")]
+pub mod test_hooks;
+#[cfg(not(feature = "test-hooks"))]
+mod test_hooks;
+#[cfg(feature = "thread-tracking")]
+mod thread_tracking;
+mod update_result;
 
+pub use atomic_repr::AtomicRepr;
+#[cfg(feature = "audit")]
+pub use audit::{Change, AUDIT_CAPACITY};
+#[cfg(feature = "std")]
+pub use cached::{Cacheable, Cached};
+pub use const_shared::{ConstBackable, ConstShared};
+pub use convert::SaturatingFrom;
+#[cfg(feature = "std")]
+pub use from_config::{ConfigError, FromConfig};
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use from_config::parse_config_value;
+pub use frozen_shared_f64::FrozenSharedF64;
+#[cfg(feature = "derive")]
+pub use shareable_derive::{FromConfig, Shareable};
+#[cfg(feature = "std")]
+pub use local_object::LocalObject;
+pub use overflow::Overflow;
+pub use poisoned::Poisoned;
+#[cfg(feature = "read_preferring")]
+pub use read_preferring_object::ReadPreferringObject;
+pub use shared::Shared;
+#[cfg(feature = "std")]
+pub use shared_append_log::SharedAppendLog;
+#[cfg(all(feature = "std", target_has_atomic = "64"))]
+pub use shared_atomic_object::{AtomicBackable, SharedAtomicObject};
+#[cfg(all(feature = "std", target_has_atomic = "64"))]
+pub use shared_bitset64::SharedBitset64;
+pub use shared_bool::SharedBool;
+#[cfg(feature = "std")]
+pub use shared_counter_set::{CounterSetGuard, SharedCounterSet};
 pub use shared_f32::SharedF32;
-#[cfg(target_pointer_width = "32")]
+#[cfg(all(feature = "std", target_pointer_width = "32"))]
 pub use shared_f64_x32::SharedF64;
 #[cfg(not(target_pointer_width = "32"))]
 pub use shared_f64_x64::SharedF64;
 pub use shared_i8::SharedI8;
 pub use shared_i16::SharedI16;
-pub use shared_i32::SharedI32;
-#[cfg(target_pointer_width = "32")]
+pub use shared_i32::{BoundedSharedI32, SharedI32};
+#[cfg(all(feature = "std", target_pointer_width = "32"))]
 pub use shared_i64_x32::SharedI64;
 #[cfg(not(target_pointer_width = "32"))]
 pub use shared_i64_x64::SharedI64;
 pub use shared_isize::SharedIsize;
-pub use shared_object::SharedObject;
+#[cfg(feature = "std")]
+pub use shared_object::{SharedObject, SharedReader};
+#[cfg(feature = "std")]
+pub use shared_peak::SharedPeak;
+#[cfg(feature = "std")]
+pub use shared_stamped::SharedStamped;
+#[cfg(feature = "std")]
+pub use shared_sum::SharedSum;
 pub use shared_u8::SharedU8;
 pub use shared_u16::SharedU16;
 pub use shared_u32::SharedU32;
-#[cfg(target_pointer_width = "32")]
+#[cfg(all(feature = "std", target_has_atomic = "64"))]
+pub use shared_u64_ref::SharedU64Ref;
+#[cfg(all(feature = "std", target_pointer_width = "32"))]
 pub use shared_u64_x32::SharedU64;
 #[cfg(not(target_pointer_width = "32"))]
 pub use shared_u64_x64::SharedU64;
 pub use shared_usize::SharedUsize;
+pub use update_result::UpdateResult;
@@ -0,0 +1,180 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+use std::cell::RefCell;
+use std::rc::Rc;
+
+//*************************************************************************************************
+/// Internal data structure that identifies how we are accessing the data.
+enum Data<T>
+{
+    //---------------------------------------------------------------------------------------------
+    /// There is only 1 instance of the element.
+    Single(Rc<T>),
+
+    //---------------------------------------------------------------------------------------------
+    /// There are or were multiple instances of the element.
+    Multiple(Rc<RefCell<Rc<T>>>)
+}
+
+//*************************************************************************************************
+/// Object data element that can only be shared on the current thread.
+///
+/// This is the single threaded version of SharedObject.  dup_local() links the objects through an
+/// Rc and a RefCell instead of an Arc and a Mutex, so sharing the data between several objects on
+/// the same thread costs nothing more than a reference count.  The objects are not Send, the
+/// compiler won't let them be moved to another thread; use SharedObject when the data needs to
+/// cross threads.
+///
+/// A SharedObject can't be linked with a LocalObject, doing so would make every SharedObject
+/// unsafe to send to another thread.
+///
+/// # Examples
+///
+/// ```
+/// use shareable::LocalObject;
+///
+/// let mut value1 = LocalObject::new(String::from("abc"));
+/// let value2     = value1.dup_local();
+///
+/// value1.set(String::from("xyz"));
+///
+/// assert_eq!(*value2.get(), "xyz");
+/// ```
+pub struct LocalObject<T>
+{
+    //---------------------------------------------------------------------------------------------
+    /// The internal data element.
+    data : Data<T>
+}
+
+impl<T> LocalObject<T>
+{
+    //********************************************************************************************
+    /// Construct a new instance of the object.
+    pub fn new(
+        value : T
+        ) -> LocalObject<T>
+    {
+        LocalObject {
+            data : Data::Single(Rc::new(value))
+        }
+    }
+
+    //********************************************************************************************
+    /// Set the value of the object.
+    pub fn set(
+        &mut self,
+        val : T
+        )
+    {
+        match self.data
+        {
+            Data::Single(_)         => self.data = Data::Single(Rc::new(val)),
+            Data::Multiple(ref mem) => *mem.borrow_mut() = Rc::new(val)
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns the value of the object.
+    pub fn get(&self) -> Rc<T>
+    {
+        match self.data
+        {
+            Data::Single(ref val)   => val.clone(),
+            Data::Multiple(ref mem) => mem.borrow().clone()
+        }
+    }
+
+    //********************************************************************************************
+    /// Clones the object for use on the current thread.  After this call all access to the data
+    /// will be done via a shared RefCell element.
+    pub fn dup_local(&mut self) -> LocalObject<T>
+    {
+        let data = match self.data
+        {
+            Data::Single(ref val)   => Rc::new(RefCell::new(val.clone())),
+            Data::Multiple(ref val) => val.clone()
+        };
+
+        self.data = Data::Multiple(data.clone());
+
+        LocalObject { data : Data::Multiple(data) }
+    }
+}
+
+use std::fmt::{Debug, Display, Formatter, Error};
+
+impl<T : Debug> Debug for LocalObject<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Debug.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{:?}", self.get())
+    }
+}
+
+impl<T : Display> Display for LocalObject<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Display.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{}", self.get())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    //*********************************************************************************************
+    /// Test that get/set work with only 1 instance.
+    #[test]
+    fn single()
+    {
+        let mut test = super::LocalObject::new(String::from("abc"));
+
+        assert_eq!(*test.get(), "abc");
+        test.set(String::from("xyz"));
+        assert_eq!(*test.get(), "xyz");
+    }
+
+    //*********************************************************************************************
+    /// Test that get/set work with multiple instances.
+    #[test]
+    fn multiple()
+    {
+        let mut test1 = super::LocalObject::new(String::from("abc"));
+        let mut test2 = test1.dup_local();
+        let mut test3 = test2.dup_local();
+
+        assert_eq!(*test3.get(), "abc");
+
+        test1.set(String::from("xyz"));
+
+        assert_eq!(*test2.get(), "xyz");
+        assert_eq!(*test3.get(), "xyz");
+
+        test3.set(String::from("123"));
+
+        assert_eq!(*test1.get(), "123");
+        assert_eq!(*test2.get(), "123");
+
+        test2.set(String::from("mno"));
+
+        assert_eq!(*test1.get(), "mno");
+        assert_eq!(*test3.get(), "mno");
+    }
+}
@@ -0,0 +1,211 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+//! Debug check for code that must not block.
+//!
+//! Threads that must never block, like real time audio threads, can mark the code that runs on
+//! them with enter().  While the returned guard is alive, any call on the current thread that
+//! could block on a lock, like get() or set() on a shared SharedObject, panics.  The numeric
+//! types use atomics and never block so they are not checked, except for SharedU64, SharedI64
+//! and SharedF64 on 32 bit targets, which guard the shared value with a Mutex.
+//!
+//! The check is only done in debug builds, in release builds enter() does nothing.
+//!
+//! # Examples
+//!
+//! ```should_panic
+//! use shareable::{no_block, SharedObject};
+//!
+//! let mut value1 = SharedObject::new(1);
+//! let value2     = value1.dup();
+//!
+//! let _guard = no_block::enter();
+//!
+//! value2.get();
+//! # #[cfg(not(debug_assertions))]
+//! # panic!();
+//! ```
+use std::cell::Cell;
+use std::marker::PhantomData;
+
+thread_local! {
+    //---------------------------------------------------------------------------------------------
+    /// Number of no block zones the current thread is in.
+    static DEPTH : Cell<usize> = const { Cell::new(0) };
+}
+
+//*************************************************************************************************
+/// Guard returned by enter(), the zone is exited when the guard is dropped.
+///
+/// The guard belongs to the thread that created it and can't be sent to another thread.
+pub struct NoBlockGuard
+{
+    //---------------------------------------------------------------------------------------------
+    /// Makes the guard !Send.
+    _thread : PhantomData<*const ()>
+}
+
+impl Drop for NoBlockGuard
+{
+    //*********************************************************************************************
+    /// Exits the zone.
+    fn drop(&mut self)
+    {
+        DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+//*************************************************************************************************
+/// Enters a no block zone on the current thread.  Zones can be nested.
+pub fn enter() -> NoBlockGuard
+{
+    DEPTH.with(|depth| depth.set(depth.get() + 1));
+
+    NoBlockGuard { _thread : PhantomData }
+}
+
+//*************************************************************************************************
+/// Returns true if the current thread is in a no block zone.
+pub fn is_active() -> bool
+{
+    DEPTH.with(|depth| depth.get() > 0)
+}
+
+//*************************************************************************************************
+/// Called before an operation that could block, panics in debug builds if the current thread is
+/// in a no block zone.
+#[inline]
+pub(crate) fn check()
+{
+    if cfg!(debug_assertions) && is_active()
+    {
+        panic!("blocking operation called inside a no_block zone");
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use SharedObject;
+
+    //*********************************************************************************************
+    /// Test that the zone is exited when the guard is dropped.
+    #[test]
+    fn test_enter()
+    {
+        assert!(!super::is_active());
+
+        {
+            let _guard1 = super::enter();
+            let _guard2 = super::enter();
+
+            assert!(super::is_active());
+        }
+
+        assert!(!super::is_active());
+    }
+
+    //*********************************************************************************************
+    /// Test that get() on a shared SharedObject panics inside the zone.
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "no_block")]
+    fn test_shared_get_panics()
+    {
+        let mut test1 = SharedObject::new(1);
+        let test2     = test1.dup();
+
+        let _guard = super::enter();
+
+        test2.get();
+    }
+
+    //*********************************************************************************************
+    /// Test that will_clone_on_write() on a shared SharedObject panics inside the zone, it takes
+    /// the read lock.
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "no_block")]
+    fn test_will_clone_on_write_panics()
+    {
+        let mut test1 = SharedObject::new(1);
+        let test2     = test1.dup();
+
+        let _guard = super::enter();
+
+        test2.will_clone_on_write();
+    }
+
+    //*********************************************************************************************
+    /// Test that swap_with() between two shared SharedObjects panics inside the zone, it takes
+    /// both write locks.
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "no_block")]
+    fn test_swap_with_panics()
+    {
+        let mut test1 = SharedObject::new(1);
+        let mut test2 = SharedObject::new(2);
+        let _test3    = test1.dup();
+        let _test4    = test2.dup();
+
+        let _guard = super::enter();
+
+        test1.swap_with(&mut test2);
+    }
+
+    //*********************************************************************************************
+    /// Test that swap_with() with one shared SharedObject panics inside the zone, it takes the
+    /// write lock of the shared one.
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "no_block")]
+    fn test_swap_with_one_shared_panics()
+    {
+        let mut test1 = SharedObject::new(1);
+        let mut test2 = SharedObject::new(2);
+        let _test3    = test2.dup();
+
+        let _guard = super::enter();
+
+        test1.swap_with(&mut test2);
+    }
+
+    //*********************************************************************************************
+    /// Test that with_value() on a shared SharedObject panics inside the zone, it takes the write
+    /// lock.
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "no_block")]
+    fn test_with_value_panics()
+    {
+        let mut test1 = SharedObject::new(1);
+        let _test2    = test1.dup();
+
+        let _guard = super::enter();
+
+        test1.with_value(2, || ());
+    }
+
+    //*********************************************************************************************
+    /// Test that the paths that can't block are allowed inside the zone.
+    #[test]
+    fn test_non_blocking_allowed()
+    {
+        let mut test1 = SharedObject::new(1);
+        let mut test2 = ::SharedU64::new(1);
+        let test3     = test2.dup();
+
+        let _guard = super::enter();
+
+        test1.set(2);
+
+        assert_eq!(*test1.get(), 2);
+        assert_eq!(test3.get(), 1);
+    }
+}
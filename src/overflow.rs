@@ -0,0 +1,43 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+use core::error;
+use core::fmt::{Display, Formatter, Error};
+
+//*************************************************************************************************
+/// Error returned by the checked operations, identifies which bound the result went past.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overflow
+{
+    //---------------------------------------------------------------------------------------------
+    /// The result would have been larger than the maximum value.
+    Positive,
+
+    //---------------------------------------------------------------------------------------------
+    /// The result would have been smaller than the minimum value.
+    Negative
+}
+
+impl Display for Overflow
+{
+    //*********************************************************************************************
+    /// Implementation of Display.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        match *self
+        {
+            Overflow::Positive => write!(f, "overflow past the maximum value"),
+            Overflow::Negative => write!(f, "overflow past the minimum value")
+        }
+    }
+}
+
+impl error::Error for Overflow {}
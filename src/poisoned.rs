@@ -0,0 +1,32 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+use core::error;
+use core::fmt::{Display, Formatter, Error};
+
+//*************************************************************************************************
+/// Error returned by checked_get() and checked_set() when another thread panicked while holding
+/// the lock guarding the value.  The value itself is still usable, get() and set() ignore the
+/// poisoning.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Poisoned;
+
+impl Display for Poisoned
+{
+    //*********************************************************************************************
+    /// Implementation of Display.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "another thread panicked while holding the lock on the value")
+    }
+}
+
+impl error::Error for Poisoned {}
@@ -0,0 +1,118 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+use alloc::sync::Arc;
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+#[cfg(not(feature = "std"))]
+use core::mem;
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+
+#[cfg(not(feature = "std"))]
+use once_cell::race::OnceBox;
+
+//*************************************************************************************************
+/// The shared storage created by clone() of an unshared object, set at most once through a shared
+/// reference.  A OnceLock with std, a OnceBox from once_cell without it.
+pub(crate) struct Promoted<T>
+{
+    //---------------------------------------------------------------------------------------------
+    /// The storage, if clone() created it.
+    #[cfg(feature = "std")]
+    cell : OnceLock<Arc<T>>,
+
+    //---------------------------------------------------------------------------------------------
+    /// The storage, if clone() created it.
+    #[cfg(not(feature = "std"))]
+    cell : OnceBox<Arc<T>>
+}
+
+impl<T> Promoted<T>
+{
+    //********************************************************************************************
+    /// Construct a new instance of the object without storage.
+    pub(crate) fn new() -> Promoted<T>
+    {
+        #[cfg(feature = "std")]
+        {
+            Promoted { cell : OnceLock::new() }
+        }
+
+        #[cfg(not(feature = "std"))]
+        {
+            Promoted { cell : OnceBox::new() }
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns the storage if it was created.
+    pub(crate) fn get(&self) -> Option<&Arc<T>>
+    {
+        self.cell.get()
+    }
+
+    //********************************************************************************************
+    /// Returns the storage, creating it with f if it doesn't exist yet.  Without std, f can run on
+    /// several threads at once, the first result stored wins and the others are dropped.
+    pub(crate) fn get_or_init<F : FnOnce() -> Arc<T>>(
+        &self,
+        f : F
+        ) -> &Arc<T>
+    {
+        #[cfg(feature = "std")]
+        {
+            self.cell.get_or_init(f)
+        }
+
+        #[cfg(not(feature = "std"))]
+        {
+            self.cell.get_or_init(|| Box::new(f()))
+        }
+    }
+
+    //********************************************************************************************
+    /// Removes the storage and returns it, if it was created.
+    pub(crate) fn take(&mut self) -> Option<Arc<T>>
+    {
+        #[cfg(feature = "std")]
+        {
+            self.cell.take()
+        }
+
+        #[cfg(not(feature = "std"))]
+        {
+            mem::take(&mut self.cell).get().cloned()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::sync::Arc;
+
+    //*********************************************************************************************
+    /// Test that the storage is only created once and can be taken back out.
+    #[test]
+    fn test_take()
+    {
+        let mut test = super::Promoted::new();
+
+        assert!(test.get().is_none());
+
+        let first = test.get_or_init(|| Arc::new(1)).clone();
+        let again = test.get_or_init(|| Arc::new(2)).clone();
+
+        assert!(Arc::ptr_eq(&first, &again));
+        assert!(Arc::ptr_eq(test.get().unwrap(), &first));
+        assert!(Arc::ptr_eq(&test.take().unwrap(), &first));
+        assert!(test.get().is_none());
+        assert!(test.take().is_none());
+    }
+}
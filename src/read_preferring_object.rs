@@ -0,0 +1,256 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+use std::sync::Arc;
+use parking_lot::RwLock;
+
+//*************************************************************************************************
+/// Internal data structure that identifies how we are accessing the data.
+enum Data<T>
+{
+    //---------------------------------------------------------------------------------------------
+    /// There is only 1 instance of the element.
+    Single(Arc<T>),
+
+    //---------------------------------------------------------------------------------------------
+    /// There are or were multiple instances of the element.
+    Multiple(Arc<RwLock<Arc<T>>>)
+}
+
+//*************************************************************************************************
+/// Shareable object data element that favors readers over writers.
+///
+/// This works like SharedObject, but once the object is shared the value is saved behind a
+/// parking_lot RwLock instead of the std one.  get() takes a recursive read lock, which is
+/// granted whenever other readers hold the lock, even if a writer is waiting.  A steady stream of
+/// readers is never stalled by an occasional writer; the price is that set() can wait for as
+/// long as readers keep overlapping, so this is meant for values that are read far more often
+/// than they are set.  Only available with the read_preferring feature.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use shareable::ReadPreferringObject;
+///
+/// let mut config = ReadPreferringObject::new(String::from("abc"));
+/// let reader     = config.dup();
+///
+/// config.set(String::from("xyz"));
+///
+/// let thread = thread::spawn(move || {
+///     assert_eq!(*reader.get(), "xyz");
+/// });
+///
+/// thread.join().unwrap();
+/// ```
+pub struct ReadPreferringObject<T>
+{
+    //---------------------------------------------------------------------------------------------
+    /// The internal data element.
+    data : Data<T>
+}
+
+impl<T> ReadPreferringObject<T>
+{
+    //********************************************************************************************
+    /// Construct a new instance of the object.
+    pub fn new(
+        value : T
+        ) -> ReadPreferringObject<T>
+    {
+        ReadPreferringObject {
+            data : Data::Single(Arc::new(value))
+        }
+    }
+
+    //********************************************************************************************
+    /// Set the value of the object.  If the object is shared this waits until no readers hold
+    /// the lock.
+    pub fn set(
+        &mut self,
+        val : T
+        )
+    {
+        match self.data
+        {
+            Data::Single(_)         => self.data = Data::Single(Arc::new(val)),
+            Data::Multiple(ref mem) => *mem.write() = Arc::new(val)
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns the value of the object.  Never waits for a writer that is only waiting for the
+    /// lock.
+    pub fn get(&self) -> Arc<T>
+    {
+        match self.data
+        {
+            Data::Single(ref val)   => val.clone(),
+            Data::Multiple(ref mem) => mem.read_recursive().clone()
+        }
+    }
+
+    //********************************************************************************************
+    /// Clones the object.  After this call all access to the data will be done via a shared
+    /// RwLock element.
+    pub fn dup(&mut self) -> ReadPreferringObject<T>
+    {
+        let data = match self.data
+        {
+            Data::Single(ref val)   => Arc::new(RwLock::new(val.clone())),
+            Data::Multiple(ref val) => val.clone()
+        };
+
+        self.data = Data::Multiple(data.clone());
+
+        ReadPreferringObject { data : Data::Multiple(data) }
+    }
+
+    //********************************************************************************************
+    /// Shares the object, this is the same as dup().  The returned object is linked to this one,
+    /// setting the value on either object is seen by both.
+    pub fn share(&mut self) -> ReadPreferringObject<T>
+    {
+        self.dup()
+    }
+
+    //********************************************************************************************
+    /// Returns a new object holding the current value.  Unlike share() the returned object is
+    /// independent of this one, setting the value on either object is not seen by the other.
+    pub fn snapshot(&self) -> ReadPreferringObject<T>
+    {
+        ReadPreferringObject { data : Data::Single(self.get()) }
+    }
+
+    //********************************************************************************************
+    /// Returns true if the value is in the shared storage, which is the case once dup() has been
+    /// called.
+    pub fn is_shared(&self) -> bool
+    {
+        matches!(self.data, Data::Multiple(_))
+    }
+}
+
+use std::fmt::{Debug, Display, Formatter, Error};
+
+impl<T : Debug> Debug for ReadPreferringObject<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Debug.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{:?}", self.get())
+    }
+}
+
+impl<T : Display> Display for ReadPreferringObject<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Display.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{}", self.get())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    //*********************************************************************************************
+    /// Test that get/set work with multiple instances.
+    #[test]
+    fn multiple()
+    {
+        let mut test1 = super::ReadPreferringObject::new(String::from("abc"));
+        let mut test2 = test1.dup();
+        let test3     = test1.snapshot();
+
+        test2.set(String::from("xyz"));
+
+        assert_eq!(*test1.get(), "xyz");
+        assert_eq!(*test3.get(), "abc");
+    }
+
+    //*********************************************************************************************
+    /// Test that readers keep making progress while a writer keeps setting the value.
+    #[test]
+    fn readers_under_writer()
+    {
+        let mut writer  = super::ReadPreferringObject::new(0usize);
+        let done        = Arc::new(AtomicBool::new(false));
+        let reads       = Arc::new(AtomicUsize::new(0));
+        let mut threads = Vec::new();
+
+        for _ in 0..4
+        {
+            let reader = writer.dup();
+            let done   = done.clone();
+            let reads  = reads.clone();
+
+            threads.push(thread::spawn(move || {
+                let mut last = 0;
+
+                while !done.load(Ordering::Relaxed)
+                {
+                    let val = *reader.get();
+
+                    assert!(val >= last);
+
+                    last = val;
+                    reads.fetch_add(1, Ordering::Relaxed);
+                }
+            }));
+        }
+
+        let start    = Instant::now();
+        let mut sets = 0;
+
+        while start.elapsed() < Duration::from_millis(200)
+        {
+            sets += 1;
+            writer.set(sets);
+        }
+
+        done.store(true, Ordering::Relaxed);
+
+        for thread in threads
+        {
+            thread.join().unwrap();
+        }
+
+        assert!(reads.load(Ordering::Relaxed) > 0);
+        assert_eq!(*writer.get(), sets);
+    }
+
+    //*********************************************************************************************
+    /// Test that is_shared() is false until dup() is called.
+    #[test]
+    fn is_shared()
+    {
+        let mut test1 = super::ReadPreferringObject::new(5);
+
+        assert!(!test1.is_shared());
+
+        let test2 = test1.dup();
+
+        assert!(test1.is_shared());
+        assert!(test2.is_shared());
+    }
+}
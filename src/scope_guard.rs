@@ -0,0 +1,41 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+//*************************************************************************************************
+/// Calls a function when dropped, including while unwinding from a panic.
+pub(crate) struct ScopeGuard<F : FnOnce()>
+{
+    //---------------------------------------------------------------------------------------------
+    /// The function, taken when it is called.
+    f : Option<F>
+}
+
+impl<F : FnOnce()> ScopeGuard<F>
+{
+    //********************************************************************************************
+    /// Construct a new instance of the object.
+    pub(crate) fn new(
+        f : F
+        ) -> ScopeGuard<F>
+    {
+        ScopeGuard { f : Some(f) }
+    }
+}
+
+impl<F : FnOnce()> Drop for ScopeGuard<F>
+{
+    //*********************************************************************************************
+    /// Calls the function.
+    fn drop(&mut self)
+    {
+        if let Some(f) = self.f.take()
+        {
+            f();
+        }
+    }
+}
@@ -0,0 +1,960 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+use alloc::sync::Arc;
+use core::convert::TryFrom;
+use core::mem;
+use core::num::Wrapping;
+use core::ops::Range;
+use core::sync::atomic::Ordering;
+
+use AtomicRepr;
+use SaturatingFrom;
+use UpdateResult;
+use auto_downgrade::AutoDowngrade;
+use promoted::Promoted;
+use scope_guard::ScopeGuard;
+use test_hooks::{self, Point};
+#[cfg(all(feature = "strict-ordering", debug_assertions))]
+use strict_ordering::ThreadTracker;
+#[cfg(feature = "thread-tracking")]
+use thread_tracking::ThreadSet;
+
+//*************************************************************************************************
+/// Internal data structure that identifies how we are accessing the data.
+enum Data<T : AtomicRepr>
+{
+    //---------------------------------------------------------------------------------------------
+    /// There is only 1 instance of the element.
+    Single(T),
+
+    //---------------------------------------------------------------------------------------------
+    /// There are or were multiple instances of the element.
+    Multiple(Arc<T::Storage>)
+}
+
+//*************************************************************************************************
+/// Shareable integer data element, the implementation behind SharedU8 through SharedUsize.
+///
+/// If only 1 instance of the element is needed then that data is just saved as a normal memory
+/// location.  If multiple instances are needed then the value is moved into storage in an Arc so
+/// it can be safely shared between threads.  The storage is the atomic for T, or a Mutex for the
+/// 64 bit types on 32 bit targets, see AtomicRepr.
+///
+/// The methods that update the value through a shared reference, like fetch_add(), need the
+/// storage, so the first of them called on an unshared object moves the value into it the same
+/// way clone() does.
+///
+/// # Examples
+///
+/// ```
+/// use shareable::Shared;
+///
+/// let mut value1 = Shared::new(63u16);
+/// let value2     = value1.dup();
+///
+/// value1.set(31);
+///
+/// assert_eq!(value2.get(), 31);
+/// ```
+pub struct Shared<T : AtomicRepr>
+{
+    //---------------------------------------------------------------------------------------------
+    /// The internal data element.
+    data     : Data<T>,
+
+    //---------------------------------------------------------------------------------------------
+    /// The shared storage created by clone() while the object was unshared, the object switches
+    /// to it the next time it has mutable access.
+    promoted : Promoted<T::Storage>,
+
+    //---------------------------------------------------------------------------------------------
+    /// Counts the calls to set() in auto downgrade mode, see set_auto_downgrade().
+    collapse : AutoDowngrade,
+
+    //---------------------------------------------------------------------------------------------
+    /// The threads that called get() or set(), shared with the other instances.  Only with the
+    /// thread-tracking feature.
+    #[cfg(feature = "thread-tracking")]
+    seen     : ThreadSet,
+
+    //---------------------------------------------------------------------------------------------
+    /// The threads that accessed the shared value, only with the strict-ordering feature in debug
+    /// builds.
+    #[cfg(all(feature = "strict-ordering", debug_assertions))]
+    threads  : ThreadTracker
+}
+
+impl<T : AtomicRepr> Shared<T>
+{
+    //********************************************************************************************
+    /// Construct a new instance of the object.
+    pub fn new(
+        value : T
+        ) -> Shared<T>
+    {
+        Shared {
+            data     : Data::Single(value),
+            promoted : Promoted::new(),
+            collapse : AutoDowngrade::off(),
+
+            #[cfg(feature = "thread-tracking")]
+            seen     : ThreadSet::new(),
+
+            #[cfg(all(feature = "strict-ordering", debug_assertions))]
+            threads  : ThreadTracker::new()
+        }
+    }
+
+    //********************************************************************************************
+    /// Set the value of the object.
+    pub fn set(
+        &mut self,
+        val : T
+        )
+    {
+        self.set_with(val, Ordering::Relaxed);
+    }
+
+    //********************************************************************************************
+    /// Set the value, storing it with the given memory ordering.  set() uses Relaxed, pass
+    /// Release or SeqCst when the value guards other data.  Until the object is shared no other
+    /// thread can see it, so the ordering only matters after dup().  Once it is shared, Acquire
+    /// and AcqRel panic like they do with the store() of the atomics.  The order is ignored when
+    /// the value is guarded by a Mutex, which already orders the accesses.
+    pub fn set_with(
+        &mut self,
+        val   : T,
+        order : Ordering
+        )
+    {
+        self.settle();
+
+        #[cfg(feature = "thread-tracking")]
+        self.seen.record();
+
+        if self.collapse.tick()
+        {
+            self.try_downgrade();
+        }
+
+        #[cfg(all(feature = "strict-ordering", debug_assertions))]
+        self.threads.check(T::LOCK_FREE && self.is_shared(), order);
+
+        match self.data
+        {
+            Data::Single(ref mut mem) => *mem = val,
+            Data::Multiple(ref mem)   => T::store(mem, val, order)
+        }
+    }
+
+    //********************************************************************************************
+    /// Set the value and return the previous value in a single operation.
+    pub fn swap(
+        &mut self,
+        val : T
+        ) -> T
+    {
+        self.settle();
+
+        match self.data
+        {
+            Data::Single(ref mut mem) => mem::replace(mem, val),
+            Data::Multiple(ref mem)   => T::swap(mem, val, Ordering::Relaxed)
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns the value of the object.
+    pub fn get(&self) -> T
+    {
+        self.get_with(Ordering::Relaxed)
+    }
+
+    //********************************************************************************************
+    /// Returns the value, loading it with the given memory ordering.  get() uses Relaxed, pass
+    /// Acquire or SeqCst when the value guards other data.  Panics if order is Release or
+    /// AcqRel, like the load() of the atomics.  The order is ignored when the value is guarded by
+    /// a Mutex.
+    pub fn get_with(
+        &self,
+        order : Ordering
+        ) -> T
+    {
+        #[cfg(all(feature = "strict-ordering", debug_assertions))]
+        self.threads.check(T::LOCK_FREE && self.is_shared(), order);
+
+        #[cfg(feature = "thread-tracking")]
+        self.seen.record();
+
+        match self.data
+        {
+            Data::Single(val)       => self.promoted.get().map_or(val, |mem| T::load(mem, order)),
+            Data::Multiple(ref mem) => T::load(mem, order)
+        }
+    }
+
+    //********************************************************************************************
+    /// Clones the object, this is the same as clone().  After this call all access to the data
+    /// will be done via the shared storage.
+    pub fn dup(&mut self) -> Shared<T>
+    {
+        let copy = self.clone();
+
+        self.settle();
+
+        copy
+    }
+
+    //********************************************************************************************
+    /// Returns the storage holding the value.  An unshared value is moved into new storage first,
+    /// like clone() does, so it can be updated through a shared reference.
+    pub(crate) fn storage(&self) -> &T::Storage
+    {
+        match self.data
+        {
+            Data::Single(val)       => self.promoted.get_or_init(|| Arc::new(T::new_storage(val))),
+            Data::Multiple(ref mem) => mem
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns the storage holding the value while the object is shared, or None while the value
+    /// is stored in the object itself.
+    pub(crate) fn shared_storage(&self) -> Option<&T::Storage>
+    {
+        match self.data
+        {
+            Data::Single(_)         => self.promoted.get().map(|mem| &**mem),
+            Data::Multiple(ref mem) => Some(mem)
+        }
+    }
+
+    //********************************************************************************************
+    /// Switches to the shared storage created by clone(), if there is one.
+    fn settle(&mut self)
+    {
+        if let Some(mem) = self.promoted.take()
+        {
+            self.data = Data::Multiple(mem);
+        }
+    }
+
+    //********************************************************************************************
+    /// Makes this object and other share new storage holding val.  The objects that were sharing
+    /// the data with either of them keep using the old storage.
+    pub(crate) fn link(
+        &mut self,
+        other : &mut Shared<T>,
+        val   : T
+        )
+    {
+        let data = Arc::new(T::new_storage(val));
+
+        self.promoted.take();
+        other.promoted.take();
+
+        self.data  = Data::Multiple(data.clone());
+        other.data = Data::Multiple(data);
+
+        #[cfg(all(feature = "strict-ordering", debug_assertions))]
+        {
+            self.threads  = ThreadTracker::new();
+            other.threads = self.threads.clone();
+        }
+    }
+
+    //********************************************************************************************
+    /// Runs the compare and swap loop of fetch_update() with Relaxed ordering, calling the test
+    /// hook between reading the value and swapping in the new one.  There is no loop when the
+    /// value is guarded by a Mutex, f and the hook run once under the lock.
+    fn update_loop<F : FnMut(T) -> Option<T>>(
+        &self,
+        mut f : F
+        ) -> Result<T, T>
+    {
+        T::fetch_update(self.storage(), Ordering::Relaxed, Ordering::Relaxed, |old| {
+            let new = f(old);
+
+            test_hooks::hit(Point::CompareExchange);
+
+            new
+        })
+    }
+
+    //********************************************************************************************
+    /// Shares the object, this is the same as dup().  The returned object is linked to this one,
+    /// setting the value on either object is seen by both.
+    pub fn share(&mut self) -> Shared<T>
+    {
+        self.dup()
+    }
+
+    //********************************************************************************************
+    /// Returns a new object holding the current value.  Unlike share() the returned object is
+    /// independent of this one, setting the value on either object is not seen by the other.
+    pub fn snapshot(&self) -> Shared<T>
+    {
+        Shared::new(self.get())
+    }
+
+    //********************************************************************************************
+    /// Returns the value converted to another integer type, or None if the value doesn't fit in
+    /// the target type.
+    pub fn get_checked<U : TryFrom<T>>(&self) -> Option<U>
+    {
+        U::try_from(self.get()).ok()
+    }
+
+    //********************************************************************************************
+    /// Returns the value converted to another integer type, clamped to the range of the target
+    /// type if it doesn't fit.
+    pub fn get_saturating<U : SaturatingFrom<T>>(&self) -> U
+    {
+        U::saturating_from(self.get())
+    }
+
+    //********************************************************************************************
+    /// Returns true if no other objects are sharing the data.  This is always true before dup()
+    /// is called, and becomes true again once all the other objects have been dropped.
+    pub fn is_uniquely_owned(&self) -> bool
+    {
+        match self.data
+        {
+            Data::Single(_)         => {
+                self.promoted.get().is_none_or(|mem| Arc::strong_count(mem) == 1)
+            },
+            Data::Multiple(ref mem) => Arc::strong_count(mem) == 1
+        }
+    }
+
+    //********************************************************************************************
+    /// Consumes the object and returns the value if no other objects are sharing the data, or
+    /// hands the object back in Err if they are.  The value is copied out of the storage, so
+    /// unlike SharedObject::try_into_inner() there is no unwrapping involved, this only checks
+    /// that the value isn't being lost to the other objects.
+    pub fn try_into_inner(self) -> Result<T, Shared<T>>
+    {
+        if !self.is_uniquely_owned()
+        {
+            return Err(self);
+        }
+
+        Ok(self.get_with(Ordering::Acquire))
+    }
+
+    //********************************************************************************************
+    /// Returns the range from the current value up to (but not including) end.  The start of
+    /// the range is read once when this is called, setting the value afterwards doesn't change
+    /// the range.
+    pub fn range_to(
+        &self,
+        end : T
+        ) -> Range<T>
+    {
+        self.get()..end
+    }
+
+    //********************************************************************************************
+    /// Returns the value wrapped in a Wrapping so arithmetic on it wraps around on overflow.
+    pub fn wrapping(&self) -> Wrapping<T>
+    {
+        Wrapping(self.get())
+    }
+
+    //********************************************************************************************
+    /// Sets the value to new if it is equal to current, like AtomicU32::compare_exchange().
+    /// Returns Ok with the previous value if the value was replaced, or Err with the actual value
+    /// if it wasn't.  success is the ordering used when the value is replaced and failure the one
+    /// used when it isn't, both are ignored when the value is guarded by a Mutex.
+    pub fn compare_exchange(
+        &self,
+        current : T,
+        new     : T,
+        success : Ordering,
+        failure : Ordering
+        ) -> Result<T, T>
+    {
+        T::compare_exchange(self.storage(), current, new, success, failure)
+    }
+
+    //********************************************************************************************
+    /// Sets the value to new if it is equal to current.  Returns the value that was stored
+    /// before the call, the exchange happened if it is equal to current.
+    pub fn compare_exchange_value(
+        &self,
+        current : T,
+        new     : T
+        ) -> T
+    {
+        match self.compare_exchange(current, new, Ordering::Relaxed, Ordering::Relaxed)
+        {
+            Ok(old) | Err(old) => old
+        }
+    }
+
+    //********************************************************************************************
+    /// Adds to the value and returns the previous value.  The addition wraps around on overflow,
+    /// it never panics, even in debug builds.
+    pub fn fetch_add(
+        &self,
+        val : T
+        ) -> T
+    {
+        T::fetch_add(self.storage(), val, Ordering::Relaxed)
+    }
+
+    //********************************************************************************************
+    /// Subtracts from the value and returns the previous value.  The subtraction wraps around on
+    /// overflow, it never panics, even in debug builds.
+    pub fn fetch_sub(
+        &self,
+        val : T
+        ) -> T
+    {
+        T::fetch_sub(self.storage(), val, Ordering::Relaxed)
+    }
+
+    //********************************************************************************************
+    /// Sets the value to the bitwise and of the current value and val, returns the previous value.
+    pub fn fetch_and(
+        &self,
+        val : T
+        ) -> T
+    {
+        T::fetch_and(self.storage(), val, Ordering::Relaxed)
+    }
+
+    //********************************************************************************************
+    /// Sets the value to the bitwise or of the current value and val, returns the previous value.
+    pub fn fetch_or(
+        &self,
+        val : T
+        ) -> T
+    {
+        T::fetch_or(self.storage(), val, Ordering::Relaxed)
+    }
+
+    //********************************************************************************************
+    /// Sets the value to the bitwise xor of the current value and val, returns the previous value.
+    pub fn fetch_xor(
+        &self,
+        val : T
+        ) -> T
+    {
+        T::fetch_xor(self.storage(), val, Ordering::Relaxed)
+    }
+
+    //********************************************************************************************
+    /// Sets the value to the maximum of the current value and val, returns the previous value.
+    pub fn fetch_max(
+        &self,
+        val : T
+        ) -> T
+    {
+        T::fetch_max(self.storage(), val, Ordering::Relaxed)
+    }
+
+    //********************************************************************************************
+    /// Sets the value to the minimum of the current value and val, returns the previous value.
+    pub fn fetch_min(
+        &self,
+        val : T
+        ) -> T
+    {
+        T::fetch_min(self.storage(), val, Ordering::Relaxed)
+    }
+
+    //********************************************************************************************
+    /// Sets the value to new if pred returns true for the current value.  Returns Ok with the
+    /// previous value if the value was replaced, or Err with the current value if it wasn't.
+    ///
+    /// If another thread changes the value while this call is running, pred is called again
+    /// with the new value, which is why it is FnMut instead of FnOnce.
+    pub fn replace_if<P : FnMut(T) -> bool>(
+        &self,
+        new      : T,
+        mut pred : P
+        ) -> Result<T, T>
+    {
+        self.update_loop(|old| if pred(old) { Some(new) } else { None })
+    }
+
+    //********************************************************************************************
+    /// Calls f with the current value and stores the result if it returns Some, like
+    /// AtomicU32::fetch_update().  Returns Ok with the previous value if f returned Some, or Err
+    /// with the current value if it returned None.  f is called again if another thread changes
+    /// the value in between, which is why it is FnMut.
+    pub fn fetch_update<F : FnMut(T) -> Option<T>>(
+        &self,
+        set_order   : Ordering,
+        fetch_order : Ordering,
+        f           : F
+        ) -> Result<T, T>
+    {
+        T::fetch_update(self.storage(), set_order, fetch_order, f)
+    }
+
+    //********************************************************************************************
+    /// Moves the value into the shared storage used after dup() without creating another object,
+    /// so the storage is allocated now instead of by the first dup().  Does nothing if the object
+    /// is already shared.
+    pub fn pre_share(&mut self)
+    {
+        self.settle();
+
+        if let Data::Single(val) = self.data
+        {
+            self.data = Data::Multiple(Arc::new(T::new_storage(val)));
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns true if the value is in the shared storage, which is the case once dup(),
+    /// pre_share() or a method updating the value through a shared reference has been called.
+    pub fn is_shared(&self) -> bool
+    {
+        matches!(self.data, Data::Multiple(_)) || self.promoted.get().is_some()
+    }
+
+    //********************************************************************************************
+    /// Returns the number of distinct threads that have called get() or set() on this object or
+    /// the objects sharing the data with it.  Only available with the thread-tracking feature.
+    #[cfg(feature = "thread-tracking")]
+    pub fn accessing_threads(&self) -> usize
+    {
+        self.seen.count()
+    }
+
+    //********************************************************************************************
+    /// Moves the value back out of the shared storage if no other objects are sharing it anymore,
+    /// so access is as cheap as before dup().  Returns true if the object is unshared after the
+    /// call, or false if other objects are still sharing the data.
+    pub fn try_downgrade(&mut self) -> bool
+    {
+        self.settle();
+
+        if let Data::Multiple(ref mut mem) = self.data
+        {
+            let val = match Arc::get_mut(mem)
+            {
+                Some(mem) => *T::get_mut(mem),
+                None      => return false
+            };
+
+            self.data = Data::Single(val);
+
+            #[cfg(all(feature = "strict-ordering", debug_assertions))]
+            {
+                self.threads = ThreadTracker::new();
+            }
+        }
+
+        true
+    }
+
+    //********************************************************************************************
+    /// Turns auto downgrade mode on or off.  In this mode every 64th call to set() checks whether
+    /// the other objects sharing the data are gone, and if they are moves the value back out of
+    /// the shared storage like try_downgrade().  The check is an Arc::get_mut(), the other calls
+    /// only count.  Objects created by dup() start with the mode off.
+    pub fn set_auto_downgrade(
+        &mut self,
+        on : bool
+        )
+    {
+        self.collapse.enable(on);
+    }
+
+    //********************************************************************************************
+    /// Sets the value to the larger of the current value and f(current), returning the previous
+    /// value.  Use this for a gauge whose candidate value is derived from its current value.
+    ///
+    /// If another thread changes the value while this call is running, f is called again with
+    /// the new value, which is why it is FnMut instead of FnOnce.
+    pub fn fetch_max_with<F : FnMut(T) -> T>(
+        &self,
+        mut f : F
+        ) -> T
+    {
+        let result = self.update_loop(|old| {
+            let new = f(old);
+
+            if new > old { Some(new) } else { None }
+        });
+
+        match result
+        {
+            Ok(old) | Err(old) => old
+        }
+    }
+
+    //********************************************************************************************
+    /// Adds val to the value, clamping at the bounds of the type instead of overflowing.  Returns
+    /// the new value and true if it was clamped, so a bounded accumulator can tell when it hits
+    /// the limit.
+    pub fn saturating_add_flagged(
+        &self,
+        val : T
+        ) -> (T, bool)
+    {
+        let old = match self.update_loop(|old| Some(old.saturating_add(val)))
+        {
+            Ok(old) | Err(old) => old
+        };
+
+        (old.saturating_add(val), old.checked_add(val).is_none())
+    }
+
+    //********************************************************************************************
+    /// Adds val to the value and returns the new value.  The addition wraps around on overflow,
+    /// it never panics, even in debug builds.
+    pub fn wrapping_add(
+        &self,
+        val : T
+        ) -> T
+    {
+        self.fetch_add(val).wrapping_add(val)
+    }
+
+    //********************************************************************************************
+    /// Subtracts val from the value and returns the new value.  The subtraction wraps around on
+    /// overflow, it never panics, even in debug builds.
+    pub fn wrapping_sub(
+        &self,
+        val : T
+        ) -> T
+    {
+        self.fetch_sub(val).wrapping_sub(val)
+    }
+
+    //********************************************************************************************
+    /// Adds val to the value and returns the new value, clamping at the bounds of the type
+    /// instead of overflowing.
+    pub fn saturating_add(
+        &self,
+        val : T
+        ) -> T
+    {
+        self.saturating_add_flagged(val).0
+    }
+
+    //********************************************************************************************
+    /// Subtracts val from the value and returns the new value, clamping at the bounds of the type
+    /// instead of overflowing.
+    pub fn saturating_sub(
+        &self,
+        val : T
+        ) -> T
+    {
+        let old = match self.update_loop(|old| Some(old.saturating_sub(val)))
+        {
+            Ok(old) | Err(old) => old
+        };
+
+        old.saturating_sub(val)
+    }
+
+    //********************************************************************************************
+    /// Adds val to the value and returns the new value, or leaves the value unchanged and returns
+    /// None if the addition would overflow.
+    pub fn checked_add(
+        &self,
+        val : T
+        ) -> Option<T>
+    {
+        self.update_loop(|old| old.checked_add(val)).ok().map(|old| old.wrapping_add(val))
+    }
+
+    //********************************************************************************************
+    /// Subtracts val from the value and returns the new value, or leaves the value unchanged and
+    /// returns None if the subtraction would overflow.
+    pub fn checked_sub(
+        &self,
+        val : T
+        ) -> Option<T>
+    {
+        self.update_loop(|old| old.checked_sub(val)).ok().map(|old| old.wrapping_sub(val))
+    }
+
+    //********************************************************************************************
+    /// Sets the value to the smaller of the value and val.  Returns the previous value and true
+    /// if val became the new minimum, so a low water mark tracker knows whether it won without
+    /// comparing again.
+    pub fn fetch_min_flagged(
+        &self,
+        val : T
+        ) -> (T, bool)
+    {
+        let old = self.fetch_min(val);
+
+        (old, val < old)
+    }
+
+    //********************************************************************************************
+    /// Sets the value to temp while f runs, then restores the previous value, even if f panics.
+    ///
+    /// The override isn't atomic across threads.  Objects sharing the data see temp while f runs,
+    /// and a value set by another object during f is overwritten when the previous value is
+    /// restored, so this only makes sense when this object is the only writer.
+    pub fn with_value<R, F : FnOnce() -> R>(
+        &mut self,
+        temp : T,
+        f    : F
+        ) -> R
+    {
+        let old = self.get();
+
+        self.set(temp);
+
+        let _restore = ScopeGuard::new(|| self.set(old));
+
+        f()
+    }
+
+    //********************************************************************************************
+    /// Sets the value to f(current) and returns the old value, the new value, and whether it
+    /// changed, all from the same atomic update.
+    ///
+    /// If another thread changes the value while this call is running, f is called again with
+    /// the new value, so f may run more than once and should not have side effects.
+    pub fn update_full<F : FnMut(T) -> T>(
+        &self,
+        mut f : F
+        ) -> UpdateResult<T>
+    {
+        let mut new = T::default();
+        let result  = self.update_loop(|old| {
+            new = f(old);
+
+            Some(new)
+        });
+        let old     = match result
+        {
+            Ok(old) | Err(old) => old
+        };
+
+        UpdateResult { old, new, changed : old != new }
+    }
+}
+
+impl<T : AtomicRepr> Clone for Shared<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Clone, the same as dup() except that it only needs a shared reference.
+    ///
+    /// Cloning an unshared object copies the value into shared storage, and this object switches
+    /// to that storage.  The value of an unshared object only changes through a mutable reference,
+    /// the methods updating it through a shared reference switch to the same storage first, so no
+    /// update is lost while the clone is being made.
+    fn clone(&self) -> Shared<T>
+    {
+        let data = match self.data
+        {
+            Data::Single(val)       => self.promoted.get_or_init(|| {
+                let data = Arc::new(T::new_storage(val));
+
+                test_hooks::hit(Point::Dup);
+
+                data
+            }).clone(),
+            Data::Multiple(ref mem) => mem.clone()
+        };
+
+        Shared {
+            data     : Data::Multiple(data),
+            promoted : Promoted::new(),
+            collapse : AutoDowngrade::off(),
+
+            #[cfg(feature = "thread-tracking")]
+            seen     : self.seen.clone(),
+
+            #[cfg(all(feature = "strict-ordering", debug_assertions))]
+            threads  : self.threads.clone()
+        }
+    }
+}
+
+impl<T : AtomicRepr> From<Wrapping<T>> for Shared<T>
+{
+    //*********************************************************************************************
+    /// Construct a new instance of the object from a Wrapping value.
+    fn from(
+        value : Wrapping<T>
+        ) -> Shared<T>
+    {
+        Shared::new(value.0)
+    }
+}
+
+use core::fmt::{Binary, Debug, Display, Formatter, Error, LowerHex, Octal, UpperHex};
+use core::hash::{Hash, Hasher};
+use core::str::FromStr;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+impl<T : AtomicRepr + Debug> Debug for Shared<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Debug.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{:?}", self.get())
+    }
+}
+
+impl<T : AtomicRepr + Display> Display for Shared<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Display.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{}", self.get())
+    }
+}
+
+impl<T : AtomicRepr + LowerHex> LowerHex for Shared<T>
+{
+    //*********************************************************************************************
+    /// Implementation of LowerHex, formats the value from get() with the same flags.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        LowerHex::fmt(&self.get(), f)
+    }
+}
+
+impl<T : AtomicRepr + UpperHex> UpperHex for Shared<T>
+{
+    //*********************************************************************************************
+    /// Implementation of UpperHex, formats the value from get() with the same flags.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        UpperHex::fmt(&self.get(), f)
+    }
+}
+
+impl<T : AtomicRepr + Binary> Binary for Shared<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Binary, formats the value from get() with the same flags.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        Binary::fmt(&self.get(), f)
+    }
+}
+
+impl<T : AtomicRepr + Octal> Octal for Shared<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Octal, formats the value from get() with the same flags.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        Octal::fmt(&self.get(), f)
+    }
+}
+
+impl<T : AtomicRepr> PartialEq for Shared<T>
+{
+    //*********************************************************************************************
+    /// Implementation of PartialEq, compares the current values from get().
+    fn eq(
+        &self,
+        other : &Shared<T>
+        ) -> bool
+    {
+        self.get() == other.get()
+    }
+}
+
+impl<T : AtomicRepr> Eq for Shared<T> {}
+
+impl<T : AtomicRepr> PartialEq<T> for Shared<T>
+{
+    //*********************************************************************************************
+    /// Implementation of PartialEq against a plain value, compares the current value from get().
+    fn eq(
+        &self,
+        other : &T
+        ) -> bool
+    {
+        self.get() == *other
+    }
+}
+
+impl<T : AtomicRepr + Hash> Hash for Shared<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Hash, hashes the current value from get().  Changing the value of a key
+    /// in a map or set breaks the lookup, the same as with any interior mutable key.
+    fn hash<H : Hasher>(
+        &self,
+        state : &mut H
+        )
+    {
+        self.get().hash(state)
+    }
+}
+
+impl<T : AtomicRepr + FromStr> FromStr for Shared<T>
+{
+    type Err = T::Err;
+
+    //*********************************************************************************************
+    /// Implementation of FromStr, parses the value the same way T does.
+    fn from_str(
+        s : &str
+        ) -> Result<Shared<T>, Self::Err>
+    {
+        T::from_str(s).map(Shared::new)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T : AtomicRepr + Serialize> Serialize for Shared<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Serialize, writes the current value from get().
+    fn serialize<S : Serializer>(
+        &self,
+        serializer : S
+        ) -> Result<S::Ok, S::Error>
+    {
+        self.get().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T : AtomicRepr + Deserialize<'de>> Deserialize<'de> for Shared<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Deserialize, the loaded value starts out unshared.
+    fn deserialize<D : Deserializer<'de>>(
+        deserializer : D
+        ) -> Result<Shared<T>, D::Error>
+    {
+        T::deserialize(deserializer).map(Shared::new)
+    }
+}
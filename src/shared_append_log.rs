@@ -0,0 +1,231 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+use std::sync::{Arc, OnceLock, RwLock};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+//*************************************************************************************************
+/// Number of items saved in each chunk of the log.
+const CHUNK_SIZE : usize = 256;
+
+//*************************************************************************************************
+/// Internal data shared between all the instances.
+struct Log<T>
+{
+    //---------------------------------------------------------------------------------------------
+    /// Index of the next item to append.
+    next : AtomicUsize,
+
+    //---------------------------------------------------------------------------------------------
+    /// The chunks holding the items, the write lock is only taken to add a chunk.
+    chunks : RwLock<Vec<Box<[OnceLock<T>]>>>
+}
+
+//*************************************************************************************************
+/// Shareable log that items can only be appended to.
+///
+/// Each push() reserves a slot with an atomic increment and writes the item into it while holding
+/// a read lock, so appenders never wait for each other.  The slots are allocated in chunks of 256
+/// items, the write lock is only taken while a new chunk is added.  Items are never moved once
+/// written.
+///
+/// Like SharedBitset64 the log is always shared, cloning the object returns a new instance
+/// pointing at the same log.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use shareable::SharedAppendLog;
+///
+/// let log    = SharedAppendLog::new();
+/// let writer = log.clone();
+///
+/// thread::spawn(move || writer.push("started")).join().unwrap();
+///
+/// log.push("finished");
+///
+/// assert_eq!(log.snapshot(), ["started", "finished"]);
+/// ```
+pub struct SharedAppendLog<T>
+{
+    //---------------------------------------------------------------------------------------------
+    /// The internal data element.
+    data : Arc<Log<T>>
+}
+
+impl<T> SharedAppendLog<T>
+{
+    //********************************************************************************************
+    /// Construct a new empty log.
+    pub fn new() -> SharedAppendLog<T>
+    {
+        SharedAppendLog {
+            data : Arc::new(Log {
+                next   : AtomicUsize::new(0),
+                chunks : RwLock::new(Vec::new())
+            })
+        }
+    }
+
+    //********************************************************************************************
+    /// Appends an item to the log, returns the index of the item.
+    pub fn push(
+        &self,
+        item : T
+        ) -> usize
+    {
+        let index = self.data.next.fetch_add(1, Ordering::Relaxed);
+        let chunk = index / CHUNK_SIZE;
+
+        if self.data.chunks.read().unwrap().len() <= chunk
+        {
+            let mut chunks = self.data.chunks.write().unwrap();
+
+            while chunks.len() <= chunk
+            {
+                chunks.push((0..CHUNK_SIZE).map(|_| OnceLock::new()).collect());
+            }
+        }
+
+        let chunks = self.data.chunks.read().unwrap();
+
+        if chunks[chunk][index % CHUNK_SIZE].set(item).is_err()
+        {
+            unreachable!("the slot {} was written twice", index);
+        }
+
+        index
+    }
+
+    //********************************************************************************************
+    /// Returns the number of slots reserved by push().  This includes the items that are still
+    /// being written by other threads.
+    pub fn len(&self) -> usize
+    {
+        self.data.next.load(Ordering::Relaxed)
+    }
+
+    //********************************************************************************************
+    /// Returns true if nothing was pushed into the log.
+    pub fn is_empty(&self) -> bool
+    {
+        self.len() == 0
+    }
+}
+
+impl<T : Clone> SharedAppendLog<T>
+{
+    //********************************************************************************************
+    /// Returns a copy of the items in the log, in the order their slots were reserved.  An item
+    /// that another thread is still writing is not included, once all the push() calls have
+    /// returned every item is included.
+    pub fn snapshot(&self) -> Vec<T>
+    {
+        let len    = self.len();
+        let chunks = self.data.chunks.read().unwrap();
+
+        chunks.iter()
+            .flat_map(|chunk| chunk.iter())
+            .take(len)
+            .filter_map(|slot| slot.get().cloned())
+            .collect()
+    }
+}
+
+impl<T> Clone for SharedAppendLog<T>
+{
+    //*********************************************************************************************
+    /// Returns a new instance pointing at the same log.
+    fn clone(&self) -> SharedAppendLog<T>
+    {
+        SharedAppendLog { data : self.data.clone() }
+    }
+}
+
+impl<T> Default for SharedAppendLog<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Default.
+    fn default() -> SharedAppendLog<T>
+    {
+        SharedAppendLog::new()
+    }
+}
+
+use std::fmt::{Debug, Formatter, Error};
+
+impl<T : Clone + Debug> Debug for SharedAppendLog<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Debug.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{:?}", self.snapshot())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::thread;
+
+    //*********************************************************************************************
+    /// Test that no items are lost when many threads append at the same time.
+    #[test]
+    fn test_appenders()
+    {
+        let log         = super::SharedAppendLog::new();
+        let mut threads = Vec::new();
+
+        for t in 0..8
+        {
+            let writer = log.clone();
+
+            threads.push(thread::spawn(move || {
+                for i in 0..1000
+                {
+                    writer.push(t * 1000 + i);
+                }
+            }));
+        }
+
+        for thread in threads
+        {
+            thread.join().unwrap();
+        }
+
+        let mut items = log.snapshot();
+
+        assert_eq!(log.len(), 8000);
+
+        items.sort_unstable();
+
+        assert_eq!(items, (0..8000).collect::<Vec<_>>());
+    }
+
+    //*********************************************************************************************
+    /// Test that the items of a single appender keep their order.
+    #[test]
+    fn test_order()
+    {
+        let log = super::SharedAppendLog::new();
+
+        assert!(log.is_empty());
+
+        for i in 0..600
+        {
+            assert_eq!(log.push(i), i);
+        }
+
+        assert_eq!(log.snapshot(), (0..600).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,392 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+mod sealed
+{
+    //*********************************************************************************************
+    /// Prevents AtomicBackable from being implemented outside of the crate.
+    pub trait Sealed {}
+}
+
+//*************************************************************************************************
+/// Small Copy types that can be saved in an AtomicU64.
+///
+/// The trait is sealed, it is implemented for the primitive types, bool, char, and pairs of the
+/// 32 bit types.
+pub trait AtomicBackable : sealed::Sealed + Copy
+{
+    //*********************************************************************************************
+    /// Converts the value to the bits saved in the atomic.
+    #[doc(hidden)]
+    fn into_bits(self) -> u64;
+
+    //*********************************************************************************************
+    /// Converts the bits saved in the atomic back to the value.
+    #[doc(hidden)]
+    fn from_bits(bits : u64) -> Self;
+}
+
+macro_rules! atomic_backable_int {
+    ($($t:ty),*) => {$(
+        impl sealed::Sealed for $t {}
+
+        impl AtomicBackable for $t
+        {
+            fn into_bits(self) -> u64 { self as u64 }
+            fn from_bits(bits : u64) -> $t { bits as $t }
+        }
+    )*}
+}
+
+atomic_backable_int!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl sealed::Sealed for f32 {}
+
+impl AtomicBackable for f32
+{
+    fn into_bits(self) -> u64 { self.to_bits() as u64 }
+    fn from_bits(bits : u64) -> f32 { f32::from_bits(bits as u32) }
+}
+
+impl sealed::Sealed for f64 {}
+
+impl AtomicBackable for f64
+{
+    fn into_bits(self) -> u64 { self.to_bits() }
+    fn from_bits(bits : u64) -> f64 { f64::from_bits(bits) }
+}
+
+impl sealed::Sealed for bool {}
+
+impl AtomicBackable for bool
+{
+    fn into_bits(self) -> u64 { self as u64 }
+    fn from_bits(bits : u64) -> bool { bits != 0 }
+}
+
+impl sealed::Sealed for char {}
+
+impl AtomicBackable for char
+{
+    fn into_bits(self) -> u64 { self as u64 }
+    fn from_bits(bits : u64) -> char { char::from_u32(bits as u32).unwrap() }
+}
+
+macro_rules! atomic_backable_pair {
+    ($($t:ty),*) => {$(
+        impl sealed::Sealed for ($t, $t) {}
+
+        impl AtomicBackable for ($t, $t)
+        {
+            fn into_bits(self) -> u64
+            {
+                let low  = AtomicBackable::into_bits(self.0) & 0xFFFF_FFFF;
+                let high = AtomicBackable::into_bits(self.1) << 32;
+
+                low | high
+            }
+
+            fn from_bits(bits : u64) -> ($t, $t)
+            {
+                (
+                    <$t as AtomicBackable>::from_bits(bits & 0xFFFF_FFFF),
+                    <$t as AtomicBackable>::from_bits(bits >> 32)
+                )
+            }
+        }
+    )*}
+}
+
+atomic_backable_pair!(i32, u32, f32);
+
+//*************************************************************************************************
+/// Internal data structure that identifies how we are accessing the data.
+enum Data
+{
+    //---------------------------------------------------------------------------------------------
+    /// There is only 1 instance of the element.
+    Single(AtomicU64),
+
+    //---------------------------------------------------------------------------------------------
+    /// There are or were multiple instances of the element.
+    Multiple(Arc<AtomicU64>)
+}
+
+//*************************************************************************************************
+/// Shareable object data element for small Copy types.
+///
+/// This works like SharedObject, but the value is saved in an AtomicU64 instead of behind a
+/// Mutex, so reading and writing it never blocks and get() returns a copy of the value instead of
+/// an Arc.  Only the types implementing AtomicBackable can be used.
+///
+/// SharedObject can't switch to the atomic storage by itself based on the type without
+/// specialization, which is why this is a separate type instead of SharedObject<T> picking the
+/// storage for AtomicBackable types.  Code that needs a SharedObject<(u32, u32)> that never
+/// blocks uses SharedAtomicObject<(u32, u32)> instead.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use shareable::SharedAtomicObject;
+///
+/// let mut value1 = SharedAtomicObject::new((1u32, 2u32));
+/// let mut value2 = value1.dup();
+///
+/// let thread = thread::spawn(move || {
+///     value2.set((3, 4));
+/// });
+///
+/// thread.join().unwrap();
+///
+/// assert_eq!(value1.get(), (3, 4));
+/// ```
+pub struct SharedAtomicObject<T : AtomicBackable>
+{
+    //---------------------------------------------------------------------------------------------
+    /// The internal data element.
+    data : Data,
+
+    //---------------------------------------------------------------------------------------------
+    /// The type saved in the data element.
+    kind : PhantomData<T>
+}
+
+impl<T : AtomicBackable> SharedAtomicObject<T>
+{
+    //********************************************************************************************
+    /// Construct a new instance of the object.
+    pub fn new(
+        value : T
+        ) -> SharedAtomicObject<T>
+    {
+        SharedAtomicObject {
+            data : Data::Single(AtomicU64::new(value.into_bits())),
+            kind : PhantomData
+        }
+    }
+
+    //********************************************************************************************
+    /// Set the value of the object.
+    pub fn set(
+        &mut self,
+        val : T
+        )
+    {
+        match self.data
+        {
+            Data::Single(ref mut mem) => *mem.get_mut() = val.into_bits(),
+            Data::Multiple(ref mem)   => mem.store(val.into_bits(), Ordering::Relaxed)
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns the value of the object.
+    pub fn get(&self) -> T
+    {
+        T::from_bits(self.storage().load(Ordering::Relaxed))
+    }
+
+    //********************************************************************************************
+    /// Returns the value of the object.  The value is never locked so this always returns Some,
+    /// it exists so code written against SharedObject::try_get() works unchanged.
+    pub fn try_get(&self) -> Option<T>
+    {
+        Some(self.get())
+    }
+
+    //********************************************************************************************
+    /// Clones the object.  After this call all access to the data will be done via a shared
+    /// AtomicU64 element.
+    pub fn dup(&mut self) -> SharedAtomicObject<T>
+    {
+        let data = match self.data
+        {
+            Data::Single(ref mem)   => Arc::new(AtomicU64::new(mem.load(Ordering::Relaxed))),
+            Data::Multiple(ref val) => val.clone()
+        };
+
+        self.data = Data::Multiple(data.clone());
+
+        SharedAtomicObject { data : Data::Multiple(data), kind : PhantomData }
+    }
+
+    //********************************************************************************************
+    /// Returns the atomic holding the value.
+    fn storage(&self) -> &AtomicU64
+    {
+        match self.data
+        {
+            Data::Single(ref mem)   => mem,
+            Data::Multiple(ref mem) => mem
+        }
+    }
+
+    //********************************************************************************************
+    /// Shares the object, this is the same as dup().  The returned object is linked to this one,
+    /// setting the value on either object is seen by both.
+    pub fn share(&mut self) -> SharedAtomicObject<T>
+    {
+        self.dup()
+    }
+
+    //********************************************************************************************
+    /// Returns a new object holding the current value.  Unlike share() the returned object is
+    /// independent of this one, setting the value on either object is not seen by the other.
+    pub fn snapshot(&self) -> SharedAtomicObject<T>
+    {
+        SharedAtomicObject::new(self.get())
+    }
+
+    //********************************************************************************************
+    /// Returns true if the value is in the shared storage, which is the case once dup() has been
+    /// called.
+    pub fn is_shared(&self) -> bool
+    {
+        matches!(self.data, Data::Multiple(_))
+    }
+}
+
+use std::fmt::{Debug, Display, Formatter, Error};
+
+impl<T : AtomicBackable + Debug> Debug for SharedAtomicObject<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Debug.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{:?}", self.get())
+    }
+}
+
+impl<T : AtomicBackable + Display> Display for SharedAtomicObject<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Display.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{}", self.get())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::thread;
+
+    //*********************************************************************************************
+    /// Test that get/set work with only 1 instance.
+    #[test]
+    fn test_single()
+    {
+        let mut test = super::SharedAtomicObject::new((-1i32, 7i32));
+
+        assert_eq!(test.get(), (-1, 7));
+        test.set((i32::MIN, i32::MAX));
+        assert_eq!(test.get(), (i32::MIN, i32::MAX));
+
+        let mut test = super::SharedAtomicObject::new('x');
+
+        test.set('\u{1F600}');
+        assert_eq!(test.get(), '\u{1F600}');
+
+        let test = super::SharedAtomicObject::new((-0.5f32, 2.5f32));
+
+        assert_eq!(test.get(), (-0.5, 2.5));
+    }
+
+    //*********************************************************************************************
+    /// Test that get/set work with multiple instances.
+    #[test]
+    fn test_multiple()
+    {
+        let mut test1 = super::SharedAtomicObject::new(-5i64);
+        let mut test2 = test1.dup();
+        let test3     = test1.snapshot();
+
+        test2.set(i64::MIN);
+
+        assert_eq!(test1.get(), i64::MIN);
+        assert_eq!(test3.get(), -5);
+    }
+
+    //*********************************************************************************************
+    /// Test that an 8 byte pair is never torn and try_get() never fails while another thread
+    /// writes it, at the same time as SharedObject::try_get() fails because that thread holds
+    /// the SharedObject write lock.
+    #[test]
+    fn test_lock_free()
+    {
+        use std::sync::mpsc;
+        use SharedObject;
+
+        let mut test        = super::SharedAtomicObject::new((0u32, 0u32));
+        let mut writer      = test.dup();
+        let mut object      = SharedObject::new((0u32, 0u32));
+        let mut locker      = object.dup();
+        let (locked, ready) = mpsc::channel();
+        let (finish, done)  = mpsc::channel();
+
+        let thread = thread::spawn(move || {
+            locker.update(|val| {
+                locked.send(()).unwrap();
+
+                for i in 0..100000
+                {
+                    writer.set((i, i));
+                }
+
+                done.recv().unwrap();
+
+                *val
+            });
+        });
+
+        ready.recv().unwrap();
+
+        assert!(object.try_get().is_none());
+
+        for _ in 0..100000
+        {
+            let (a, b) = test.try_get().unwrap();
+
+            assert_eq!(a, b);
+        }
+
+        finish.send(()).unwrap();
+        thread.join().unwrap();
+
+        assert_eq!(object.try_get().map(|val| *val), Some((0, 0)));
+        assert_eq!(test.get(), (99999, 99999));
+    }
+
+    //*********************************************************************************************
+    /// Test that is_shared() is false until dup() is called.
+    #[test]
+    fn test_is_shared()
+    {
+        let mut test1 = super::SharedAtomicObject::new(5.0f32);
+
+        assert!(!test1.is_shared());
+
+        let test2 = test1.dup();
+
+        assert!(test1.is_shared());
+        assert!(test2.is_shared());
+    }
+}
@@ -0,0 +1,223 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+//*************************************************************************************************
+/// Shareable set of 64 bits.
+///
+/// Unlike the other shareable elements the bitset is always saved in an AtomicU64, it is intended
+/// for tracking state between threads (for example which workers are active) so it starts out
+/// shared.  Cloning the object returns a new instance pointing at the same bits.
+///
+/// Bit indexes must be less than 64, the methods panic if they are not.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use shareable::SharedBitset64;
+///
+/// let active = SharedBitset64::new(0);
+/// let worker = active.clone();
+///
+/// let thread = thread::spawn(move || {
+///     worker.set_bit(3);
+/// });
+///
+/// thread.join().unwrap();
+///
+/// assert!(active.test_bit(3));
+/// assert_eq!(active.count_ones(), 1);
+/// ```
+#[derive(Clone)]
+pub struct SharedBitset64
+{
+    //---------------------------------------------------------------------------------------------
+    /// The internal data element.
+    data : Arc<AtomicU64>
+}
+
+impl SharedBitset64
+{
+    //********************************************************************************************
+    /// Construct a new instance of the object.
+    pub fn new(
+        bits : u64
+        ) -> SharedBitset64
+    {
+        SharedBitset64 {
+            data : Arc::new(AtomicU64::new(bits))
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns all the bits.
+    pub fn get(&self) -> u64
+    {
+        self.data.load(Ordering::Relaxed)
+    }
+
+    //********************************************************************************************
+    /// Sets the bit at the index, returns the previous state of the bit.
+    pub fn set_bit(
+        &self,
+        index : u32
+        ) -> bool
+    {
+        let mask = SharedBitset64::mask(index);
+
+        self.data.fetch_or(mask, Ordering::Relaxed) & mask != 0
+    }
+
+    //********************************************************************************************
+    /// Clears the bit at the index, returns the previous state of the bit.
+    pub fn clear_bit(
+        &self,
+        index : u32
+        ) -> bool
+    {
+        let mask = SharedBitset64::mask(index);
+
+        self.data.fetch_and(!mask, Ordering::Relaxed) & mask != 0
+    }
+
+    //********************************************************************************************
+    /// Returns the state of the bit at the index.
+    pub fn test_bit(
+        &self,
+        index : u32
+        ) -> bool
+    {
+        self.get() & SharedBitset64::mask(index) != 0
+    }
+
+    //********************************************************************************************
+    /// Returns the number of bits that are set.
+    pub fn count_ones(&self) -> u32
+    {
+        self.get().count_ones()
+    }
+
+    //********************************************************************************************
+    /// Replaces all the bits with new if they are currently equal to current.  Returns the
+    /// previous bits on success or the actual bits on failure.
+    pub fn compare_exchange(
+        &self,
+        current : u64,
+        new     : u64
+        ) -> Result<u64, u64>
+    {
+        self.data.compare_exchange(current, new, Ordering::Relaxed, Ordering::Relaxed)
+    }
+
+    //********************************************************************************************
+    /// Returns the mask for the bit at the index.
+    fn mask(index : u32) -> u64
+    {
+        assert!(index < 64, "bit index {} is out of range", index);
+
+        1 << index
+    }
+}
+
+use std::fmt::{Debug, Display, Formatter, Error};
+
+impl Debug for SharedBitset64
+{
+    //*********************************************************************************************
+    /// Implementation of Debug.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{:#066b}", self.get())
+    }
+}
+
+impl Display for SharedBitset64
+{
+    //*********************************************************************************************
+    /// Implementation of Display.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{:064b}", self.get())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::thread;
+
+    //*********************************************************************************************
+    /// Test setting, clearing and testing bits.
+    #[test]
+    fn test_bits()
+    {
+        let test = super::SharedBitset64::new(0b101);
+
+        assert!(test.test_bit(0));
+        assert!(!test.test_bit(1));
+        assert!(test.test_bit(2));
+
+        assert!(!test.set_bit(63));
+        assert!(test.set_bit(63));
+        assert!(test.clear_bit(0));
+        assert!(!test.clear_bit(0));
+
+        assert_eq!(test.get(), (1 << 63) | 0b100);
+        assert_eq!(test.count_ones(), 2);
+    }
+
+    //*********************************************************************************************
+    /// Test compare_exchange on the whole word.
+    #[test]
+    fn test_compare_exchange()
+    {
+        let test = super::SharedBitset64::new(0b11);
+
+        assert_eq!(test.compare_exchange(0b01, 0b10), Err(0b11));
+        assert_eq!(test.compare_exchange(0b11, 0b10), Ok(0b11));
+        assert_eq!(test.get(), 0b10);
+    }
+
+    //*********************************************************************************************
+    /// Test setting and clearing bits from multiple threads.
+    #[test]
+    fn test_threads()
+    {
+        let test = super::SharedBitset64::new(0);
+
+        let threads : Vec<_> = (0..64).map(|index| {
+            let bits = test.clone();
+
+            thread::spawn(move || {
+                bits.set_bit(index);
+
+                if index % 2 == 1
+                {
+                    bits.clear_bit(index);
+                }
+            })
+        }).collect();
+
+        for thread in threads
+        {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(test.count_ones(), 32);
+        assert_eq!(test.get(), 0x5555_5555_5555_5555);
+    }
+}
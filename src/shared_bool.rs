@@ -0,0 +1,667 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use auto_downgrade::AutoDowngrade;
+use promoted::Promoted;
+use test_hooks::{self, Point};
+#[cfg(all(feature = "strict-ordering", debug_assertions))]
+use strict_ordering::ThreadTracker;
+#[cfg(feature = "thread-tracking")]
+use thread_tracking::ThreadSet;
+
+//*************************************************************************************************
+/// Internal data structure that identifies how we are accessing the data.
+enum Data
+{
+    //---------------------------------------------------------------------------------------------
+    /// There is only 1 instance of the element.
+    Single(bool),
+
+    //---------------------------------------------------------------------------------------------
+    /// There are or were multiple instances of the element.
+    Multiple(Arc<AtomicBool>)
+}
+
+//*************************************************************************************************
+/// Shareable bool data element.
+///
+/// If only 1 instance of the element is needed then that data is just saved as a normal memory
+/// location.  If multiple instances are needed then the value is saved in an AtomicBool so it
+/// can be safely shared between threads.  The fetch methods update the value through a shared
+/// reference, so the first of them called on an unshared object moves the value into an
+/// AtomicBool the same way clone() does.
+///
+/// # Examples
+///
+/// ```
+/// use shareable::SharedBool;
+///
+/// // Single thread, no expensive structures used.
+/// let mut value1 = SharedBool::new(false);
+///
+/// println!("Value: {}", value1.get());
+///
+/// value1.set(true);
+///
+/// println!("Value: {}", value1.get());
+/// ```
+///
+/// ```
+/// # #[cfg(not(all(feature = "strict-ordering", debug_assertions)))]
+/// # {
+/// use std::sync::mpsc;
+/// use std::thread;
+/// use shareable::SharedBool;
+///
+/// // Multiple threads, atomic values are used.
+/// let mut value1 = SharedBool::new(false);
+/// let mut value2 = value1.dup();
+///
+/// let (tx, rx) = mpsc::channel();
+///
+/// let thread = thread::spawn(move || {
+///     rx.recv();
+///     assert!(value2.get());
+/// });
+///
+/// value1.set(true);
+///
+/// tx.send(());
+/// thread.join().unwrap();
+/// # }
+/// ```
+pub struct SharedBool
+{
+    //---------------------------------------------------------------------------------------------
+    /// The internal data element.
+    data     : Data,
+
+    //---------------------------------------------------------------------------------------------
+    /// The shared storage created by clone() while the object was unshared, the object switches
+    /// to it the next time it has mutable access.
+    promoted : Promoted<AtomicBool>,
+
+    //---------------------------------------------------------------------------------------------
+    /// Counts the calls to set() in auto downgrade mode, see set_auto_downgrade().
+    collapse : AutoDowngrade,
+
+    //---------------------------------------------------------------------------------------------
+    /// The threads that called get() or set(), shared with the other instances.  Only with the
+    /// thread-tracking feature.
+    #[cfg(feature = "thread-tracking")]
+    seen     : ThreadSet,
+
+    //---------------------------------------------------------------------------------------------
+    /// The threads that accessed the shared value, only with the strict-ordering feature in debug
+    /// builds.
+    #[cfg(all(feature = "strict-ordering", debug_assertions))]
+    threads  : ThreadTracker
+}
+
+impl SharedBool
+{
+    //********************************************************************************************
+    /// Construct a new instance of the object.
+    pub fn new(
+        value : bool
+        ) -> SharedBool
+    {
+        SharedBool {
+            data     : Data::Single(value),
+            promoted : Promoted::new(),
+            collapse : AutoDowngrade::off(),
+
+            #[cfg(feature = "thread-tracking")]
+            seen     : ThreadSet::new(),
+
+            #[cfg(all(feature = "strict-ordering", debug_assertions))]
+            threads  : ThreadTracker::new()
+        }
+    }
+
+    //********************************************************************************************
+    /// Set the value of the object.
+    pub fn set(
+        &mut self,
+        val : bool
+        )
+    {
+        self.set_with(val, Ordering::Relaxed);
+    }
+
+    //********************************************************************************************
+    /// Set the value, storing it with the given memory ordering.  set() uses Relaxed, pass
+    /// Release or SeqCst when the flag guards other data.  Until the object is shared no other
+    /// thread can see it, so the ordering only matters after dup().  Once it is shared, Acquire
+    /// and AcqRel panic like they do with AtomicBool::store().
+    pub fn set_with(
+        &mut self,
+        val   : bool,
+        order : Ordering
+        )
+    {
+        self.settle();
+
+        #[cfg(feature = "thread-tracking")]
+        self.seen.record();
+
+        if self.collapse.tick()
+        {
+            self.try_downgrade();
+        }
+
+        #[cfg(all(feature = "strict-ordering", debug_assertions))]
+        self.threads.check(self.is_shared(), order);
+
+        match self.data
+        {
+            Data::Single(ref mut mem) => *mem = val,
+            Data::Multiple(ref mem)   => mem.store(val, order)
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns the value of the object.
+    pub fn get(&self) -> bool
+    {
+        self.get_with(Ordering::Relaxed)
+    }
+
+    //********************************************************************************************
+    /// Returns the value, loading it with the given memory ordering.  get() uses Relaxed, pass
+    /// Acquire or SeqCst when the flag guards other data.  Panics if order is Release or AcqRel,
+    /// like AtomicBool::load().
+    pub fn get_with(
+        &self,
+        order : Ordering
+        ) -> bool
+    {
+        #[cfg(all(feature = "strict-ordering", debug_assertions))]
+        self.threads.check(self.is_shared(), order);
+
+        #[cfg(feature = "thread-tracking")]
+        self.seen.record();
+
+        match self.data
+        {
+            Data::Single(val)       => self.promoted.get().map_or(val, |mem| mem.load(order)),
+            Data::Multiple(ref mem) => mem.load(order)
+        }
+    }
+
+    //********************************************************************************************
+    /// Clones the object, this is the same as clone().  After this call all access to the data
+    /// will be done via a shared AtomicBool element.
+    pub fn dup(&mut self) -> SharedBool
+    {
+        let copy = self.clone();
+
+        self.settle();
+
+        copy
+    }
+
+    //********************************************************************************************
+    /// Returns the atomic holding the value.  An unshared value is moved into a new atomic first,
+    /// like clone() does, so it can be updated through a shared reference.
+    fn storage(&self) -> &AtomicBool
+    {
+        match self.data
+        {
+            Data::Single(val)       => {
+                self.promoted.get_or_init(|| Arc::new(AtomicBool::new(val)))
+            },
+            Data::Multiple(ref mem) => mem
+        }
+    }
+
+    //********************************************************************************************
+    /// Switches to the shared storage created by clone(), if there is one.
+    fn settle(&mut self)
+    {
+        if let Some(mem) = self.promoted.take()
+        {
+            self.data = Data::Multiple(mem);
+        }
+    }
+
+    //********************************************************************************************
+    /// Shares the object, this is the same as dup().  The returned object is linked to this one,
+    /// setting the value on either object is seen by both.
+    pub fn share(&mut self) -> SharedBool
+    {
+        self.dup()
+    }
+
+    //********************************************************************************************
+    /// Returns a new object holding the current value.  Unlike share() the returned object is
+    /// independent of this one, setting the value on either object is not seen by the other.
+    pub fn snapshot(&self) -> SharedBool
+    {
+        SharedBool::new(self.get())
+    }
+
+    //********************************************************************************************
+    /// Returns true if no other objects are sharing the data.  This is always true before dup()
+    /// is called, and becomes true again once all the other objects have been dropped.
+    pub fn is_uniquely_owned(&self) -> bool
+    {
+        match self.data
+        {
+            Data::Single(_)         => {
+                self.promoted.get().is_none_or(|mem| Arc::strong_count(mem) == 1)
+            },
+            Data::Multiple(ref mem) => Arc::strong_count(mem) == 1
+        }
+    }
+
+    //********************************************************************************************
+    /// Consumes the object and returns the value if no other objects are sharing the data, or
+    /// hands the object back in Err if they are.  The value is copied out of the storage, so
+    /// unlike SharedObject::try_into_inner() there is no unwrapping involved, this only checks
+    /// that the value isn't being lost to the other objects.
+    pub fn try_into_inner(self) -> Result<bool, SharedBool>
+    {
+        if !self.is_uniquely_owned()
+        {
+            return Err(self);
+        }
+
+        Ok(self.get_with(Ordering::Acquire))
+    }
+
+    //********************************************************************************************
+    /// Returns true if the value is in the shared storage, which is the case once dup() or one of
+    /// the fetch methods has been called.
+    pub fn is_shared(&self) -> bool
+    {
+        matches!(self.data, Data::Multiple(_)) || self.promoted.get().is_some()
+    }
+
+    //********************************************************************************************
+    /// Returns the number of distinct threads that have called get() or set() on this object or
+    /// the objects sharing the data with it.  Only available with the thread-tracking feature.
+    #[cfg(feature = "thread-tracking")]
+    pub fn accessing_threads(&self) -> usize
+    {
+        self.seen.count()
+    }
+
+    //********************************************************************************************
+    /// Moves the value back out of the shared storage if no other objects are sharing it anymore,
+    /// so access is as cheap as before dup().  Returns true if the object is unshared after the
+    /// call, or false if other objects are still sharing the data.
+    pub fn try_downgrade(&mut self) -> bool
+    {
+        self.settle();
+
+        if let Data::Multiple(ref mut mem) = self.data
+        {
+            let val = match Arc::get_mut(mem)
+            {
+                Some(mem) => *mem.get_mut(),
+                None      => return false
+            };
+
+            self.data = Data::Single(val);
+
+            #[cfg(all(feature = "strict-ordering", debug_assertions))]
+            {
+                self.threads = ThreadTracker::new();
+            }
+        }
+
+        true
+    }
+
+    //********************************************************************************************
+    /// Turns auto downgrade mode on or off.  In this mode every 64th call to set() checks whether
+    /// the other objects sharing the data are gone, and if they are moves the value back out of
+    /// the shared storage like try_downgrade().  The check is an Arc::get_mut(), the other calls
+    /// only count.  Objects created by dup() start with the mode off.
+    pub fn set_auto_downgrade(
+        &mut self,
+        on : bool
+        )
+    {
+        self.collapse.enable(on);
+    }
+
+    //********************************************************************************************
+    /// Returns the atomic holding the value while the object is shared, or None while the value
+    /// is stored in the object itself.  The atomic is the one every object sharing the data uses.
+    pub fn as_atomic(&self) -> Option<&AtomicBool>
+    {
+        match self.data
+        {
+            Data::Single(_)         => self.promoted.get().map(|mem| &**mem),
+            Data::Multiple(ref mem) => Some(mem)
+        }
+    }
+
+    //********************************************************************************************
+    /// Logical and of the value with val, returns the previous value.
+    pub fn fetch_and(
+        &self,
+        val : bool
+        ) -> bool
+    {
+        self.storage().fetch_and(val, Ordering::Relaxed)
+    }
+
+    //********************************************************************************************
+    /// Logical or of the value with val, returns the previous value.
+    pub fn fetch_or(
+        &self,
+        val : bool
+        ) -> bool
+    {
+        self.storage().fetch_or(val, Ordering::Relaxed)
+    }
+
+    //********************************************************************************************
+    /// Logical xor of the value with val, returns the previous value.
+    pub fn fetch_xor(
+        &self,
+        val : bool
+        ) -> bool
+    {
+        self.storage().fetch_xor(val, Ordering::Relaxed)
+    }
+
+    //********************************************************************************************
+    /// Flips the value and returns the previous value.  Every call from every thread flips it
+    /// exactly once, so n calls leave the value flipped if n is odd.
+    pub fn toggle(&self) -> bool
+    {
+        self.fetch_xor(true)
+    }
+}
+
+impl Clone for SharedBool
+{
+    //*********************************************************************************************
+    /// Implementation of Clone, the same as dup() except that it only needs a shared reference.
+    ///
+    /// Cloning an unshared object copies the value into shared storage, and this object switches
+    /// to that storage.  The value of an unshared object only changes through a mutable reference,
+    /// the fetch methods switch to the same storage first, so no update is lost while the clone is
+    /// being made.
+    fn clone(&self) -> SharedBool
+    {
+        let data = match self.data
+        {
+            Data::Single(val)       => self.promoted.get_or_init(|| {
+                let data = Arc::new(AtomicBool::new(val));
+
+                test_hooks::hit(Point::Dup);
+
+                data
+            }).clone(),
+            Data::Multiple(ref mem) => mem.clone()
+        };
+
+        SharedBool {
+            data     : Data::Multiple(data),
+            promoted : Promoted::new(),
+            collapse : AutoDowngrade::off(),
+
+            #[cfg(feature = "thread-tracking")]
+            seen     : self.seen.clone(),
+
+            #[cfg(all(feature = "strict-ordering", debug_assertions))]
+            threads  : self.threads.clone()
+        }
+    }
+}
+
+impl From<AtomicBool> for SharedBool
+{
+    //*********************************************************************************************
+    /// Construct a new, unshared instance of the object from the value of an atomic.
+    fn from(
+        value : AtomicBool
+        ) -> SharedBool
+    {
+        SharedBool::new(value.into_inner())
+    }
+}
+
+impl From<SharedBool> for AtomicBool
+{
+    //*********************************************************************************************
+    /// Construct a new atomic holding the current value of the object.
+    fn from(
+        value : SharedBool
+        ) -> AtomicBool
+    {
+        AtomicBool::new(value.get())
+    }
+}
+
+use core::fmt::{Debug, Display, Formatter, Error};
+use core::hash::{Hash, Hasher};
+use core::str::FromStr;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+impl Debug for SharedBool
+{
+    //*********************************************************************************************
+    /// Implementation of Debug.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{:?}", self.get())
+    }
+}
+
+impl Display for SharedBool
+{
+    //*********************************************************************************************
+    /// Implementation of Display.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{}", self.get())
+    }
+}
+
+impl PartialEq for SharedBool
+{
+    //*********************************************************************************************
+    /// Implementation of PartialEq, compares the current values from get().
+    fn eq(
+        &self,
+        other : &SharedBool
+        ) -> bool
+    {
+        self.get() == other.get()
+    }
+}
+
+impl Eq for SharedBool {}
+
+impl PartialEq<bool> for SharedBool
+{
+    //*********************************************************************************************
+    /// Implementation of PartialEq against a plain bool, compares the current value from get().
+    fn eq(
+        &self,
+        other : &bool
+        ) -> bool
+    {
+        self.get() == *other
+    }
+}
+
+impl Hash for SharedBool
+{
+    //*********************************************************************************************
+    /// Implementation of Hash, hashes the current value from get().  Changing the value of a key
+    /// in a map or set breaks the lookup, the same as with any interior mutable key.
+    fn hash<H : Hasher>(
+        &self,
+        state : &mut H
+        )
+    {
+        self.get().hash(state)
+    }
+}
+
+impl FromStr for SharedBool
+{
+    type Err = <bool as FromStr>::Err;
+
+    //*********************************************************************************************
+    /// Implementation of FromStr, parses the value the same way bool does.
+    fn from_str(
+        s : &str
+        ) -> Result<SharedBool, Self::Err>
+    {
+        bool::from_str(s).map(SharedBool::new)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for SharedBool
+{
+    //*********************************************************************************************
+    /// Implementation of Serialize, writes the current value from get().
+    fn serialize<S : Serializer>(
+        &self,
+        serializer : S
+        ) -> Result<S::Ok, S::Error>
+    {
+        self.get().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for SharedBool
+{
+    //*********************************************************************************************
+    /// Implementation of Deserialize, the loaded value starts out unshared.
+    fn deserialize<D : Deserializer<'de>>(
+        deserializer : D
+        ) -> Result<SharedBool, D::Error>
+    {
+        bool::deserialize(deserializer).map(SharedBool::new)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::thread;
+
+    //*********************************************************************************************
+    /// Test that get/set work with only 1 instance.
+    #[test]
+    fn test_single()
+    {
+        let mut test = super::SharedBool::new(false);
+
+        assert!(!test.get());
+        test.set(true);
+        assert!(test.get());
+    }
+
+    //*********************************************************************************************
+    /// Test that get/set work with multiple instances.
+    #[test]
+    fn test_multiple()
+    {
+        let mut test1 = super::SharedBool::new(false);
+        let mut test2 = test1.dup();
+
+        test1.set(true);
+        assert!(test2.get());
+
+        test2.set(false);
+        assert!(!test1.get());
+    }
+
+    //*********************************************************************************************
+    /// Test that toggle() flips the value and returns the previous value in both storage states.
+    #[test]
+    fn test_toggle()
+    {
+        let mut test1 = super::SharedBool::new(false);
+
+        assert!(!test1.toggle());
+        assert!(test1.toggle());
+        assert!(!test1.get());
+
+        let test2 = test1.dup();
+
+        assert!(!test2.toggle());
+        assert!(test1.get());
+        assert!(test1.toggle());
+        assert!(!test2.get());
+    }
+
+    //*********************************************************************************************
+    /// Test the logical operations.
+    #[test]
+    fn test_fetch_logic()
+    {
+        let test = super::SharedBool::new(true);
+
+        assert!(test.fetch_and(false));
+        assert!(!test.fetch_or(true));
+        assert!(test.fetch_xor(true));
+        assert!(!test.get());
+    }
+
+    //*********************************************************************************************
+    /// Test that toggles from several threads are all applied.
+    #[test]
+    fn test_threads()
+    {
+        let mut test    = super::SharedBool::new(false);
+        let mut threads = Vec::new();
+
+        for _ in 0..3
+        {
+            let shared = test.dup();
+
+            threads.push(thread::spawn(move || {
+                for _ in 0..1001
+                {
+                    shared.toggle();
+                }
+            }));
+        }
+
+        for thread in threads
+        {
+            thread.join().unwrap();
+        }
+
+        assert!(test.get());
+    }
+
+    //*********************************************************************************************
+    /// Test Debug, Display, and FromStr.
+    #[test]
+    fn test_format()
+    {
+        let test = "true".parse::<super::SharedBool>().unwrap();
+
+        assert_eq!(format!("{:?} {}", test, test), "true true");
+        assert!("yes".parse::<super::SharedBool>().is_err());
+    }
+}
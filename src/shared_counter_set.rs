@@ -0,0 +1,259 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+//*************************************************************************************************
+/// Shareable set of N u64 counters that can be read and reset together.
+///
+/// All the counters are saved in one array behind a Mutex, so drain() resets every counter in a
+/// single critical section and the returned values are always consistent with each other.  This
+/// is the alternative to SharedU64::drain_all() when the counters must be flushed as one batch.
+///
+/// Like SharedBitset64 the set is always shared, cloning the object returns a new instance
+/// pointing at the same counters.  Counter indexes must be less than N, the methods panic if
+/// they are not.
+///
+/// # Examples
+///
+/// ```
+/// use shareable::SharedCounterSet;
+///
+/// let metrics = SharedCounterSet::<3>::new();
+/// let worker  = metrics.clone();
+///
+/// worker.add(0, 5);
+/// worker.add(2, 1);
+///
+/// assert_eq!(metrics.drain(), [5, 0, 1]);
+/// assert_eq!(metrics.get(0), 0);
+/// ```
+#[derive(Clone)]
+pub struct SharedCounterSet<const N : usize>
+{
+    //---------------------------------------------------------------------------------------------
+    /// The internal data element.
+    data : Arc<Mutex<[u64; N]>>
+}
+
+impl<const N : usize> SharedCounterSet<N>
+{
+    //********************************************************************************************
+    /// Construct a new instance of the object with all the counters at zero.
+    pub fn new() -> SharedCounterSet<N>
+    {
+        SharedCounterSet {
+            data : Arc::new(Mutex::new([0; N]))
+        }
+    }
+
+    //********************************************************************************************
+    /// Adds to the counter at the index (wrapping on overflow), returns the previous value.
+    pub fn add(
+        &self,
+        index : usize,
+        val   : u64
+        ) -> u64
+    {
+        let mut data = self.data.lock().unwrap();
+        let old      = data[index];
+
+        data[index] = old.wrapping_add(val);
+
+        old
+    }
+
+    //********************************************************************************************
+    /// Returns the value of the counter at the index.
+    pub fn get(
+        &self,
+        index : usize
+        ) -> u64
+    {
+        self.data.lock().unwrap()[index]
+    }
+
+    //********************************************************************************************
+    /// Returns the values of all the counters.
+    pub fn values(&self) -> [u64; N]
+    {
+        *self.data.lock().unwrap()
+    }
+
+    //********************************************************************************************
+    /// Resets all the counters to zero and returns the values they held.
+    pub fn drain(&self) -> [u64; N]
+    {
+        let mut data = self.data.lock().unwrap();
+
+        mem::replace(&mut *data, [0; N])
+    }
+
+    //********************************************************************************************
+    /// Locks the counters and returns a guard with mutable access to all of them.
+    ///
+    /// The Mutex is held until the guard is dropped, every change made through the guard is seen
+    /// by the other instances as a single update.  Any other call on the set, from any instance,
+    /// blocks until then, including calls on the current thread, so don't call methods on the
+    /// set while holding the guard.
+    pub fn lock_all(&self) -> CounterSetGuard<'_, N>
+    {
+        CounterSetGuard {
+            lock : self.data.lock().unwrap()
+        }
+    }
+}
+
+//*************************************************************************************************
+/// Guard returned by SharedCounterSet::lock_all(), dereferences to the array of counters.
+pub struct CounterSetGuard<'a, const N : usize>
+{
+    //---------------------------------------------------------------------------------------------
+    /// The lock on the counters.
+    lock : MutexGuard<'a, [u64; N]>
+}
+
+impl<'a, const N : usize> Deref for CounterSetGuard<'a, N>
+{
+    type Target = [u64; N];
+
+    //*********************************************************************************************
+    /// Implementation of Deref.
+    fn deref(&self) -> &[u64; N]
+    {
+        &self.lock
+    }
+}
+
+impl<'a, const N : usize> DerefMut for CounterSetGuard<'a, N>
+{
+    //*********************************************************************************************
+    /// Implementation of DerefMut.
+    fn deref_mut(&mut self) -> &mut [u64; N]
+    {
+        &mut self.lock
+    }
+}
+
+impl<const N : usize> Default for SharedCounterSet<N>
+{
+    //*********************************************************************************************
+    /// Implementation of Default.
+    fn default() -> SharedCounterSet<N>
+    {
+        SharedCounterSet::new()
+    }
+}
+
+use std::fmt::{Debug, Formatter, Error};
+
+impl<const N : usize> Debug for SharedCounterSet<N>
+{
+    //*********************************************************************************************
+    /// Implementation of Debug.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{:?}", self.values())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::thread;
+
+    //*********************************************************************************************
+    /// Test adding, reading and draining the counters.
+    #[test]
+    fn test_counters()
+    {
+        let test = super::SharedCounterSet::<2>::new();
+
+        assert_eq!(test.add(0, 79), 0);
+        assert_eq!(test.add(0, 1), 79);
+        assert_eq!(test.add(1, u64::MAX), 0);
+        assert_eq!(test.add(1, 2), u64::MAX);
+
+        assert_eq!(test.values(), [80, 1]);
+        assert_eq!(test.drain(), [80, 1]);
+        assert_eq!(test.values(), [0, 0]);
+    }
+
+    //*********************************************************************************************
+    /// Test that a drain never splits a batch of updates made under the lock by other threads.
+    #[test]
+    fn test_drain_consistent()
+    {
+        let test   = super::SharedCounterSet::<2>::new();
+        let writer = test.clone();
+
+        let thread = thread::spawn(move || {
+            for _ in 0..10000
+            {
+                let mut data = writer.data.lock().unwrap();
+
+                data[0] += 1;
+                data[1] += 1;
+            }
+        });
+
+        let mut totals = [0, 0];
+
+        for _ in 0..1000
+        {
+            let drained = test.drain();
+
+            assert_eq!(drained[0], drained[1]);
+
+            totals[0] += drained[0];
+            totals[1] += drained[1];
+        }
+
+        thread.join().unwrap();
+
+        let drained = test.drain();
+
+        assert_eq!(totals[0] + drained[0], 10000);
+        assert_eq!(totals[1] + drained[1], 10000);
+    }
+
+    //*********************************************************************************************
+    /// Test that a reader never sees half of an update made through lock_all().
+    #[test]
+    fn test_lock_all()
+    {
+        let test   = super::SharedCounterSet::<2>::new();
+        let writer = test.clone();
+
+        let thread = thread::spawn(move || {
+            for i in 1..10001
+            {
+                let mut data = writer.lock_all();
+
+                data[0] = i;
+                data[1] = i * 2;
+            }
+        });
+
+        for _ in 0..1000
+        {
+            let values = test.values();
+
+            assert_eq!(values[0] * 2, values[1]);
+        }
+
+        thread.join().unwrap();
+
+        assert_eq!(test.values(), [10000, 20000]);
+    }
+}
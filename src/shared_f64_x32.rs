@@ -6,7 +6,17 @@
  * option. This file may not be copied, modified, or distributed
  * except according to those terms.
  */
-use std::sync::{Arc, Mutex};
+use std::cmp;
+use std::mem;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::sync::atomic::Ordering;
+use FrozenSharedF64;
+use Poisoned;
+use auto_downgrade::AutoDowngrade;
+use no_block;
+use scope_guard::ScopeGuard;
+#[cfg(feature = "thread-tracking")]
+use thread_tracking::ThreadSet;
 
 //*************************************************************************************************
 /// Internal data structure that identifies how we are accessing the data.
@@ -25,9 +35,11 @@ enum Data
 /// Shareable f64 data element.
 ///
 /// If only 1 instance of the element is needed then that data is just saved as a normal memory
-/// location.  If multiple instances are needed then the value is saved in an AtomicIsize if the
-/// application is 64 bit or a Mutex if the application is 32 bit so it can be safely shared
-/// between threads.
+/// location.  If multiple instances are needed then the value is saved in a Mutex so it can be
+/// safely shared between threads.  update_ema() updates the value through a shared reference, so
+/// calling it on an unshared object moves the value into a Mutex the same way clone() does.
+///
+/// On 64 bit applications the value is saved in an AtomicU64 instead of a Mutex.
 ///
 /// # Examples
 ///
@@ -69,7 +81,22 @@ pub struct SharedF64
 {
     //---------------------------------------------------------------------------------------------
     /// The internal data element.
-    data : Data
+    data     : Data,
+
+    //---------------------------------------------------------------------------------------------
+    /// The shared storage created by clone() while the object was unshared, the object switches
+    /// to it the next time it has mutable access.
+    promoted : OnceLock<Arc<Mutex<f64>>>,
+
+    //---------------------------------------------------------------------------------------------
+    /// Counts the calls to set() in auto downgrade mode, see set_auto_downgrade().
+    collapse : AutoDowngrade,
+
+    //---------------------------------------------------------------------------------------------
+    /// The threads that called get() or set(), shared with the other instances.  Only with the
+    /// thread-tracking feature.
+    #[cfg(feature = "thread-tracking")]
+    seen     : ThreadSet
 }
 
 impl SharedF64
@@ -81,7 +108,12 @@ impl SharedF64
         ) -> SharedF64
     {
         SharedF64 {
-            data : Data::Single(value)
+            data     : Data::Single(value),
+            promoted : OnceLock::new(),
+            collapse : AutoDowngrade::off(),
+
+            #[cfg(feature = "thread-tracking")]
+            seen     : ThreadSet::new()
         }
     }
 
@@ -92,53 +124,365 @@ impl SharedF64
         val : f64
         )
     {
+        self.settle();
+
+        #[cfg(feature = "thread-tracking")]
+        self.seen.record();
+
+        if self.collapse.tick()
+        {
+            self.try_downgrade();
+        }
+
         match self.data
         {
-            Data::Single(_)         => self.data = Data::Single(val),
-            Data::Multiple(ref mem) => {
-                let mut data = mem.lock().unwrap();
+            Data::Single(ref mut mem) => *mem = val,
+            Data::Multiple(ref mem)   => {
+                no_block::check();
+
+                let mut data = mem.lock().unwrap_or_else(PoisonError::into_inner);
 
                 *data = val
             }
         }
     }
 
+    //********************************************************************************************
+    /// Set the value.  The value is guarded by a Mutex on this target, which already orders the
+    /// accesses, so order is ignored.
+    pub fn set_with(
+        &mut self,
+        val    : f64,
+        _order : Ordering
+        )
+    {
+        self.set(val);
+    }
+
+    //********************************************************************************************
+    /// Set the value and return the previous value in a single operation.
+    pub fn swap(
+        &mut self,
+        val : f64
+        ) -> f64
+    {
+        self.settle();
+
+        match self.data
+        {
+            Data::Single(ref mut mem) => mem::replace(mem, val),
+            Data::Multiple(ref mem)   => {
+                no_block::check();
+
+                let mut data = mem.lock().unwrap_or_else(PoisonError::into_inner);
+
+                mem::replace(&mut *data, val)
+            }
+        }
+    }
+
     //********************************************************************************************
     /// Returns the value of the object.
     pub fn get(&self) -> f64
+    {
+        #[cfg(feature = "thread-tracking")]
+        self.seen.record();
+
+        match self.data
+        {
+            Data::Single(val) if !self.is_shared() => val,
+            _                                      => {
+                no_block::check();
+
+                *self.storage().lock().unwrap_or_else(PoisonError::into_inner)
+            }
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns the value.  The value is guarded by a Mutex on this target, which already orders
+    /// the accesses, so order is ignored.
+    pub fn get_with(
+        &self,
+        _order : Ordering
+        ) -> f64
+    {
+        self.get()
+    }
+
+    //********************************************************************************************
+    /// Returns the value, or Err if another thread panicked while holding the Mutex guarding it.
+    /// get() returns the value anyway, it is replaced as a whole so it is never half written.
+    pub fn checked_get(&self) -> Result<f64, Poisoned>
     {
         match self.data
         {
-            Data::Single(val)       => val,
-            Data::Multiple(ref mem) => {
-                let data = mem.lock().unwrap();
+            Data::Single(val) if !self.is_shared() => Ok(val),
+            _                                      => {
+                no_block::check();
 
-                *data
+                self.storage().lock().map(|data| *data).map_err(|_| Poisoned)
             }
         }
     }
 
     //********************************************************************************************
-    /// Clones the object.  After this call all access to the data will be done via an
-    /// AtomicUsize element.
+    /// Set the value, or return Err without setting it if another thread panicked while holding
+    /// the Mutex guarding it.  set() sets the value anyway.
+    pub fn checked_set(
+        &mut self,
+        val : f64
+        ) -> Result<(), Poisoned>
+    {
+        if self.is_shared() && self.storage().is_poisoned()
+        {
+            return Err(Poisoned);
+        }
+
+        self.set(val);
+
+        Ok(())
+    }
+
+    //********************************************************************************************
+    /// Clones the object, this is the same as clone().  After this call all access to the data
+    /// will be done via a shared Mutex element.
     pub fn dup(&mut self) -> SharedF64
+    {
+        let copy = self.clone();
+
+        self.settle();
+
+        copy
+    }
+
+    //********************************************************************************************
+    /// Returns the Mutex holding the value.  An unshared value is moved into a new Mutex first,
+    /// like clone() does, so it can be updated through a shared reference.
+    fn storage(&self) -> &Mutex<f64>
     {
         match self.data
         {
-            Data::Single(val) => {
-                let data = Arc::new(Mutex::new(val));
-                self.data = Data::Multiple(data.clone());
+            Data::Single(val)       => self.promoted.get_or_init(|| Arc::new(Mutex::new(val))),
+            Data::Multiple(ref mem) => mem
+        }
+    }
+
+    //********************************************************************************************
+    /// Switches to the shared storage created by clone(), if there is one.
+    fn settle(&mut self)
+    {
+        if let Some(mem) = self.promoted.take()
+        {
+            self.data = Data::Multiple(mem);
+        }
+    }
 
-                SharedF64 { data : Data::Multiple(data) }
+    //********************************************************************************************
+    /// Shares the object, this is the same as dup().  The returned object is linked to this one,
+    /// setting the value on either object is seen by both.
+    pub fn share(&mut self) -> SharedF64
+    {
+        self.dup()
+    }
+
+    //********************************************************************************************
+    /// Returns a new object holding the current value.  Unlike share() the returned object is
+    /// independent of this one, setting the value on either object is not seen by the other.
+    pub fn snapshot(&self) -> SharedF64
+    {
+        SharedF64::new(self.get())
+    }
+
+    //********************************************************************************************
+    /// Returns true if no other objects are sharing the data.  This is always true before dup()
+    /// is called, and becomes true again once all the other objects have been dropped.
+    pub fn is_uniquely_owned(&self) -> bool
+    {
+        match self.data
+        {
+            Data::Single(_)         => {
+                self.promoted.get().is_none_or(|mem| Arc::strong_count(mem) == 1)
             },
-            Data::Multiple(ref val) => {
-                SharedF64 { data : Data::Multiple(val.clone()) }
-            }
+            Data::Multiple(ref mem) => Arc::strong_count(mem) == 1
+        }
+    }
+
+    //********************************************************************************************
+    /// Consumes the object and returns the value if no other objects are sharing the data, or
+    /// hands the object back in Err if they are.  The value is copied out of the storage, so
+    /// unlike SharedObject::try_into_inner() there is no unwrapping involved, this only checks
+    /// that the value isn't being lost to the other objects.
+    pub fn try_into_inner(self) -> Result<f64, SharedF64>
+    {
+        if !self.is_uniquely_owned()
+        {
+            return Err(self);
+        }
+
+        Ok(self.get_with(Ordering::Acquire))
+    }
+
+    //********************************************************************************************
+    /// Compares the value with the value of another object using the total ordering of
+    /// f64::total_cmp(), so NaN and -0.0 have a well defined position.
+    pub fn total_cmp(
+        &self,
+        other : &SharedF64
+        ) -> cmp::Ordering
+    {
+        self.get().total_cmp(&other.get())
+    }
+
+    //********************************************************************************************
+    /// Returns a frozen copy of the current value that implements Ord.
+    pub fn freeze(&self) -> FrozenSharedF64
+    {
+        FrozenSharedF64::new(self.get())
+    }
+
+    //********************************************************************************************
+    /// Folds sample into the value as an exponential moving average, storing
+    /// alpha * sample + (1 - alpha) * old, and returns the new average.  Samples added by several
+    /// threads at once are all included, but the order they are folded in is not defined, so the
+    /// result can differ slightly from run to run.
+    pub fn update_ema(
+        &self,
+        sample : f64,
+        alpha  : f64
+        ) -> f64
+    {
+        no_block::check();
+
+        let mut data = self.storage().lock().unwrap_or_else(PoisonError::into_inner);
+
+        *data = alpha * sample + (1.0 - alpha) * *data;
+
+        *data
+    }
+
+    //********************************************************************************************
+    /// Moves the value into the shared storage used after dup() without creating another object,
+    /// so the storage is allocated now instead of by the first dup().  Does nothing if the object
+    /// is already shared.
+    pub fn pre_share(&mut self)
+    {
+        self.settle();
+
+        if let Data::Single(val) = self.data
+        {
+            self.data = Data::Multiple(Arc::new(Mutex::new(val)));
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns true if the value is in the shared storage, which is the case once dup(),
+    /// pre_share() or update_ema() has been called.
+    pub fn is_shared(&self) -> bool
+    {
+        matches!(self.data, Data::Multiple(_)) || self.promoted.get().is_some()
+    }
+
+    //********************************************************************************************
+    /// Returns the number of distinct threads that have called get() or set() on this object or
+    /// the objects sharing the data with it.  Only available with the thread-tracking feature.
+    #[cfg(feature = "thread-tracking")]
+    pub fn accessing_threads(&self) -> usize
+    {
+        self.seen.count()
+    }
+
+    //********************************************************************************************
+    /// Moves the value back out of the shared storage if no other objects are sharing it anymore,
+    /// so access is as cheap as before dup().  Returns true if the object is unshared after the
+    /// call, or false if other objects are still sharing the data.
+    pub fn try_downgrade(&mut self) -> bool
+    {
+        self.settle();
+
+        if let Data::Multiple(ref mut mem) = self.data
+        {
+            let val = match Arc::get_mut(mem)
+            {
+                Some(mem) => *mem.get_mut().unwrap_or_else(PoisonError::into_inner),
+                None      => return false
+            };
+
+            self.data = Data::Single(val);
+        }
+
+        true
+    }
+
+    //********************************************************************************************
+    /// Turns auto downgrade mode on or off.  In this mode every 64th call to set() checks whether
+    /// the other objects sharing the data are gone, and if they are moves the value back out of
+    /// the shared storage like try_downgrade().  The check is an Arc::get_mut(), the other calls
+    /// only count.  Objects created by dup() start with the mode off.
+    pub fn set_auto_downgrade(
+        &mut self,
+        on : bool
+        )
+    {
+        self.collapse.enable(on);
+    }
+
+    //********************************************************************************************
+    /// Sets the value to temp while f runs, then restores the previous value, even if f panics.
+    ///
+    /// The override isn't atomic across threads.  Objects sharing the data see temp while f runs,
+    /// and a value set by another object during f is overwritten when the previous value is
+    /// restored, so this only makes sense when this object is the only writer.
+    pub fn with_value<R, F : FnOnce() -> R>(
+        &mut self,
+        temp : f64,
+        f    : F
+        ) -> R
+    {
+        let old = self.get();
+
+        self.set(temp);
+
+        let _restore = ScopeGuard::new(|| self.set(old));
+
+        f()
+    }
+}
+
+impl Clone for SharedF64
+{
+    //*********************************************************************************************
+    /// Implementation of Clone, the same as dup() except that it only needs a shared reference.
+    ///
+    /// Cloning an unshared object copies the value into shared storage, and this object switches
+    /// to that storage.  The value of an unshared object only changes through a mutable reference,
+    /// update_ema() switches to the same storage first, so no update is lost while the clone is
+    /// being made.
+    fn clone(&self) -> SharedF64
+    {
+        let data = match self.data
+        {
+            Data::Single(val)       => {
+                self.promoted.get_or_init(|| Arc::new(Mutex::new(val))).clone()
+            },
+            Data::Multiple(ref mem) => mem.clone()
+        };
+
+        SharedF64 {
+            data     : Data::Multiple(data),
+            promoted : OnceLock::new(),
+            collapse : AutoDowngrade::off(),
+
+            #[cfg(feature = "thread-tracking")]
+            seen     : self.seen.clone()
         }
     }
 }
 
 use std::fmt::{Debug, Display, Formatter, Error};
+use std::str::FromStr;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 impl Debug for SharedF64
 {
@@ -166,6 +510,73 @@ impl Display for SharedF64
     }
 }
 
+impl PartialEq for SharedF64
+{
+    //*********************************************************************************************
+    /// Implementation of PartialEq, compares the current values from get().
+    fn eq(
+        &self,
+        other : &SharedF64
+        ) -> bool
+    {
+        self.get() == other.get()
+    }
+}
+
+impl PartialEq<f64> for SharedF64
+{
+    //*********************************************************************************************
+    /// Implementation of PartialEq against a plain f64, compares the current value from get().
+    fn eq(
+        &self,
+        other : &f64
+        ) -> bool
+    {
+        self.get() == *other
+    }
+}
+
+impl FromStr for SharedF64
+{
+    type Err = <f64 as FromStr>::Err;
+
+    //*********************************************************************************************
+    /// Implementation of FromStr, parses the value the same way f64 does.
+    fn from_str(
+        s : &str
+        ) -> Result<SharedF64, Self::Err>
+    {
+        f64::from_str(s).map(SharedF64::new)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for SharedF64
+{
+    //*********************************************************************************************
+    /// Implementation of Serialize, writes the current value from get().
+    fn serialize<S : Serializer>(
+        &self,
+        serializer : S
+        ) -> Result<S::Ok, S::Error>
+    {
+        self.get().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for SharedF64
+{
+    //*********************************************************************************************
+    /// Implementation of Deserialize, the loaded value starts out unshared.
+    fn deserialize<D : Deserializer<'de>>(
+        deserializer : D
+        ) -> Result<SharedF64, D::Error>
+    {
+        f64::deserialize(deserializer).map(SharedF64::new)
+    }
+}
+
 #[cfg(test)]
 mod tests
 {
@@ -213,4 +624,226 @@ mod tests
         assert_eq!(test2.get(), 11.87);
         assert_eq!(test3.get(), 11.87);
     }
+
+    //*********************************************************************************************
+    /// Test that share() links the objects and snapshot() does not.
+    #[test]
+    fn test_share_snapshot()
+    {
+        let mut test1 = super::SharedF64::new(79.5);
+        let test2     = test1.share();
+        let test3     = test1.snapshot();
+
+        test1.set(41.25);
+
+        assert_eq!(test1.get(), 41.25);
+        assert_eq!(test2.get(), 41.25);
+        assert_eq!(test3.get(), 79.5);
+    }
+
+    //*********************************************************************************************
+    /// Test that is_uniquely_owned() tracks the other instances.
+    #[test]
+    fn test_is_uniquely_owned()
+    {
+        let mut test1 = super::SharedF64::new(79.5);
+
+        assert!(test1.is_uniquely_owned());
+
+        let test2 = test1.dup();
+
+        assert!(!test1.is_uniquely_owned());
+        assert!(!test2.is_uniquely_owned());
+
+        drop(test2);
+
+        assert!(test1.is_uniquely_owned());
+    }
+
+    //*********************************************************************************************
+    /// Test total_cmp() with NaN and signed zeros.
+    #[test]
+    fn test_total_cmp()
+    {
+        use std::cmp::Ordering;
+
+        let nan  = super::SharedF64::new(f64::NAN);
+        let neg0 = super::SharedF64::new(-0.0);
+        let pos0 = super::SharedF64::new(0.0);
+        let inf  = super::SharedF64::new(f64::INFINITY);
+
+        assert_eq!(neg0.total_cmp(&pos0), Ordering::Less);
+        assert_eq!(pos0.total_cmp(&neg0), Ordering::Greater);
+        assert_eq!(pos0.total_cmp(&pos0), Ordering::Equal);
+        assert_eq!(inf.total_cmp(&nan), Ordering::Less);
+        assert_eq!(nan.total_cmp(&nan), Ordering::Equal);
+    }
+
+    //*********************************************************************************************
+    /// Test that pre_share() moves the value into the storage used by dup().
+    #[test]
+    fn test_pre_share()
+    {
+        let mut test1 = super::SharedF64::new(1.0);
+
+        assert!(!test1.is_shared());
+
+        test1.pre_share();
+
+        assert!(test1.is_shared());
+        assert!(test1.is_uniquely_owned());
+        assert_eq!(test1.get(), 1.0);
+
+        let storage = test1.storage() as *const _;
+        let test2   = test1.dup();
+
+        assert!(test2.is_shared());
+        assert_eq!(test2.storage() as *const _, storage);
+    }
+
+    //*********************************************************************************************
+    /// Test parsing the value from a string.
+    #[test]
+    fn test_from_str()
+    {
+        let test : super::SharedF64 = "12".parse().unwrap();
+
+        assert_eq!(test.get(), "12".parse::<f64>().unwrap());
+        assert!("abc".parse::<super::SharedF64>().is_err());
+    }
+
+    //*********************************************************************************************
+    /// Test a SeqCst store seen by an Acquire load on another object.
+    #[test]
+    fn test_get_set_with()
+    {
+        use std::sync::atomic::Ordering;
+        use std::thread;
+
+        let mut test1 = super::SharedF64::new(0.0);
+        let mut test2 = test1.dup();
+
+        let thread = thread::spawn(move || test2.set_with(7.5, Ordering::SeqCst));
+
+        thread.join().unwrap();
+
+        assert_eq!(test1.get_with(Ordering::Acquire), 7.5);
+
+        test1.set_with(0.0, Ordering::Release);
+        assert_eq!(test1.get_with(Ordering::SeqCst), 0.0);
+    }
+
+    //*********************************************************************************************
+    /// Test that with_value() restores the value after the scope, including after a panic.
+    #[test]
+    fn test_with_value()
+    {
+        use std::panic::{self, AssertUnwindSafe};
+
+        let mut test1 = super::SharedF64::new(1.5);
+        let test2     = test1.dup();
+
+        assert_eq!(test1.with_value(2.5, || test2.get()), 2.5);
+        assert_eq!(test2.get(), 1.5);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            test1.with_value(2.5, || panic!("inside the scope"))
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(test2.get(), 1.5);
+    }
+
+    //*********************************************************************************************
+    /// Test that swap() returns the previous value with 1 and 2 instances.
+    #[test]
+    fn test_swap()
+    {
+        let mut test1 = super::SharedF64::new(1.5);
+
+        assert_eq!(test1.swap(2.5), 1.5);
+        assert_eq!(test1.get(), 2.5);
+
+        let mut test2 = test1.dup();
+
+        assert_eq!(test2.swap(3.5), 2.5);
+        assert_eq!(test1.swap(1.5), 3.5);
+        assert_eq!(test2.get(), 1.5);
+    }
+
+    //*********************************************************************************************
+    /// Test that clone() shares the value through a shared reference, like dup() does.
+    #[test]
+    fn test_clone()
+    {
+        let mut test1 = super::SharedF64::new(1.5);
+        let holder    = &test1;
+        let mut test2 = holder.clone();
+        let test3     = test2.clone();
+
+        assert!(test1.is_shared());
+        assert!(!test1.is_uniquely_owned());
+
+        test1.set(2.5);
+        assert_eq!(test2.get(), 2.5);
+        assert_eq!(test3.get(), 2.5);
+
+        test2.set(3.5);
+        assert_eq!(test1.get(), 3.5);
+
+        drop(test2);
+        drop(test3);
+        assert!(test1.is_uniquely_owned());
+    }
+
+    //*********************************************************************************************
+    /// Test that equality compares the current values with the usual f64 semantics.
+    #[test]
+    fn test_eq()
+    {
+        let mut test1 = super::SharedF64::new(31.5);
+        let test2     = test1.dup();
+        let test3     = super::SharedF64::new(31.5);
+
+        assert_eq!(test2, test3);
+        assert_eq!(test3, 31.5);
+
+        test1.set(f64::NAN);
+
+        assert_ne!(test1, test2);
+        assert_ne!(test2, f64::NAN);
+    }
+
+    //*********************************************************************************************
+    /// Test that update_ema() includes the samples from every thread.
+    #[test]
+    fn test_update_ema()
+    {
+        use std::thread;
+
+        let mut test1 = super::SharedF64::new(0.0);
+
+        assert_eq!(test1.update_ema(10.0, 0.5), 5.0);
+        assert_eq!(test1.update_ema(10.0, 0.5), 7.5);
+
+        test1.set(0.0);
+
+        let threads : Vec<_> = (0..4).map(|_| {
+            let test2 = test1.dup();
+
+            thread::spawn(move || {
+                for _ in 0..1000
+                {
+                    test2.update_ema(20.0, 0.01);
+                }
+            })
+        }).collect();
+
+        for thread in threads
+        {
+            thread.join().unwrap();
+        }
+
+        assert!((test1.get() - 20.0).abs() < 0.001);
+    }
 }
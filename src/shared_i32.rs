@@ -6,28 +6,21 @@
  * option. This file may not be copied, modified, or distributed
  * except according to those terms.
  */
-use std::sync::Arc;
-use std::sync::atomic::{AtomicIsize, Ordering};
+use core::fmt::{Debug, Display, Formatter, Error};
+use core::sync::atomic::{AtomicI32, Ordering};
 
-//*************************************************************************************************
-/// Internal data structure that identifies how we are accessing the data.
-enum Data
-{
-    //---------------------------------------------------------------------------------------------
-    /// There is only 1 instance of the element.
-    Single(i32),
+use Shared;
 
-    //---------------------------------------------------------------------------------------------
-    /// There are or were multiple instances of the element.
-    Multiple(Arc<AtomicIsize>)
-}
+lock_free!(i32, AtomicI32);
+byte_order!(i32);
 
 //*************************************************************************************************
 /// Shareable i32 data element.
 ///
-/// If only 1 instance of the element is needed then that data is just saved as a normal memory
-/// location.  If multiple instances are needed then the value is saved in an AtomicIsize so it
-/// can be safely shared between threads.
+/// If only 1 instance of the element is needed then the value is saved in an AtomicI32 owned by
+/// the object, reading and writing it costs the same as a normal memory location.  If multiple
+/// instances are needed then the AtomicI32 is moved into an Arc so it can be safely shared between
+/// threads.
 ///
 /// # Examples
 ///
@@ -45,6 +38,8 @@ enum Data
 /// ```
 ///
 /// ```
+/// # #[cfg(not(all(feature = "strict-ordering", debug_assertions)))]
+/// # {
 /// use std::sync::mpsc;
 /// use std::thread;
 /// use shareable::SharedI32;
@@ -64,75 +59,143 @@ enum Data
 ///
 /// tx.send(());
 /// thread.join().unwrap();
+/// # }
+/// ```
+pub type SharedI32 = Shared<i32>;
+
+impl SharedI32
+{
+    //********************************************************************************************
+    /// Construct a new instance of a bounded object, see BoundedSharedI32.
+    pub fn new_bounded(
+        value : i32,
+        min   : i32,
+        max   : i32
+        ) -> BoundedSharedI32
+    {
+        BoundedSharedI32::new(value, min, max)
+    }
+}
+
+//*************************************************************************************************
+/// Shareable i32 data element that is always kept between a minimum and maximum value.
+///
+/// Every value that is set or added is clamped into the range, so the invariant is enforced by
+/// the object rather than at every call site.  The bounds are saved in each instance next to a
+/// SharedI32, which is why this is a separate type instead of an option on SharedI32: objects
+/// that don't need bounds don't pay for them.
+///
+/// # Examples
+///
+/// ```
+/// use shareable::SharedI32;
+///
+/// let mut volume = SharedI32::new_bounded(50, 0, 100);
+///
+/// volume.set(150);
+/// assert_eq!(volume.get(), 100);
+///
+/// volume.fetch_add(-30);
+/// assert_eq!(volume.get(), 70);
 /// ```
-pub struct SharedI32
+pub struct BoundedSharedI32
 {
     //---------------------------------------------------------------------------------------------
-    /// The internal data element.
-    data : Data
+    /// The value.
+    value : SharedI32,
+
+    //---------------------------------------------------------------------------------------------
+    /// The smallest allowed value.
+    min : i32,
+
+    //---------------------------------------------------------------------------------------------
+    /// The largest allowed value.
+    max : i32
 }
 
-impl SharedI32
+impl BoundedSharedI32
 {
     //********************************************************************************************
-    /// Construct a new instance of the object.
+    /// Construct a new instance of the object, the initial value is clamped into the range.
+    /// Panics if min is greater than max.
     pub fn new(
-        value : i32
-        ) -> SharedI32
+        value : i32,
+        min   : i32,
+        max   : i32
+        ) -> BoundedSharedI32
     {
-        SharedI32 {
-            data : Data::Single(value)
+        assert!(min <= max, "the minimum {} is greater than the maximum {}", min, max);
+
+        BoundedSharedI32 {
+            value : SharedI32::new(value.clamp(min, max)),
+            min,
+            max
         }
     }
 
     //********************************************************************************************
-    /// Set the value of the object.
+    /// Set the value of the object, the value is clamped into the range.
     pub fn set(
         &mut self,
         val : i32
         )
     {
-        match self.data
-        {
-            Data::Single(_)         => self.data = Data::Single(val),
-            Data::Multiple(ref mem) => mem.store(val as isize, Ordering::Relaxed)
-        }
+        let val = val.clamp(self.min, self.max);
+
+        self.value.set(val);
     }
 
     //********************************************************************************************
     /// Returns the value of the object.
     pub fn get(&self) -> i32
     {
-        match self.data
-        {
-            Data::Single(val)       => val,
-            Data::Multiple(ref mem) => mem.load(Ordering::Relaxed) as i32
-        }
+        self.value.get()
     }
 
     //********************************************************************************************
-    /// Clones the object.  After this call all access to the data will be done via an
-    /// AtomicIsize element.
-    pub fn dup(&mut self) -> SharedI32
+    /// Atomically adds to the value, clamping the result into the range instead of wrapping.
+    /// Returns the previous value.
+    pub fn fetch_add(
+        &self,
+        val : i32
+        ) -> i32
     {
-        match self.data
-        {
-            Data::Single(val) => {
-                let data = Arc::new(AtomicIsize::new(val as isize));
-                self.data = Data::Multiple(data.clone());
-
-                SharedI32 { data : Data::Multiple(data) }
-            },
-            Data::Multiple(ref val) => {
-                SharedI32 { data : Data::Multiple(val.clone()) }
-            }
+        let (min, max) = (self.min, self.max);
+
+        self.value.storage()
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |old| {
+                Some(old.saturating_add(val).clamp(min, max))
+            })
+            .unwrap()
+    }
+
+    //********************************************************************************************
+    /// Returns the smallest allowed value.
+    pub fn min(&self) -> i32
+    {
+        self.min
+    }
+
+    //********************************************************************************************
+    /// Returns the largest allowed value.
+    pub fn max(&self) -> i32
+    {
+        self.max
+    }
+
+    //********************************************************************************************
+    /// Clones the object, the new object shares the value and the bounds.
+    pub fn dup(&mut self) -> BoundedSharedI32
+    {
+        BoundedSharedI32 {
+            value : self.value.dup(),
+            min   : self.min,
+            max   : self.max
         }
     }
 }
 
-use std::fmt::{Debug, Display, Formatter, Error};
-
-impl Debug for SharedI32
+impl Debug for BoundedSharedI32
 {
     //*********************************************************************************************
     /// Implementation of Debug.
@@ -145,7 +208,7 @@ impl Debug for SharedI32
     }
 }
 
-impl Display for SharedI32
+impl Display for BoundedSharedI32
 {
     //*********************************************************************************************
     /// Implementation of Display.
@@ -205,4 +268,448 @@ mod tests
         assert_eq!(test2.get(), -11);
         assert_eq!(test3.get(), -11);
     }
+
+    //*********************************************************************************************
+    /// Test that share() links the objects and snapshot() does not.
+    #[test]
+    fn test_share_snapshot()
+    {
+        let mut test1 = super::SharedI32::new(79);
+        let test2     = test1.share();
+        let test3     = test1.snapshot();
+
+        test1.set(41);
+
+        assert_eq!(test1.get(), 41);
+        assert_eq!(test2.get(), 41);
+        assert_eq!(test3.get(), 79);
+    }
+
+    //*********************************************************************************************
+    /// Test the checked and saturating conversions.
+    #[test]
+    fn test_convert()
+    {
+        let mut test = super::SharedI32::new(79);
+
+        assert_eq!(test.get_checked::<u8>(), Some(79));
+        assert_eq!(test.get_saturating::<u8>(), 79);
+
+        test.set(i32::MIN);
+
+        assert_eq!(test.get_checked::<u8>(), None);
+        assert_eq!(test.get_saturating::<u8>(), 0);
+    }
+
+    //*********************************************************************************************
+    /// Test that is_uniquely_owned() tracks the other instances.
+    #[test]
+    fn test_is_uniquely_owned()
+    {
+        let mut test1 = super::SharedI32::new(79);
+
+        assert!(test1.is_uniquely_owned());
+
+        let test2 = test1.dup();
+
+        assert!(!test1.is_uniquely_owned());
+        assert!(!test2.is_uniquely_owned());
+
+        drop(test2);
+
+        assert!(test1.is_uniquely_owned());
+    }
+
+    //*********************************************************************************************
+    /// Test that a bounded object clamps values in the Single state.
+    #[test]
+    fn test_bounded_single()
+    {
+        let mut test = super::SharedI32::new_bounded(-500, -10, 10);
+
+        assert_eq!(test.get(), -10);
+        test.set(79);
+        assert_eq!(test.get(), 10);
+        test.set(-3);
+        assert_eq!(test.get(), -3);
+
+        assert_eq!(test.fetch_add(i32::MAX), -3);
+        assert_eq!(test.get(), 10);
+        assert_eq!(test.fetch_add(-15), 10);
+        assert_eq!(test.get(), -5);
+    }
+
+    //*********************************************************************************************
+    /// Test that a bounded object clamps values in the Multiple state.
+    #[test]
+    fn test_bounded_multiple()
+    {
+        let mut test1 = super::BoundedSharedI32::new(0, 0, 100);
+        let mut test2 = test1.dup();
+
+        test1.set(-79);
+
+        assert_eq!(test2.get(), 0);
+
+        test2.set(179);
+
+        assert_eq!(test1.get(), 100);
+
+        let threads : Vec<_> = (0..4).map(|_| {
+            let value = test1.dup();
+
+            ::std::thread::spawn(move || {
+                for _ in 0..100
+                {
+                    value.fetch_add(-1);
+                }
+            })
+        }).collect();
+
+        for thread in threads
+        {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(test1.get(), 0);
+        assert_eq!(test2.min(), 0);
+        assert_eq!(test2.max(), 100);
+    }
+
+    //*********************************************************************************************
+    /// Test that range_to() starts at the value when it was called.
+    #[test]
+    fn test_range_to()
+    {
+        let mut test1 = super::SharedI32::new(3);
+        let test2     = test1.dup();
+        let range     = test2.range_to(7);
+
+        test1.set(5);
+
+        assert_eq!(range.collect::<Vec<_>>(), vec![3, 4, 5, 6]);
+        assert_eq!(test2.range_to(7).count(), 2);
+        assert_eq!(test2.range_to(2).count(), 0);
+    }
+
+    //*********************************************************************************************
+    /// Test converting to and from Wrapping.
+    #[test]
+    fn test_wrapping()
+    {
+        use std::num::Wrapping;
+
+        let test = super::SharedI32::from(Wrapping(i32::MAX));
+
+        assert_eq!(test.get(), i32::MAX);
+        assert_eq!(test.wrapping() + Wrapping(1), Wrapping(i32::MIN));
+    }
+
+    //*********************************************************************************************
+    /// Test compare_exchange_value() when the exchange succeeds and fails.
+    #[test]
+    fn test_compare_exchange_value()
+    {
+        let mut test1 = super::SharedI32::new(5);
+        let test2     = test1.dup();
+
+        assert_eq!(test1.compare_exchange_value(5, 9), 5);
+        assert_eq!(test2.get(), 9);
+
+        assert_eq!(test2.compare_exchange_value(5, 1), 9);
+        assert_eq!(test1.get(), 9);
+    }
+
+    //*********************************************************************************************
+    /// Test that fetch_add() and fetch_sub() wrap around instead of panicking in debug builds.
+    #[test]
+    fn test_fetch_add_wraps()
+    {
+        let mut test1 = super::SharedI32::new(i32::MAX - 1);
+        let test2     = test1.dup();
+
+        assert_eq!(test1.fetch_add(1), i32::MAX - 1);
+        assert_eq!(test2.fetch_add(1), i32::MAX);
+        assert_eq!(test1.get(), i32::MIN);
+
+        assert_eq!(test2.fetch_sub(2), i32::MIN);
+        assert_eq!(test1.get(), i32::MAX - 1);
+    }
+
+    //*********************************************************************************************
+    /// Test that only one of the threads racing to replace a small value succeeds.
+    #[test]
+    fn test_replace_if()
+    {
+        use std::thread;
+
+        let mut test    = super::SharedI32::new(10);
+        let mut threads = Vec::new();
+
+        for _ in 0..4
+        {
+            let handle = test.dup();
+
+            threads.push(thread::spawn(move || handle.replace_if(100, |old| old < 50)));
+        }
+
+        let results = threads.into_iter().map(|thread| thread.join().unwrap()).collect::<Vec<_>>();
+
+        assert_eq!(results.iter().filter(|result| **result == Ok(10)).count(), 1);
+        assert_eq!(results.iter().filter(|result| **result == Err(100)).count(), 3);
+        assert_eq!(test.get(), 100);
+        assert_eq!(test.replace_if(5, |old| old == 100), Ok(100));
+    }
+
+    //*********************************************************************************************
+    /// Test that pre_share() moves the value into the storage used by dup().
+    #[test]
+    fn test_pre_share()
+    {
+        let mut test1 = super::SharedI32::new(1);
+
+        assert!(!test1.is_shared());
+
+        test1.pre_share();
+
+        assert!(test1.is_shared());
+        assert!(test1.is_uniquely_owned());
+        assert_eq!(test1.get(), 1);
+
+        let storage = test1.storage() as *const _;
+        let test2   = test1.dup();
+
+        assert!(test2.is_shared());
+        assert_eq!(test2.storage() as *const _, storage);
+    }
+
+    //*********************************************************************************************
+    /// Test that the byte order methods store the swapped bytes.
+    #[test]
+    fn test_byte_order()
+    {
+        let val      = -0x1234_5678i32;
+        let mut test = super::SharedI32::new(0);
+
+        test.set_be(val);
+
+        assert_eq!(test.get(), val.to_be());
+        assert_eq!(test.get_be(), val);
+
+        test.set_le(val);
+
+        assert_eq!(test.get(), val.to_le());
+        assert_eq!(test.get_le(), val);
+        assert_eq!(test.get_be(), i32::from_be(val.to_le()));
+    }
+
+    //*********************************************************************************************
+    /// Test that fetch_max_with() only ever raises the value.
+    #[test]
+    fn test_fetch_max_with()
+    {
+        let test     = super::SharedI32::new(10);
+        let mut prev = 10;
+
+        for i in 0..10
+        {
+            let old = test.fetch_max_with(|old| if i & 1 == 0 { old - 3 } else { old + 2 });
+
+            assert_eq!(old, prev);
+            assert!(test.get() >= prev);
+
+            prev = test.get();
+        }
+
+        assert_eq!(test.get(), 20);
+    }
+
+    //*********************************************************************************************
+    /// Test parsing the value from a string.
+    #[test]
+    fn test_from_str()
+    {
+        let test : super::SharedI32 = "12".parse().unwrap();
+
+        assert_eq!(test.get(), "12".parse::<i32>().unwrap());
+        assert!("abc".parse::<super::SharedI32>().is_err());
+    }
+
+    //*********************************************************************************************
+    /// Test that saturating_add_flagged() only sets the flag when the result is clamped.
+    #[test]
+    fn test_saturating_add_flagged()
+    {
+        let mut test = super::SharedI32::new(i32::MAX - 2);
+
+        assert_eq!(test.saturating_add_flagged(2), (i32::MAX, false));
+        assert_eq!(test.saturating_add_flagged(0), (i32::MAX, false));
+        assert_eq!(test.saturating_add_flagged(1), (i32::MAX, true));
+        assert_eq!(test.get(), i32::MAX);
+
+        test.set(i32::MIN + 1);
+
+        assert_eq!(test.saturating_add_flagged(-1), (i32::MIN, false));
+        assert_eq!(test.saturating_add_flagged(-1), (i32::MIN, true));
+    }
+
+    //*********************************************************************************************
+    /// Test a SeqCst store seen by an Acquire load on another object.
+    #[test]
+    fn test_get_set_with()
+    {
+        use std::sync::atomic::Ordering;
+        use std::thread;
+
+        let mut test1 = super::SharedI32::new(0);
+        let mut test2 = test1.dup();
+
+        let thread = thread::spawn(move || test2.set_with(7, Ordering::SeqCst));
+
+        thread.join().unwrap();
+
+        assert_eq!(test1.get_with(Ordering::Acquire), 7);
+
+        test1.set_with(0, Ordering::Release);
+        assert_eq!(test1.get_with(Ordering::SeqCst), 0);
+    }
+
+    //*********************************************************************************************
+    /// Test that exactly the callers that lowered the minimum see the flag.
+    #[test]
+    fn test_fetch_min_flagged()
+    {
+        use std::collections::HashSet;
+        use std::thread;
+
+        let mut test    = super::SharedI32::new(100);
+        let mut threads = Vec::new();
+
+        for t in 0..4
+        {
+            let low = test.dup();
+
+            threads.push(thread::spawn(move || {
+                (0..20).map(|i| {
+                    let val         = 99 - ((i % 5) * 16 + t * 4 + i / 5);
+                    let (old, lower) = low.fetch_min_flagged(val);
+
+                    (val, old, lower)
+                }).collect::<Vec<_>>()
+            }));
+        }
+
+        let results  = threads.into_iter().flat_map(|thread| thread.join().unwrap());
+        let results  = results.collect::<Vec<_>>();
+        let lowered  = results.iter().filter(|r| r.2).map(|r| r.0).collect::<HashSet<_>>();
+        let mut mins = results.iter().map(|r| r.1).collect::<HashSet<_>>();
+
+        mins.insert(test.get());
+        mins.remove(&100);
+
+        assert!(results.iter().all(|&(val, old, lower)| lower == (val < old)));
+        assert_eq!(lowered, mins);
+        assert_eq!(test.get(), 20);
+    }
+
+    //*********************************************************************************************
+    /// Test that with_value() restores the value after the scope, including after a panic.
+    #[test]
+    fn test_with_value()
+    {
+        use std::panic::{self, AssertUnwindSafe};
+
+        let mut test1 = super::SharedI32::new(1);
+        let test2     = test1.dup();
+
+        assert_eq!(test1.with_value(2, || test2.get()), 2);
+        assert_eq!(test2.get(), 1);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            test1.with_value(2, || panic!("inside the scope"))
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(test2.get(), 1);
+    }
+
+    //*********************************************************************************************
+    /// Test compare_exchange() succeeding and failing with 1 and 2 instances.
+    #[test]
+    fn test_compare_exchange()
+    {
+        use std::sync::atomic::Ordering;
+
+        let mut test1 = super::SharedI32::new(5);
+
+        assert_eq!(test1.compare_exchange(6, 7, Ordering::AcqRel, Ordering::Acquire), Err(5));
+        assert_eq!(test1.compare_exchange(5, 7, Ordering::AcqRel, Ordering::Acquire), Ok(5));
+        assert_eq!(test1.get(), 7);
+
+        let test2 = test1.dup();
+
+        assert_eq!(test2.compare_exchange(5, 9, Ordering::SeqCst, Ordering::Relaxed), Err(7));
+        assert_eq!(test2.compare_exchange(7, 9, Ordering::SeqCst, Ordering::Relaxed), Ok(7));
+        assert_eq!(test1.get(), 9);
+    }
+
+    //*********************************************************************************************
+    /// Test that swap() returns the previous value with 1 and 2 instances.
+    #[test]
+    fn test_swap()
+    {
+        let mut test1 = super::SharedI32::new(1);
+
+        assert_eq!(test1.swap(2), 1);
+        assert_eq!(test1.get(), 2);
+
+        let mut test2 = test1.dup();
+
+        assert_eq!(test2.swap(3), 2);
+        assert_eq!(test1.swap(1), 3);
+        assert_eq!(test2.get(), 1);
+    }
+
+    //*********************************************************************************************
+    /// Test that clone() shares the value through a shared reference, like dup() does.
+    #[test]
+    fn test_clone()
+    {
+        let mut test1 = super::SharedI32::new(1);
+        let holder    = &test1;
+        let mut test2 = holder.clone();
+        let test3     = test2.clone();
+
+        assert!(test1.is_shared());
+        assert!(!test1.is_uniquely_owned());
+
+        test1.set(2);
+        assert_eq!(test2.get(), 2);
+        assert_eq!(test3.get(), 2);
+
+        test2.set(3);
+        assert_eq!(test1.get(), 3);
+
+        drop(test2);
+        drop(test3);
+        assert!(test1.is_uniquely_owned());
+    }
+
+    //*********************************************************************************************
+    /// Test that update_full() reports the old and new values and whether the value changed.
+    #[test]
+    fn test_update_full()
+    {
+        let mut test1 = super::SharedI32::new(5);
+        let test2     = test1.dup();
+        let result    = test1.update_full(|val| val + 2);
+
+        assert_eq!((result.old, result.new, result.changed), (5, 7, true));
+
+        let result = test2.update_full(|val| val);
+
+        assert_eq!(result, ::UpdateResult { old : 7, new : 7, changed : false });
+        assert_eq!(test2.update_full(|val| val - 7).new, 0);
+        assert_eq!(test1.get(), 0);
+    }
 }
@@ -6,28 +6,29 @@
  * option. This file may not be copied, modified, or distributed
  * except according to those terms.
  */
-use std::sync::{Arc, Mutex};
+use std::mem;
+use std::sync::{Mutex, PoisonError};
+use std::sync::atomic::Ordering;
 
-//*************************************************************************************************
-/// Internal data structure that identifies how we are accessing the data.
-enum Data
-{
-    //---------------------------------------------------------------------------------------------
-    /// There is only 1 instance of the element.
-    Single(i64),
+use Overflow;
+use Poisoned;
+use Shared;
+use no_block;
+use test_hooks::{self, Point};
 
-    //---------------------------------------------------------------------------------------------
-    /// There are or were multiple instances of the element.
-    Multiple(Arc<Mutex<i64>>)
-}
+locked!(i64);
+byte_order!(i64);
 
 //*************************************************************************************************
 /// Shareable i64 data element.
 ///
-/// If only 1 instance of the element is needed then that data is just saved as a normal memory
-/// location.  If multiple instances are needed then the value is saved in an AtomicIsize if the
-/// application is 64 bit or a Mutex if the application is 32 bit so it can be safely shared
-/// between threads.
+/// If only 1 instance of the element is needed then the value is saved in the object itself, so
+/// reading and writing it costs the same as a normal memory location and no lock is taken.  If
+/// multiple instances are needed then the value is moved into a Mutex<i64> inside an Arc so it
+/// can be safely shared between threads.  This target has no 64 bit atomics, so the Mutex is the
+/// shared storage Shared<i64> uses here.
+///
+/// On 64 bit applications the shared storage is an AtomicI64 instead of a Mutex.
 ///
 /// # Examples
 ///
@@ -65,104 +66,78 @@ enum Data
 /// tx.send(());
 /// thread.join().unwrap();
 /// ```
-pub struct SharedI64
-{
-    //---------------------------------------------------------------------------------------------
-    /// The internal data element.
-    data : Data
-}
+pub type SharedI64 = Shared<i64>;
 
 impl SharedI64
 {
     //********************************************************************************************
-    /// Construct a new instance of the object.
-    pub fn new(
-        value : i64
-        ) -> SharedI64
+    /// Returns the value, or Err if another thread panicked while holding the Mutex guarding it.
+    /// get() returns the value anyway, it is replaced as a whole so it is never half written.
+    pub fn checked_get(&self) -> Result<i64, Poisoned>
     {
-        SharedI64 {
-            data : Data::Single(value)
+        match self.shared_storage()
+        {
+            Some(mem) => {
+                no_block::check();
+
+                mem.lock().map(|data| *data).map_err(|_| Poisoned)
+            },
+            None      => Ok(self.get())
         }
     }
 
     //********************************************************************************************
-    /// Set the value of the object.
-    pub fn set(
+    /// Set the value, or return Err without setting it if another thread panicked while holding
+    /// the Mutex guarding it.  set() sets the value anyway.
+    pub fn checked_set(
         &mut self,
         val : i64
-        )
+        ) -> Result<(), Poisoned>
     {
-        match self.data
+        if self.shared_storage().is_some_and(|mem| mem.is_poisoned())
         {
-            Data::Single(_)         => self.data = Data::Single(val),
-            Data::Multiple(ref mem) => {
-                let mut data = mem.lock().unwrap();
-
-                *data = val
-            }
+            return Err(Poisoned);
         }
-    }
 
-    //********************************************************************************************
-    /// Returns the value of the object.
-    pub fn get(&self) -> i64
-    {
-        match self.data
-        {
-            Data::Single(val)       => val,
-            Data::Multiple(ref mem) => {
-                let data = mem.lock().unwrap();
+        self.set(val);
 
-                *data
-            }
-        }
+        Ok(())
     }
 
     //********************************************************************************************
-    /// Clones the object.  After this call all access to the data will be done via an
-    /// AtomicUsize element.
-    pub fn dup(&mut self) -> SharedI64
+    /// Returns the value as an i32, or None if the value doesn't fit in an i32.
+    pub fn get_as_i32_checked(&self) -> Option<i32>
     {
-        match self.data
-        {
-            Data::Single(val) => {
-                let data = Arc::new(Mutex::new(val));
-                self.data = Data::Multiple(data.clone());
-
-                SharedI64 { data : Data::Multiple(data) }
-            },
-            Data::Multiple(ref val) => {
-                SharedI64 { data : Data::Multiple(val.clone()) }
-            }
-        }
+        self.get_checked()
     }
-}
 
-use std::fmt::{Debug, Display, Formatter, Error};
-
-impl Debug for SharedI64
-{
-    //*********************************************************************************************
-    /// Implementation of Debug.
-    fn fmt(
-        &self,
-        f : &mut Formatter
-        ) -> Result<(), Error>
+    //********************************************************************************************
+    /// Returns the value as an i32, clamped to i32::MIN or i32::MAX if it doesn't fit.
+    pub fn get_as_i32_saturating(&self) -> i32
     {
-        write!(f, "{:?}", self.get())
+        self.get_saturating()
     }
-}
 
-impl Display for SharedI64
-{
-    //*********************************************************************************************
-    /// Implementation of Display.
-    fn fmt(
+    //********************************************************************************************
+    /// Adds val to the value and returns the new value, or leaves the value unchanged and returns
+    /// which bound the result would have gone past.
+    pub fn checked_add_signed(
         &self,
-        f : &mut Formatter
-        ) -> Result<(), Error>
+        val : i64
+        ) -> Result<i64, Overflow>
     {
-        write!(f, "{}", self.get())
+        no_block::check();
+
+        let mut data = self.storage().lock().unwrap_or_else(PoisonError::into_inner);
+
+        test_hooks::hit(Point::CompareExchange);
+
+        match data.checked_add(val)
+        {
+            Some(new)       => { *data = new; Ok(new) }
+            None if val < 0 => Err(Overflow::Negative),
+            None            => Err(Overflow::Positive)
+        }
     }
 }
 
@@ -213,4 +188,465 @@ mod tests
         assert_eq!(test2.get(), 11);
         assert_eq!(test3.get(), 11);
     }
+
+    //*********************************************************************************************
+    /// Test that share() links the objects and snapshot() does not.
+    #[test]
+    fn test_share_snapshot()
+    {
+        let mut test1 = super::SharedI64::new(79);
+        let test2     = test1.share();
+        let test3     = test1.snapshot();
+
+        test1.set(41);
+
+        assert_eq!(test1.get(), 41);
+        assert_eq!(test2.get(), 41);
+        assert_eq!(test3.get(), 79);
+    }
+
+    //*********************************************************************************************
+    /// Test the checked and saturating conversions.
+    #[test]
+    fn test_convert()
+    {
+        let mut test = super::SharedI64::new(79);
+
+        assert_eq!(test.get_checked::<u8>(), Some(79));
+        assert_eq!(test.get_saturating::<u8>(), 79);
+
+        test.set(i64::MIN);
+
+        assert_eq!(test.get_checked::<u8>(), None);
+        assert_eq!(test.get_saturating::<u8>(), 0);
+    }
+
+    //*********************************************************************************************
+    /// Test the i32 conversions with values inside and outside the i32 range.
+    #[test]
+    fn test_convert_i32()
+    {
+        let mut test1 = super::SharedI64::new(-2_000_000_000);
+        let mut test2 = test1.dup();
+
+        assert_eq!(test2.get_as_i32_checked(), Some(-2_000_000_000));
+        assert_eq!(test2.get_as_i32_saturating(), -2_000_000_000);
+
+        test1.set(5_000_000_000);
+
+        assert_eq!(test2.get_as_i32_checked(), None);
+        assert_eq!(test2.get_as_i32_saturating(), i32::MAX);
+
+        test2.set(-5_000_000_000);
+
+        assert_eq!(test1.get_as_i32_checked(), None);
+        assert_eq!(test1.get_as_i32_saturating(), i32::MIN);
+    }
+
+    //*********************************************************************************************
+    /// Test that is_uniquely_owned() tracks the other instances.
+    #[test]
+    fn test_is_uniquely_owned()
+    {
+        let mut test1 = super::SharedI64::new(79);
+
+        assert!(test1.is_uniquely_owned());
+
+        let test2 = test1.dup();
+
+        assert!(!test1.is_uniquely_owned());
+        assert!(!test2.is_uniquely_owned());
+
+        drop(test2);
+
+        assert!(test1.is_uniquely_owned());
+    }
+
+    //*********************************************************************************************
+    /// Test that range_to() starts at the value when it was called.
+    #[test]
+    fn test_range_to()
+    {
+        let mut test1 = super::SharedI64::new(3);
+        let test2     = test1.dup();
+        let range     = test2.range_to(7);
+
+        test1.set(5);
+
+        assert_eq!(range.collect::<Vec<_>>(), vec![3, 4, 5, 6]);
+        assert_eq!(test2.range_to(7).count(), 2);
+        assert_eq!(test2.range_to(2).count(), 0);
+    }
+
+    //*********************************************************************************************
+    /// Test converting to and from Wrapping.
+    #[test]
+    fn test_wrapping()
+    {
+        use std::num::Wrapping;
+
+        let test = super::SharedI64::from(Wrapping(i64::MAX));
+
+        assert_eq!(test.get(), i64::MAX);
+        assert_eq!(test.wrapping() + Wrapping(1), Wrapping(i64::MIN));
+    }
+
+    //*********************************************************************************************
+    /// Test that fetch_max() only raises the value.
+    #[test]
+    fn test_fetch_max()
+    {
+        let mut test1 = super::SharedI64::new(-5);
+        let test2     = test1.dup();
+
+        assert_eq!(test1.fetch_max(-10), -5);
+        assert_eq!(test2.fetch_max(7), -5);
+        assert_eq!(test1.get(), 7);
+    }
+
+    //*********************************************************************************************
+    /// Test compare_exchange_value() when the exchange succeeds and fails.
+    #[test]
+    fn test_compare_exchange_value()
+    {
+        let mut test1 = super::SharedI64::new(5);
+        let test2     = test1.dup();
+
+        assert_eq!(test1.compare_exchange_value(5, 9), 5);
+        assert_eq!(test2.get(), 9);
+
+        assert_eq!(test2.compare_exchange_value(5, 1), 9);
+        assert_eq!(test1.get(), 9);
+    }
+
+    //*********************************************************************************************
+    /// Test that fetch_add() and fetch_sub() wrap around instead of panicking in debug builds.
+    #[test]
+    fn test_fetch_add_wraps()
+    {
+        let mut test1 = super::SharedI64::new(i64::MAX - 1);
+        let test2     = test1.dup();
+
+        assert_eq!(test1.fetch_add(1), i64::MAX - 1);
+        assert_eq!(test2.fetch_add(1), i64::MAX);
+        assert_eq!(test1.get(), i64::MIN);
+
+        assert_eq!(test2.fetch_sub(2), i64::MIN);
+        assert_eq!(test1.get(), i64::MAX - 1);
+    }
+
+    //*********************************************************************************************
+    /// Test that only one of the threads racing to replace a small value succeeds.
+    #[test]
+    fn test_replace_if()
+    {
+        use std::thread;
+
+        let mut test    = super::SharedI64::new(10);
+        let mut threads = Vec::new();
+
+        for _ in 0..4
+        {
+            let handle = test.dup();
+
+            threads.push(thread::spawn(move || handle.replace_if(100, |old| old < 50)));
+        }
+
+        let results = threads.into_iter().map(|thread| thread.join().unwrap()).collect::<Vec<_>>();
+
+        assert_eq!(results.iter().filter(|result| **result == Ok(10)).count(), 1);
+        assert_eq!(results.iter().filter(|result| **result == Err(100)).count(), 3);
+        assert_eq!(test.get(), 100);
+        assert_eq!(test.replace_if(5, |old| old == 100), Ok(100));
+    }
+
+    //*********************************************************************************************
+    /// Test that pre_share() moves the value into the storage used by dup().
+    #[test]
+    fn test_pre_share()
+    {
+        let mut test1 = super::SharedI64::new(1);
+
+        assert!(!test1.is_shared());
+
+        test1.pre_share();
+
+        assert!(test1.is_shared());
+        assert!(test1.is_uniquely_owned());
+        assert_eq!(test1.get(), 1);
+
+        let storage = test1.storage() as *const _;
+        let test2   = test1.dup();
+
+        assert!(test2.is_shared());
+        assert_eq!(test2.storage() as *const _, storage);
+    }
+
+    //*********************************************************************************************
+    /// Test that the byte order methods store the swapped bytes.
+    #[test]
+    fn test_byte_order()
+    {
+        let val      = -0x1234_5678_9ABC_DEF0i64;
+        let mut test = super::SharedI64::new(0);
+
+        test.set_be(val);
+
+        assert_eq!(test.get(), val.to_be());
+        assert_eq!(test.get_be(), val);
+
+        test.set_le(val);
+
+        assert_eq!(test.get(), val.to_le());
+        assert_eq!(test.get_le(), val);
+        assert_eq!(test.get_be(), i64::from_be(val.to_le()));
+    }
+
+    //*********************************************************************************************
+    /// Test that fetch_max_with() only ever raises the value.
+    #[test]
+    fn test_fetch_max_with()
+    {
+        let test     = super::SharedI64::new(10);
+        let mut prev = 10;
+
+        for i in 0..10
+        {
+            let old = test.fetch_max_with(|old| if i & 1 == 0 { old - 3 } else { old + 2 });
+
+            assert_eq!(old, prev);
+            assert!(test.get() >= prev);
+
+            prev = test.get();
+        }
+
+        assert_eq!(test.get(), 20);
+    }
+
+    //*********************************************************************************************
+    /// Test parsing the value from a string.
+    #[test]
+    fn test_from_str()
+    {
+        let test : super::SharedI64 = "12".parse().unwrap();
+
+        assert_eq!(test.get(), "12".parse::<i64>().unwrap());
+        assert!("abc".parse::<super::SharedI64>().is_err());
+    }
+
+    //*********************************************************************************************
+    /// Test that saturating_add_flagged() only sets the flag when the result is clamped.
+    #[test]
+    fn test_saturating_add_flagged()
+    {
+        let mut test = super::SharedI64::new(i64::MAX - 2);
+
+        assert_eq!(test.saturating_add_flagged(2), (i64::MAX, false));
+        assert_eq!(test.saturating_add_flagged(0), (i64::MAX, false));
+        assert_eq!(test.saturating_add_flagged(1), (i64::MAX, true));
+        assert_eq!(test.get(), i64::MAX);
+
+        test.set(i64::MIN + 1);
+
+        assert_eq!(test.saturating_add_flagged(-1), (i64::MIN, false));
+        assert_eq!(test.saturating_add_flagged(-1), (i64::MIN, true));
+    }
+
+    //*********************************************************************************************
+    /// Test a SeqCst store seen by an Acquire load on another object.
+    #[test]
+    fn test_get_set_with()
+    {
+        use std::sync::atomic::Ordering;
+        use std::thread;
+
+        let mut test1 = super::SharedI64::new(0);
+        let mut test2 = test1.dup();
+
+        let thread = thread::spawn(move || test2.set_with(7, Ordering::SeqCst));
+
+        thread.join().unwrap();
+
+        assert_eq!(test1.get_with(Ordering::Acquire), 7);
+
+        test1.set_with(0, Ordering::Release);
+        assert_eq!(test1.get_with(Ordering::SeqCst), 0);
+    }
+
+    //*********************************************************************************************
+    /// Test that exactly the callers that lowered the minimum see the flag.
+    #[test]
+    fn test_fetch_min_flagged()
+    {
+        use std::collections::HashSet;
+        use std::thread;
+
+        let mut test    = super::SharedI64::new(100);
+        let mut threads = Vec::new();
+
+        for t in 0..4
+        {
+            let low = test.dup();
+
+            threads.push(thread::spawn(move || {
+                (0..20).map(|i| {
+                    let val         = 99 - ((i % 5) * 16 + t * 4 + i / 5);
+                    let (old, lower) = low.fetch_min_flagged(val);
+
+                    (val, old, lower)
+                }).collect::<Vec<_>>()
+            }));
+        }
+
+        let results  = threads.into_iter().flat_map(|thread| thread.join().unwrap());
+        let results  = results.collect::<Vec<_>>();
+        let lowered  = results.iter().filter(|r| r.2).map(|r| r.0).collect::<HashSet<_>>();
+        let mut mins = results.iter().map(|r| r.1).collect::<HashSet<_>>();
+
+        mins.insert(test.get());
+        mins.remove(&100);
+
+        assert!(results.iter().all(|&(val, old, lower)| lower == (val < old)));
+        assert_eq!(lowered, mins);
+        assert_eq!(test.get(), 20);
+    }
+
+    //*********************************************************************************************
+    /// Test that with_value() restores the value after the scope, including after a panic.
+    #[test]
+    fn test_with_value()
+    {
+        use std::panic::{self, AssertUnwindSafe};
+
+        let mut test1 = super::SharedI64::new(1);
+        let test2     = test1.dup();
+
+        assert_eq!(test1.with_value(2, || test2.get()), 2);
+        assert_eq!(test2.get(), 1);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            test1.with_value(2, || panic!("inside the scope"))
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(test2.get(), 1);
+    }
+
+    //*********************************************************************************************
+    /// Test compare_exchange() succeeding and failing with 1 and 2 instances.
+    #[test]
+    fn test_compare_exchange()
+    {
+        use std::sync::atomic::Ordering;
+
+        let mut test1 = super::SharedI64::new(5);
+
+        assert_eq!(test1.compare_exchange(6, 7, Ordering::AcqRel, Ordering::Acquire), Err(5));
+        assert_eq!(test1.compare_exchange(5, 7, Ordering::AcqRel, Ordering::Acquire), Ok(5));
+        assert_eq!(test1.get(), 7);
+
+        let test2 = test1.dup();
+
+        assert_eq!(test2.compare_exchange(5, 9, Ordering::SeqCst, Ordering::Relaxed), Err(7));
+        assert_eq!(test2.compare_exchange(7, 9, Ordering::SeqCst, Ordering::Relaxed), Ok(7));
+        assert_eq!(test1.get(), 9);
+    }
+
+    //*********************************************************************************************
+    /// Test that swap() returns the previous value with 1 and 2 instances.
+    #[test]
+    fn test_swap()
+    {
+        let mut test1 = super::SharedI64::new(1);
+
+        assert_eq!(test1.swap(2), 1);
+        assert_eq!(test1.get(), 2);
+
+        let mut test2 = test1.dup();
+
+        assert_eq!(test2.swap(3), 2);
+        assert_eq!(test1.swap(1), 3);
+        assert_eq!(test2.get(), 1);
+    }
+
+    //*********************************************************************************************
+    /// Test that checked_add_signed() reports which bound was crossed and leaves the value alone.
+    #[test]
+    fn test_checked_add_signed()
+    {
+        let mut test1 = super::SharedI64::new(i64::MAX - 1);
+
+        assert_eq!(test1.checked_add_signed(1), Ok(i64::MAX));
+        assert_eq!(test1.checked_add_signed(1), Err(super::Overflow::Positive));
+        assert_eq!(test1.get(), i64::MAX);
+
+        test1.set(i64::MIN + 1);
+
+        let test2 = test1.dup();
+
+        assert_eq!(test2.checked_add_signed(-1), Ok(i64::MIN));
+        assert_eq!(test2.checked_add_signed(-1), Err(super::Overflow::Negative));
+        assert_eq!(test1.checked_add_signed(i64::MAX), Ok(-1));
+    }
+
+    //*********************************************************************************************
+    /// Test that clone() shares the value through a shared reference, like dup() does.
+    #[test]
+    fn test_clone()
+    {
+        let mut test1 = super::SharedI64::new(1);
+        let holder    = &test1;
+        let mut test2 = holder.clone();
+        let test3     = test2.clone();
+
+        assert!(test1.is_shared());
+        assert!(!test1.is_uniquely_owned());
+
+        test1.set(2);
+        assert_eq!(test2.get(), 2);
+        assert_eq!(test3.get(), 2);
+
+        test2.set(3);
+        assert_eq!(test1.get(), 3);
+
+        drop(test2);
+        drop(test3);
+        assert!(test1.is_uniquely_owned());
+    }
+
+    //*********************************************************************************************
+    /// Test that update_full() reports the old and new values and whether the value changed.
+    #[test]
+    fn test_update_full()
+    {
+        let mut test1 = super::SharedI64::new(5);
+        let test2     = test1.dup();
+        let result    = test1.update_full(|val| val + 2);
+
+        assert_eq!((result.old, result.new, result.changed), (5, 7, true));
+
+        let result = test2.update_full(|val| val);
+
+        assert_eq!(result, ::UpdateResult { old : 7, new : 7, changed : false });
+        assert_eq!(test2.update_full(|val| val - 7).new, 0);
+        assert_eq!(test1.get(), 0);
+    }
+
+    //*********************************************************************************************
+    /// Test that try_into_inner() only returns the value once the other objects are dropped.
+    #[test]
+    fn test_try_into_inner()
+    {
+        assert_eq!(super::SharedI64::new(31).try_into_inner().ok(), Some(31));
+
+        let mut test1 = super::SharedI64::new(31);
+        let test2     = test1.dup();
+        let test1     = test1.try_into_inner().unwrap_err();
+
+        assert_eq!(test1.get(), 31);
+
+        drop(test2);
+
+        assert_eq!(test1.try_into_inner().ok(), Some(31));
+    }
 }
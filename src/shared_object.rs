@@ -6,31 +6,228 @@
  * option. This file may not be copied, modified, or distributed
  * except according to those terms.
  */
-use std::sync::{Mutex, Arc};
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::ops::Deref;
+use std::sync::{Arc, Condvar, LazyLock, Mutex, OnceLock, PoisonError, RwLock, TryLockError};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use no_block;
+use Poisoned;
+use scope_guard::ScopeGuard;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use test_hooks::{self, Point};
+#[cfg(feature = "audit")]
+use audit::{AuditLog, Change};
+#[cfg(feature = "thread-tracking")]
+use thread_tracking::ThreadSet;
 
 //*************************************************************************************************
 /// Internal data structure that identifies how we are accessing the data.
-enum Data<T>
+enum Data<T : ?Sized>
 {
     //---------------------------------------------------------------------------------------------
     /// There is only 1 instance of the element.
     Single(Arc<T>),
 
     //---------------------------------------------------------------------------------------------
-    /// There are or were multiple instances of the element.
-    Multiple(Arc<Mutex<Arc<T>>>)
+    /// There is only 1 instance of the element and it was created with default() or new_with(),
+    /// the value isn't created until it is needed.
+    Lazy(LazyLock<Arc<T>, Box<dyn FnOnce() -> Arc<T> + Send>>),
+
+    //---------------------------------------------------------------------------------------------
+    /// There are or were multiple instances of the element.  The release guard must come after
+    /// the data so it is dropped after the reference count has been decremented.
+    Multiple(Arc<Storage<T>>, Release)
+}
+
+//*************************************************************************************************
+/// Function called with the value when the last instance sharing it is dropped.
+type Finalizer<T> = Box<dyn FnOnce(&T) + Send>;
+
+//*************************************************************************************************
+/// The value shared between all the instances, along with the finalizer registered by
+/// on_last_drop().  Dereferences to the RwLock holding the value.
+struct Storage<T : ?Sized>
+{
+    //---------------------------------------------------------------------------------------------
+    /// The value.
+    value       : RwLock<Arc<T>>,
+
+    //---------------------------------------------------------------------------------------------
+    /// Called with the value when the storage is dropped.
+    finalizer   : Mutex<Option<Finalizer<T>>>,
+
+    //---------------------------------------------------------------------------------------------
+    /// The channels registered by subscribe(), each is sent every new value.
+    subscribers : Mutex<Vec<Sender<Arc<T>>>>
+}
+
+impl<T : ?Sized> Storage<T>
+{
+    //********************************************************************************************
+    /// Construct a new instance of the object.
+    fn new(
+        value : Arc<T>
+        ) -> Storage<T>
+    {
+        Storage {
+            value       : RwLock::new(value),
+            finalizer   : Mutex::new(None),
+            subscribers : Mutex::new(Vec::new())
+        }
+    }
+
+    //********************************************************************************************
+    /// Sends the new value to the subscribers, dropping the ones whose receiver is gone.  Must be
+    /// called while the write lock is held so every subscriber gets the values in order.
+    fn publish(
+        &self,
+        val : &Arc<T>
+        )
+    {
+        let mut subscribers = self.subscribers.lock().unwrap_or_else(PoisonError::into_inner);
+
+        subscribers.retain(|tx| tx.send(val.clone()).is_ok());
+    }
+
+    //********************************************************************************************
+    /// Consumes the storage and returns the value without calling the finalizer.
+    fn into_value(mut self) -> Arc<T>
+    {
+        self.finalizer.get_mut().unwrap_or_else(PoisonError::into_inner).take();
+
+        let value = self.value.get_mut().unwrap_or_else(PoisonError::into_inner);
+
+        value.clone()
+    }
+
+    //********************************************************************************************
+    /// Returns the value if no finalizer or subscriber is registered, so the storage can be
+    /// dropped without losing either of them.
+    fn detach(&mut self) -> Option<Arc<T>>
+    {
+        let finalizer   = self.finalizer.get_mut().unwrap_or_else(PoisonError::into_inner);
+        let subscribers = self.subscribers.get_mut().unwrap_or_else(PoisonError::into_inner);
+
+        if finalizer.is_some() || !subscribers.is_empty()
+        {
+            return None;
+        }
+
+        Some(self.value.get_mut().unwrap_or_else(PoisonError::into_inner).clone())
+    }
+}
+
+impl<T : ?Sized> Deref for Storage<T>
+{
+    type Target = RwLock<Arc<T>>;
+
+    //*********************************************************************************************
+    /// Implementation of Deref.
+    fn deref(&self) -> &RwLock<Arc<T>>
+    {
+        &self.value
+    }
+}
+
+impl<T : ?Sized> Drop for Storage<T>
+{
+    //*********************************************************************************************
+    /// Calls the finalizer, this is only run once the last instance sharing the data is gone.
+    fn drop(&mut self)
+    {
+        let finalizer = self.finalizer.get_mut().unwrap_or_else(PoisonError::into_inner).take();
+
+        if let Some(finalizer) = finalizer
+        {
+            finalizer(self.value.get_mut().unwrap_or_else(PoisonError::into_inner));
+        }
+    }
+}
+
+//*************************************************************************************************
+/// State shared between all the instances so threads can wait for the other instances to be
+/// dropped, along with the version of the value.
+struct Teardown
+{
+    //---------------------------------------------------------------------------------------------
+    /// Number of times the value has been replaced since the data was first shared, only changed
+    /// while the write lock is held.
+    version : AtomicU64,
+
+    //---------------------------------------------------------------------------------------------
+    /// Lock used with the condition variable.
+    lock : Mutex<()>,
+
+    //---------------------------------------------------------------------------------------------
+    /// Signaled every time an instance is dropped.
+    released : Condvar
+}
+
+//*************************************************************************************************
+/// Wakes up any threads waiting in wait_until_unique() when an instance is dropped.
+#[derive(Clone)]
+struct Release(Arc<Teardown>);
+
+impl Release
+{
+    //********************************************************************************************
+    /// Construct a new instance of the object.
+    fn new() -> Release
+    {
+        Release(Arc::new(Teardown {
+            version  : AtomicU64::new(0),
+            lock     : Mutex::new(()),
+            released : Condvar::new()
+        }))
+    }
+
+    //********************************************************************************************
+    /// Increments the version, must be called while the write lock is held.
+    fn bump(&self)
+    {
+        self.0.version.fetch_add(1, Ordering::Relaxed);
+    }
+
+    //********************************************************************************************
+    /// Returns the version, must be called while the lock is held.
+    fn version(&self) -> u64
+    {
+        self.0.version.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for Release
+{
+    //*********************************************************************************************
+    /// Signal the waiting threads, the lock is taken so a waiting thread can't miss the signal
+    /// between checking the reference count and going to sleep.
+    fn drop(&mut self)
+    {
+        let _lock = self.0.lock.lock().unwrap_or_else(PoisonError::into_inner);
+
+        self.0.released.notify_all();
+    }
 }
 
 //*************************************************************************************************
 /// Shareable object data element.
 ///
 /// If only 1 instance of the element is needed then that data is just saved as a normal memory
-/// location.  If multiple instances are needed then the value is saved in an Mutex so it
-/// can be safely shared between threads.
+/// location.  If multiple instances are needed then the value is saved in an RwLock so it
+/// can be safely shared between threads, get() only takes the read lock so readers on several
+/// threads don't block each other.
 ///
 /// This object can only store read only data structures.  There is nothing implemented to provide
 /// read/write access to objects.
 ///
+/// Small Copy types implementing AtomicBackable can use SharedAtomicObject instead, which saves
+/// the value in an AtomicU64 so get() and set() never block.  SharedObject always uses the
+/// RwLock, it can't pick the storage from the type without specialization.
+///
 /// # Examples
 ///
 /// ```
@@ -67,11 +264,27 @@ enum Data<T>
 /// tx.send(());
 /// thread.join().unwrap();
 /// ```
-pub struct SharedObject<T>
+pub struct SharedObject<T : ?Sized>
 {
     //---------------------------------------------------------------------------------------------
     /// The internal data element.
-    data : Data<T>
+    data     : Data<T>,
+
+    //---------------------------------------------------------------------------------------------
+    /// The shared storage created by clone() while the object was unshared, the object switches
+    /// to it the next time it has mutable access.
+    promoted : OnceLock<(Arc<Storage<T>>, Release)>,
+
+    //---------------------------------------------------------------------------------------------
+    /// The changes made by set(), shared with the other instances.
+    #[cfg(feature = "audit")]
+    audit : AuditLog<T>,
+
+    //---------------------------------------------------------------------------------------------
+    /// The threads that called get() or set(), shared with the other instances.  Only with the
+    /// thread-tracking feature.
+    #[cfg(feature = "thread-tracking")]
+    seen : ThreadSet
 }
 
 impl<T> SharedObject<T>
@@ -83,7 +296,35 @@ impl<T> SharedObject<T>
         ) -> SharedObject<T>
     {
         SharedObject {
-            data : Data::Single(Arc::new(value))
+            data     : Data::Single(Arc::new(value)),
+            promoted : OnceLock::new(),
+
+            #[cfg(feature = "audit")]
+            audit : AuditLog::new(),
+
+            #[cfg(feature = "thread-tracking")]
+            seen : ThreadSet::new()
+        }
+    }
+
+    //********************************************************************************************
+    /// Construct a new instance of the object whose value is computed by f the first time it is
+    /// needed.  f is called at most once, even if several threads read the object at the same
+    /// time.  Calling dup() or share() computes the value.
+    pub fn new_with<F>(
+        f : F
+        ) -> SharedObject<T>
+        where F : FnOnce() -> T + Send + 'static
+    {
+        SharedObject {
+            data     : Data::Lazy(LazyLock::new(Box::new(move || Arc::new(f())))),
+            promoted : OnceLock::new(),
+
+            #[cfg(feature = "audit")]
+            audit : AuditLog::new(),
+
+            #[cfg(feature = "thread-tracking")]
+            seen : ThreadSet::new()
         }
     }
 
@@ -94,121 +335,2516 @@ impl<T> SharedObject<T>
         val : T
         )
     {
+        let val = Arc::new(val);
+
+        self.settle();
+
+        #[cfg(feature = "thread-tracking")]
+        self.seen.record();
+
         match self.data
         {
-            Data::Single(_)         => self.data = Data::Single(Arc::new(val)),
-            Data::Multiple(ref mem) => {
-                let mut lock = mem.lock().unwrap();
+            Data::Single(_) | Data::Lazy(..) => {
+                #[cfg(feature = "audit")]
+                self.audit.record(self.get(), val.clone());
 
-                *lock = Arc::new(val);
+                self.data = Data::Single(val);
+            },
+            Data::Multiple(ref mem, ref release) => {
+                no_block::check();
+
+                let mut lock = mem.write().unwrap_or_else(PoisonError::into_inner);
+
+                #[cfg(feature = "audit")]
+                self.audit.record(lock.clone(), val.clone());
+
+                *lock = val;
+                release.bump();
+                mem.publish(&lock);
             }
         }
     }
 
     //********************************************************************************************
-    /// Returns the value of the object.
-    pub fn get(&self) -> Arc<T>
+    /// Set the value of the object, or return Err without setting it if another thread panicked
+    /// while holding the write lock.  set() sets the value anyway.
+    pub fn checked_set(
+        &mut self,
+        val : T
+        ) -> Result<(), Poisoned>
+    {
+        self.settle();
+
+        if self.shared().is_some_and(|(mem, _)| mem.is_poisoned())
+        {
+            return Err(Poisoned);
+        }
+
+        self.set(val);
+
+        Ok(())
+    }
+
+    //********************************************************************************************
+    /// Set the value.  The value is guarded by an RwLock, which already orders the accesses, so
+    /// order is ignored.  This matches set_with() on the numeric types.
+    pub fn set_with(
+        &mut self,
+        val    : T,
+        _order : Ordering
+        )
+    {
+        self.set(val);
+    }
+
+    //********************************************************************************************
+    /// Set the value and return the previous value in a single operation.  If the object is
+    /// shared the write lock is held for both, so no other instance can set the value in between.
+    pub fn swap(
+        &mut self,
+        val : T
+        ) -> Arc<T>
     {
+        match self.swap_if(val, |_| true)
+        {
+            Some(old) => old,
+            None      => unreachable!()
+        }
+    }
+
+    //********************************************************************************************
+    /// Replaces the value with new if pred returns true for the current value, returning the
+    /// previous value.  Returns None and drops new if pred returns false.  If the object is
+    /// shared the write lock is held while pred runs, so no other instance can set the value
+    /// between the check and the swap.
+    pub fn swap_if<P : FnOnce(&T) -> bool>(
+        &mut self,
+        new  : T,
+        pred : P
+        ) -> Option<Arc<T>>
+    {
+        let new = Arc::new(new);
+
+        self.settle();
+
         match self.data
         {
-            Data::Single(ref val)   => val.clone(),
-            Data::Multiple(ref mem) => {
-                let lock = mem.lock().unwrap();
+            Data::Single(_) | Data::Lazy(..) => {
+                let old = self.get();
 
-                lock.clone()
+                if !pred(&old)
+                {
+                    return None;
+                }
+
+                #[cfg(feature = "audit")]
+                self.audit.record(old.clone(), new.clone());
+
+                self.data = Data::Single(new);
+
+                Some(old)
+            },
+            Data::Multiple(ref mem, ref release) => {
+                no_block::check();
+
+                let mut lock = mem.write().unwrap_or_else(PoisonError::into_inner);
+
+                if !pred(&lock)
+                {
+                    return None;
+                }
+
+                #[cfg(feature = "audit")]
+                self.audit.record(lock.clone(), new.clone());
+
+                release.bump();
+                mem.publish(&new);
+
+                Some(mem::replace(&mut *lock, new))
             }
         }
     }
 
     //********************************************************************************************
-    /// Clones the object.  After this call all access to the data will be done via an
-    /// AtomicIsize element.
-    pub fn dup(&mut self) -> SharedObject<T>
+    /// Replaces the value with f(current).  If the object is shared the write lock is held while
+    /// f runs, so no other instance can set the value between the read and the write and no update
+    /// is lost.  f should be short and must not use this object or the objects sharing its data.
+    pub fn update<F : FnOnce(&T) -> T>(
+        &mut self,
+        f : F
+        )
+    {
+        self.modify(f);
+    }
+
+    //********************************************************************************************
+    /// Replaces the value with f(current) like update(), and returns the value it stored.  The
+    /// result is taken while the write lock is still held, so unlike update() followed by get()
+    /// it can't be a value set by another instance in between.
+    pub fn modify<F : FnOnce(&T) -> T>(
+        &mut self,
+        f : F
+        ) -> Arc<T>
     {
-        let data = match self.data
+        self.settle();
+
+        match self.data
         {
-            Data::Single(ref val)   => Arc::new(Mutex::new(val.clone())),
-            Data::Multiple(ref val) => val.clone()
-        };
+            Data::Single(_) | Data::Lazy(..) => {
+                let old = self.get();
+                let new = Arc::new(f(&old));
+
+                #[cfg(feature = "audit")]
+                self.audit.record(old, new.clone());
+
+                self.data = Data::Single(new.clone());
+
+                new
+            },
+            Data::Multiple(ref mem, ref release) => {
+                no_block::check();
 
-        self.data = Data::Multiple(data.clone());
+                let mut lock = mem.write().unwrap_or_else(PoisonError::into_inner);
+                let new      = Arc::new(f(&lock));
 
-        SharedObject { data : Data::Multiple(data) }
+                #[cfg(feature = "audit")]
+                self.audit.record(lock.clone(), new.clone());
+
+                *lock = new.clone();
+                release.bump();
+                mem.publish(&lock);
+
+                new
+            }
+        }
     }
-}
 
-use std::fmt::{Debug, Display, Formatter, Error};
+    //********************************************************************************************
+    /// Consumes the object and returns the value.  Returns None if there are other instances
+    /// sharing the data or if values returned by get() are still alive.  The finalizer registered
+    /// by on_last_drop() is not called, the caller owns the value now.
+    pub fn into_inner(mut self) -> Option<T>
+    {
+        self.settle();
 
-impl<T : Debug> Debug for SharedObject<T>
-{
-    //*********************************************************************************************
-    /// Implementation of Debug.
-    fn fmt(
-        &self,
-        f : &mut Formatter
-        ) -> Result<(), Error>
+        let val = match self.data
+        {
+            Data::Single(val)      => val,
+            Data::Lazy(lazy)       => LazyLock::force(&lazy).clone(),
+            Data::Multiple(mem, _) => match Arc::try_unwrap(mem)
+            {
+                Ok(mem) => mem.into_value(),
+                Err(_)  => return None
+            }
+        };
+
+        Arc::try_unwrap(val).ok()
+    }
+
+    //********************************************************************************************
+    /// Consumes the object and returns the value, like into_inner(), but hands the object back
+    /// in Err if there are other instances sharing the data or values returned by get() are still
+    /// alive, so it isn't lost.
+    pub fn try_into_inner(mut self) -> Result<T, SharedObject<T>>
     {
-        write!(f, "{:?}", self.get())
+        self.settle();
+
+        if self.will_clone_on_write()
+        {
+            return Err(self);
+        }
+
+        match self.into_inner()
+        {
+            Some(val) => Ok(val),
+            None      => unreachable!()
+        }
     }
-}
 
-impl<T : Display> Display for SharedObject<T>
-{
-    //*********************************************************************************************
-    /// Implementation of Display.
-    fn fmt(
-        &self,
-        f : &mut Formatter
-        ) -> Result<(), Error>
+    //********************************************************************************************
+    /// Sets the value to temp while f runs, then restores the previous value, even if f panics.
+    ///
+    /// The override isn't atomic across threads.  Objects sharing the data see temp while f runs,
+    /// and a value set by another object during f is overwritten when the previous value is
+    /// restored, so this only makes sense when this object is the only writer.
+    pub fn with_value<R, F : FnOnce() -> R>(
+        &mut self,
+        temp : T,
+        f    : F
+        ) -> R
     {
-        write!(f, "{}", self.get())
+        let old = self.exchange(Arc::new(temp));
+
+        let _restore = ScopeGuard::new(|| { self.exchange(old); });
+
+        f()
     }
 }
 
-#[cfg(test)]
-mod tests
+impl<T : ?Sized> SharedObject<T>
 {
-    //*********************************************************************************************
-    /// Test that get/set work with only 1 instance.
-    #[test]
-    fn single()
+    //********************************************************************************************
+    /// Construct a new instance of the object from a value that is already on the heap.  The box
+    /// is converted directly into the shared storage so large values never have to be moved
+    /// across the stack.
+    pub fn new_boxed(
+        value : Box<T>
+        ) -> SharedObject<T>
     {
-        let mut test = super::SharedObject::new(String::from("abc"));
+        SharedObject {
+            data     : Data::Single(Arc::from(value)),
+            promoted : OnceLock::new(),
 
-        assert_eq!(*test.get(), "abc");
-        test.set(String::from("xyz"));
-        assert_eq!(*test.get(), "xyz");
+            #[cfg(feature = "audit")]
+            audit : AuditLog::new(),
+
+            #[cfg(feature = "thread-tracking")]
+            seen : ThreadSet::new()
+        }
     }
 
-    //*********************************************************************************************
-    /// Test that get/set work with multiple instances.
-    #[test]
-    fn multiple()
+    //********************************************************************************************
+    /// Construct a new instance of the object using an Arc that is already held elsewhere, the
+    /// Arc is used as the storage instead of being wrapped in another Arc.  get() returns clones
+    /// of the adopted Arc until the value is set.
+    pub fn adopt(
+        value : Arc<T>
+        ) -> SharedObject<T>
     {
-        let mut test1 = super::SharedObject::new(String::from("abc"));
-        let mut test2 = test1.dup();
-        let mut test3 = test2.dup();
-
-        assert_eq!(*test1.get(), "abc");
-        assert_eq!(*test2.get(), "abc");
-        assert_eq!(*test3.get(), "abc");
+        SharedObject {
+            data     : Data::Single(value),
+            promoted : OnceLock::new(),
 
-        test1.set(String::from("xyz"));
+            #[cfg(feature = "audit")]
+            audit : AuditLog::new(),
 
-        assert_eq!(*test1.get(), "xyz");
-        assert_eq!(*test2.get(), "xyz");
-        assert_eq!(*test3.get(), "xyz");
+            #[cfg(feature = "thread-tracking")]
+            seen : ThreadSet::new()
+        }
+    }
 
-        test2.set(String::from("mno"));
+    //********************************************************************************************
+    /// Returns the value of the object.
+    pub fn get(&self) -> Arc<T>
+    {
+        #[cfg(feature = "thread-tracking")]
+        self.seen.record();
 
-        assert_eq!(*test1.get(), "mno");
-        assert_eq!(*test2.get(), "mno");
-        assert_eq!(*test3.get(), "mno");
+        match self.shared()
+        {
+            Some((mem, _)) => {
+                no_block::check();
 
-        test3.set(String::from("123"));
+                let lock = mem.read().unwrap_or_else(PoisonError::into_inner);
 
-        assert_eq!(*test1.get(), "123");
+                lock.clone()
+            },
+            None => self.local().clone()
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns the value.  The value is guarded by an RwLock, which already orders the accesses,
+    /// so order is ignored.  This matches get_with() on the numeric types.
+    pub fn get_with(
+        &self,
+        _order : Ordering
+        ) -> Arc<T>
+    {
+        self.get()
+    }
+
+    //********************************************************************************************
+    /// Calls f with a reference to the value and returns its result, without cloning the Arc
+    /// like get() does.  If the object is shared the read lock is held while f runs, which blocks
+    /// the writers, so f should be short and must not use this object or the objects sharing its
+    /// data.
+    pub fn with<R, F : FnOnce(&T) -> R>(
+        &self,
+        f : F
+        ) -> R
+    {
+        match self.shared()
+        {
+            Some((mem, _)) => {
+                no_block::check();
+
+                let lock = mem.read().unwrap_or_else(PoisonError::into_inner);
+
+                f(&lock)
+            },
+            None => f(self.local())
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns the last AUDIT_CAPACITY changes made by set() on this object or the objects
+    /// sharing the data with it, oldest first.  Only available with the audit feature.
+    ///
+    /// Recording a change needs the previous value, so setting an object created with default()
+    /// or new_with() computes the initial value first.
+    #[cfg(feature = "audit")]
+    pub fn audit_log(&self) -> Vec<Change<T>>
+    {
+        self.audit.entries()
+    }
+
+    //********************************************************************************************
+    /// Returns the value of the object, or None if the object is shared and another thread holds
+    /// the write lock.  Never blocks, so it can be used where get() is not allowed.
+    pub fn try_get(&self) -> Option<Arc<T>>
+    {
+        match self.shared()
+        {
+            Some((mem, _)) => match mem.try_read()
+            {
+                Ok(lock)                         => Some(lock.clone()),
+                Err(TryLockError::Poisoned(err)) => Some(err.into_inner().clone()),
+                Err(TryLockError::WouldBlock)    => None
+            },
+            None => Some(self.get())
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns the value of the object, or Err if another thread panicked while holding the write
+    /// lock.  get() returns the value anyway, it is replaced as a whole so it is never left half
+    /// written.
+    pub fn checked_get(&self) -> Result<Arc<T>, Poisoned>
+    {
+        match self.shared()
+        {
+            Some((mem, _)) => {
+                no_block::check();
+
+                mem.read().map(|lock| lock.clone()).map_err(|_| Poisoned)
+            },
+            None => Ok(self.local().clone())
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns the value of the object along with its version.  The version is the number of
+    /// times the value has been replaced since the data was first shared, it is always 0 while
+    /// the object has never been shared.
+    ///
+    /// Both are read while holding the lock, so the version always belongs to the returned
+    /// value.  Readers can compare versions to find out if the value changed between two reads.
+    pub fn read_versioned(&self) -> (Arc<T>, u64)
+    {
+        match self.shared()
+        {
+            Some((mem, release)) => {
+                no_block::check();
+
+                let lock = mem.read().unwrap_or_else(PoisonError::into_inner);
+
+                (lock.clone(), release.version())
+            },
+            None => (self.get(), 0)
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns the value of the object along with a validator that returns true while the object
+    /// it is given still holds that value.  Expensive work can be done on the value and the
+    /// validator called before committing the result, to confirm the value is still current.
+    ///
+    /// The validator checks both the version and the identity of the value, so it also detects
+    /// changes to an object that was never shared.
+    pub fn read_and_validator(&self) -> (Arc<T>, impl Fn(&SharedObject<T>) -> bool)
+    {
+        let (val, version) = self.read_versioned();
+        let seen           = val.clone();
+
+        (val, move |object : &SharedObject<T>| {
+            let (current, current_version) = object.read_versioned();
+
+            current_version == version && Arc::ptr_eq(&current, &seen)
+        })
+    }
+
+    //********************************************************************************************
+    /// Returns the value if it changed since the version saved in the token, and saves the new
+    /// version in the token.  Returns None if the value didn't change.  Start with a token of 0
+    /// to only be told about values set after the object was shared.
+    ///
+    /// Like read_versioned(), only the values set after the data was first shared are counted,
+    /// an object that was never shared always returns None.
+    pub fn poll(
+        &self,
+        token : &mut u64
+        ) -> Option<Arc<T>>
+    {
+        let (val, version) = self.read_versioned();
+
+        if version == *token
+        {
+            return None;
+        }
+
+        *token = version;
+
+        Some(val)
+    }
+
+    //********************************************************************************************
+    /// Blocks the current thread until all the other instances sharing the data have been
+    /// dropped.  Returns immediately if the object was never shared.
+    pub fn wait_until_unique(&self)
+    {
+        if let Some((mem, release)) = self.shared()
+        {
+            no_block::check();
+
+            let mut lock = release.0.lock.lock().unwrap_or_else(PoisonError::into_inner);
+
+            while Arc::strong_count(mem) > 1
+            {
+                lock = release.0.released.wait(lock).unwrap_or_else(PoisonError::into_inner);
+            }
+        }
+    }
+
+    //********************************************************************************************
+    /// Clones the object, this is the same as clone().  After this call all access to the data
+    /// will be done via a shared RwLock element.
+    pub fn dup(&mut self) -> SharedObject<T>
+    {
+        let copy = self.clone();
+
+        self.settle();
+
+        copy
+    }
+
+    //********************************************************************************************
+    /// Moves the value into the shared storage used after dup() without creating another object,
+    /// so the storage is allocated now instead of by the first dup().  Does nothing if the object
+    /// is already shared.
+    pub fn pre_share(&mut self)
+    {
+        self.settle();
+
+        if let Data::Single(_) | Data::Lazy(..) = self.data
+        {
+            self.data = Data::Multiple(Arc::new(Storage::new(self.get())), Release::new());
+        }
+
+        #[cfg(feature = "audit")]
+        self.audit.prepare();
+    }
+
+    //********************************************************************************************
+    /// Returns true if the value is in the shared storage, which is the case once dup(),
+    /// pre_share(), or on_last_drop() has been called.
+    pub fn is_shared(&self) -> bool
+    {
+        self.shared().is_some()
+    }
+
+    //********************************************************************************************
+    /// Returns the number of distinct threads that have called get() or set() on this object or
+    /// the objects sharing the data with it.  Only available with the thread-tracking feature.
+    #[cfg(feature = "thread-tracking")]
+    pub fn accessing_threads(&self) -> usize
+    {
+        self.seen.count()
+    }
+
+    //********************************************************************************************
+    /// Moves the value back out of the shared storage if no other objects or readers are sharing
+    /// it anymore, so access is as cheap as before dup().  Returns true if the object is unshared
+    /// after the call.  Returns false if the data is still shared, or if a finalizer or a
+    /// subscriber is registered, since those need the shared storage.
+    pub fn try_downgrade(&mut self) -> bool
+    {
+        self.settle();
+
+        if let Data::Multiple(ref mut mem, _) = self.data
+        {
+            let val = match Arc::get_mut(mem).and_then(Storage::detach)
+            {
+                Some(val) => val,
+                None      => return false
+            };
+
+            self.data = Data::Single(val);
+        }
+
+        true
+    }
+
+    //********************************************************************************************
+    /// Registers a finalizer that is called with the value when the last instance sharing the
+    /// data is dropped, including the views returned by reader().  It is called exactly once,
+    /// from the thread dropping the last instance.  Registering another finalizer replaces the
+    /// previous one.  After this call all access to the data will be done via a shared RwLock
+    /// element, like after dup().
+    pub fn on_last_drop(
+        &mut self,
+        f : Box<dyn FnOnce(&T) + Send>
+        )
+    {
+        self.pre_share();
+
+        if let Data::Multiple(ref mem, _) = self.data
+        {
+            *mem.finalizer.lock().unwrap_or_else(PoisonError::into_inner) = Some(f);
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns a receiver that is sent every value set from now on by this object or the objects
+    /// sharing the data with it, so a dedicated thread can process the changes.  Each call
+    /// returns another receiver that gets all the values.  A receiver that is dropped is skipped
+    /// from then on.  After this call all access to the data will be done via a shared RwLock
+    /// element, like after dup().
+    pub fn subscribe(&mut self) -> Receiver<Arc<T>>
+    {
+        let (tx, rx) = mpsc::channel();
+
+        self.pre_share();
+
+        if let Data::Multiple(ref mem, _) = self.data
+        {
+            mem.subscribers.lock().unwrap_or_else(PoisonError::into_inner).push(tx);
+        }
+
+        rx
+    }
+
+    //********************************************************************************************
+    /// Shares the object, this is the same as dup().  The returned object is linked to this one,
+    /// setting the value on either object is seen by both.
+    pub fn share(&mut self) -> SharedObject<T>
+    {
+        self.dup()
+    }
+
+    //********************************************************************************************
+    /// Returns a new object holding the current value.  Unlike share() the returned object is
+    /// independent of this one, setting the value on either object is not seen by the other.  The
+    /// value itself is not cloned, both objects point at the same read only data until one of
+    /// them is set.
+    pub fn snapshot(&self) -> SharedObject<T>
+    {
+        SharedObject {
+            data     : Data::Single(self.get()),
+            promoted : OnceLock::new(),
+
+            #[cfg(feature = "audit")]
+            audit : AuditLog::new(),
+
+            #[cfg(feature = "thread-tracking")]
+            seen : ThreadSet::new()
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns true if no other objects are sharing the data.  This is always true before dup()
+    /// is called, and becomes true again once all the other objects have been dropped.
+    pub fn is_uniquely_owned(&self) -> bool
+    {
+        match self.shared()
+        {
+            Some((mem, _)) => Arc::strong_count(mem) == 1,
+            None           => true
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns true if modifying the value in place would have to clone it first, because other
+    /// objects are sharing the data or values returned by get() are still alive.  When this
+    /// returns false the caller is the only owner of the value.
+    pub fn will_clone_on_write(&self) -> bool
+    {
+        match (self.shared(), &self.data)
+        {
+            (Some((mem, _)), _) => {
+                no_block::check();
+
+                let value = mem.read().unwrap_or_else(PoisonError::into_inner);
+
+                Arc::strong_count(mem) > 1 || Arc::strong_count(&value) > 1
+            },
+            (None, Data::Lazy(lazy)) if LazyLock::get(lazy).is_none() => false,
+            (None, _)                                                 => {
+                Arc::strong_count(self.local()) > 1
+            }
+        }
+    }
+
+    //********************************************************************************************
+    /// Exchanges the values of this object and another object.  Any objects sharing the data
+    /// with either object see the exchanged values.
+    ///
+    /// If both objects are shared then both write locks are held while the values are exchanged.
+    /// The locks are always taken in the order of their addresses, so two threads exchanging the
+    /// same pair of objects in opposite directions cannot deadlock.  If only one object is shared
+    /// then only its write lock is held, the other object isn't visible to any other thread.
+    pub fn swap_with(
+        &mut self,
+        other : &mut SharedObject<T>
+        )
+    {
+        self.settle();
+        other.settle();
+
+        if let (Data::Multiple(mem1, rel1), Data::Multiple(mem2, rel2)) = (&self.data, &other.data)
+        {
+            if Arc::ptr_eq(mem1, mem2)
+            {
+                return;
+            }
+
+            let self_first      = (Arc::as_ptr(mem1) as usize) < (Arc::as_ptr(mem2) as usize);
+            let (first, second) = if self_first { (mem1, mem2) } else { (mem2, mem1) };
+
+            no_block::check();
+
+            let mut lock1 = first.write().unwrap_or_else(PoisonError::into_inner);
+            let mut lock2 = second.write().unwrap_or_else(PoisonError::into_inner);
+
+            #[cfg(feature = "audit")]
+            {
+                let (old1, old2) = if self_first
+                {
+                    (&*lock1, &*lock2)
+                }
+                else
+                {
+                    (&*lock2, &*lock1)
+                };
+
+                self.audit.record(old1.clone(), old2.clone());
+                other.audit.record(old2.clone(), old1.clone());
+            }
+
+            mem::swap(&mut *lock1, &mut *lock2);
+            rel1.bump();
+            rel2.bump();
+            first.publish(&lock1);
+            second.publish(&lock2);
+
+            return;
+        }
+
+        let (shared, local) = match other.data
+        {
+            Data::Multiple(..) => (other, self),
+            _                  => (self, other)
+        };
+        let old             = shared.exchange(local.get());
+
+        local.exchange(old);
+    }
+
+    //********************************************************************************************
+    /// Replaces the value and returns the previous value, holding the write lock while doing it
+    /// if the object is shared.
+    fn exchange(
+        &mut self,
+        val : Arc<T>
+        ) -> Arc<T>
+    {
+        self.settle();
+
+        #[cfg(feature = "thread-tracking")]
+        self.seen.record();
+
+        match self.data
+        {
+            Data::Multiple(ref mem, ref release) => {
+                no_block::check();
+
+                let mut lock = mem.write().unwrap_or_else(PoisonError::into_inner);
+
+                #[cfg(feature = "audit")]
+                self.audit.record(lock.clone(), val.clone());
+
+                release.bump();
+                mem.publish(&val);
+
+                mem::replace(&mut *lock, val)
+            },
+            _ => {
+                let old = self.get();
+
+                #[cfg(feature = "audit")]
+                self.audit.record(old.clone(), val.clone());
+
+                self.data = Data::Single(val);
+
+                old
+            }
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns the shared storage, or None if the object is unshared.  This includes the storage
+    /// created by clone() while the object was unshared.
+    fn shared(&self) -> Option<(&Arc<Storage<T>>, &Release)>
+    {
+        match self.data
+        {
+            Data::Multiple(ref mem, ref release) => Some((mem, release)),
+            _                                    => {
+                self.promoted.get().map(|(mem, release)| (mem, release))
+            }
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns the value of an unshared object, computing it first if the object was created by
+    /// default() or new_with().
+    fn local(&self) -> &Arc<T>
+    {
+        match self.data
+        {
+            Data::Single(ref val) => val,
+            Data::Lazy(ref lazy)  => LazyLock::force(lazy),
+            Data::Multiple(..)    => unreachable!()
+        }
+    }
+
+    //********************************************************************************************
+    /// Switches to the shared storage created by clone(), if there is one.
+    fn settle(&mut self)
+    {
+        if let Some((mem, release)) = self.promoted.take()
+        {
+            self.data = Data::Multiple(mem, release);
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns a read only view of the object.  The view shares the data like dup() does, but it
+    /// only provides get(), so it can be handed to code that must not set the value.
+    pub fn reader(&mut self) -> SharedReader<T>
+    {
+        match self.dup().data
+        {
+            Data::Multiple(data, release) => SharedReader { data, release },
+            _                             => unreachable!()
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T : Serialize + ?Sized> SharedObject<T>
+{
+    //********************************************************************************************
+    /// Serializes the value in place through with(), so the Arc isn't cloned.  If the object is
+    /// shared the read lock is held while the value is serialized.  Only available with the serde
+    /// feature.
+    pub fn serialize_with<S : Serializer>(
+        &self,
+        serializer : S
+        ) -> Result<S::Ok, S::Error>
+    {
+        self.with(|val| val.serialize(serializer))
+    }
+}
+
+impl<S : Clone> SharedObject<Option<S>>
+{
+    //********************************************************************************************
+    /// Replaces Some(slot) with None and returns the slot, or returns None if the object is
+    /// already empty.  The check and the swap happen under one write lock, so two instances can't
+    /// take the same slot.  The slot is cloned only if another reader still holds the old value.
+    pub fn take_if_some(&mut self) -> Option<Arc<S>>
+    {
+        let old = self.swap_if(None, Option::is_some)?;
+
+        match Arc::try_unwrap(old)
+        {
+            Ok(slot) => slot.map(Arc::new),
+            Err(old) => old.as_ref().clone().map(Arc::new)
+        }
+    }
+}
+
+impl<T : Eq + Hash> SharedObject<T>
+{
+    //********************************************************************************************
+    /// Replaces the value with new unless new is in seen, returning the previous value.  Returns
+    /// None and drops new if it was already seen, which is useful for skipping duplicate events.
+    pub fn swap_if_not_in(
+        &mut self,
+        new  : T,
+        seen : &HashSet<T>
+        ) -> Option<Arc<T>>
+    {
+        if seen.contains(&new)
+        {
+            return None;
+        }
+
+        self.swap_if(new, |_| true)
+    }
+}
+
+impl<T : Clone> SharedObject<T>
+{
+    //********************************************************************************************
+    /// Returns a clone of the value.  The lock is only held long enough to get the current value,
+    /// the clone itself is made outside of the lock.
+    pub fn get_cloned(&self) -> T
+    {
+        (*self.get()).clone()
+    }
+}
+
+impl<T : Clone> SharedObject<Vec<T>>
+{
+    //********************************************************************************************
+    /// Returns an iterator over a copy of the current vector.  The vector is cloned so the
+    /// iteration is not affected by other threads setting the value and the lock is not held
+    /// while iterating.
+    pub fn iter_snapshot(&self) -> ::std::vec::IntoIter<T>
+    {
+        self.get_cloned().into_iter()
+    }
+
+    //********************************************************************************************
+    /// Consumes the object and converts the vector into an Arc<[T]> that can be cheaply cloned.
+    /// The vector is moved if this is the last reference to it, otherwise it is cloned.
+    pub fn freeze_into_arc_slice(self) -> Arc<[T]>
+    {
+        let val = self.get();
+
+        drop(self);
+
+        Arc::from(Arc::try_unwrap(val).unwrap_or_else(|val| (*val).clone()))
+    }
+}
+
+impl<T> SharedObject<[T]>
+{
+    //********************************************************************************************
+    /// Construct a new instance of the object holding a slice, get() returns an Arc<[T]>.  The
+    /// elements are moved into the shared storage, they are never cloned so T doesn't need to be
+    /// Clone.  The value can't be replaced with set() since a slice isn't Sized.
+    pub fn from_boxed_slice(
+        value : Box<[T]>
+        ) -> SharedObject<[T]>
+    {
+        SharedObject::new_boxed(value)
+    }
+}
+
+impl SharedObject<String>
+{
+    //********************************************************************************************
+    /// Consumes the object and converts the string into an object holding its bytes.  The
+    /// string's buffer is reused if this is the last reference to it, otherwise it is copied.
+    /// The returned object isn't shared with the other instances.
+    pub fn into_bytes(self) -> SharedObject<Vec<u8>>
+    {
+        let val = self.get();
+
+        drop(self);
+
+        SharedObject::new(match Arc::try_unwrap(val)
+        {
+            Ok(val)  => val.into_bytes(),
+            Err(val) => val.as_bytes().to_vec()
+        })
+    }
+}
+
+//*************************************************************************************************
+/// Read only view of a SharedObject.
+///
+/// Created by SharedObject::reader(), the view shares the data with the object it was created from
+/// but only provides get(), so consumers holding a view can't set the value.  Cloning the view
+/// returns another view of the same data.  The view is Send and Sync when T is.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use shareable::SharedObject;
+///
+/// let mut value  = SharedObject::new(String::from("abc"));
+/// let reader     = value.reader();
+///
+/// value.set(String::from("xyz"));
+///
+/// let thread = thread::spawn(move || {
+///     assert_eq!(*reader.get(), "xyz");
+/// });
+///
+/// thread.join().unwrap();
+/// ```
+pub struct SharedReader<T : ?Sized>
+{
+    //---------------------------------------------------------------------------------------------
+    /// The internal data element, must come before the release guard.
+    data : Arc<Storage<T>>,
+
+    //---------------------------------------------------------------------------------------------
+    /// Wakes up wait_until_unique() when the view is dropped.
+    release : Release
+}
+
+impl<T : ?Sized> SharedReader<T>
+{
+    //********************************************************************************************
+    /// Returns the value of the object.
+    pub fn get(&self) -> Arc<T>
+    {
+        no_block::check();
+
+        let lock = self.data.read().unwrap_or_else(PoisonError::into_inner);
+
+        lock.clone()
+    }
+}
+
+impl<T : ?Sized> Clone for SharedReader<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Clone.
+    fn clone(&self) -> SharedReader<T>
+    {
+        SharedReader {
+            data    : self.data.clone(),
+            release : self.release.clone()
+        }
+    }
+}
+
+impl<T : ?Sized> Clone for SharedObject<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Clone, the same as dup() except that it only needs a shared reference.
+    /// Cloning an unshared object moves the value into shared storage, and this object switches
+    /// to that storage.
+    fn clone(&self) -> SharedObject<T>
+    {
+        let (data, release) = match self.data
+        {
+            Data::Multiple(ref mem, ref release) => (mem.clone(), release.clone()),
+            _                                    => {
+                let (mem, release) = self.promoted.get_or_init(|| {
+                    let value = self.get();
+
+                    test_hooks::hit(Point::Dup);
+
+                    (Arc::new(Storage::new(value)), Release::new())
+                });
+
+                (mem.clone(), release.clone())
+            }
+        };
+
+        SharedObject {
+            data     : Data::Multiple(data, release),
+            promoted : OnceLock::new(),
+
+            #[cfg(feature = "audit")]
+            audit : self.audit.share(),
+
+            #[cfg(feature = "thread-tracking")]
+            seen : self.seen.clone()
+        }
+    }
+}
+
+impl<T : Default> Default for SharedObject<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Default.  The default value is not created until it is first needed, so
+    /// an object that is defaulted and then set never allocates the default value.
+    fn default() -> SharedObject<T>
+    {
+        SharedObject {
+            data     : Data::Lazy(LazyLock::new(Box::new(|| Arc::new(T::default())))),
+            promoted : OnceLock::new(),
+
+            #[cfg(feature = "audit")]
+            audit : AuditLog::new(),
+
+            #[cfg(feature = "thread-tracking")]
+            seen : ThreadSet::new()
+        }
+    }
+}
+
+use std::fmt::{Debug, Display, Formatter, Error};
+use std::str::FromStr;
+
+impl<T : Debug + ?Sized> Debug for SharedObject<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Debug.  The alternate form ({:#?}) also shows the address of the shared
+    /// data and the number of instances sharing it, so two dumps with the same address refer to
+    /// the same data.  Objects that were never shared (or were created by snapshot()) show
+    /// "unshared" instead.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        if !f.alternate()
+        {
+            return write!(f, "{:?}", self.get());
+        }
+
+        let mut out = f.debug_struct("SharedObject");
+
+        out.field("value", &self.get());
+
+        match self.shared()
+        {
+            Some((mem, _)) => out
+                .field("data", &Arc::as_ptr(mem))
+                .field("strong_count", &Arc::strong_count(mem)),
+            None => out.field("data", &"unshared")
+        };
+
+        out.finish()
+    }
+}
+
+impl<T : Debug + ?Sized> Debug for SharedReader<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Debug.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{:?}", self.get())
+    }
+}
+
+impl<T : Display + ?Sized> Display for SharedObject<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Display.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{}", self.get())
+    }
+}
+
+impl<T : Display + ?Sized> Display for SharedReader<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Display.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{}", self.get())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T : Serialize + ?Sized> Serialize for SharedObject<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Serialize, see serialize_with().
+    fn serialize<S : Serializer>(
+        &self,
+        serializer : S
+        ) -> Result<S::Ok, S::Error>
+    {
+        self.serialize_with(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T : Deserialize<'de>> Deserialize<'de> for SharedObject<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Deserialize, the loaded value starts out unshared.
+    fn deserialize<D : Deserializer<'de>>(
+        deserializer : D
+        ) -> Result<SharedObject<T>, D::Error>
+    {
+        T::deserialize(deserializer).map(SharedObject::new)
+    }
+}
+
+impl<T : PartialEq + ?Sized> PartialEq for SharedObject<T>
+{
+    //*********************************************************************************************
+    /// Implementation of PartialEq, compares the values the objects hold.
+    fn eq(
+        &self,
+        other : &SharedObject<T>
+        ) -> bool
+    {
+        *self.get() == *other.get()
+    }
+}
+
+impl<T : Eq + ?Sized> Eq for SharedObject<T> {}
+
+impl<T : PartialEq> PartialEq<T> for SharedObject<T>
+{
+    //*********************************************************************************************
+    /// Implementation of PartialEq against a plain value, compares it to the value held.
+    fn eq(
+        &self,
+        other : &T
+        ) -> bool
+    {
+        *self.get() == *other
+    }
+}
+
+impl<T : Hash + ?Sized> Hash for SharedObject<T>
+{
+    //*********************************************************************************************
+    /// Implementation of Hash, hashes the current value.  Changing the value of a key in a map or
+    /// set breaks the lookup, the same as with any interior mutable key.
+    fn hash<H : Hasher>(
+        &self,
+        state : &mut H
+        )
+    {
+        self.get().hash(state)
+    }
+}
+
+impl<T : FromStr> FromStr for SharedObject<T>
+{
+    type Err = T::Err;
+
+    //*********************************************************************************************
+    /// Implementation of FromStr, parses the value the same way T does.
+    fn from_str(
+        s : &str
+        ) -> Result<SharedObject<T>, T::Err>
+    {
+        T::from_str(s).map(SharedObject::new)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    //*********************************************************************************************
+    /// Test that get/set work with only 1 instance.
+    #[test]
+    fn single()
+    {
+        let mut test = super::SharedObject::new(String::from("abc"));
+
+        assert_eq!(*test.get(), "abc");
+        test.set(String::from("xyz"));
+        assert_eq!(*test.get(), "xyz");
+    }
+
+    //*********************************************************************************************
+    /// Test that get/set work with multiple instances.
+    #[test]
+    fn multiple()
+    {
+        let mut test1 = super::SharedObject::new(String::from("abc"));
+        let mut test2 = test1.dup();
+        let mut test3 = test2.dup();
+
+        assert_eq!(*test1.get(), "abc");
+        assert_eq!(*test2.get(), "abc");
+        assert_eq!(*test3.get(), "abc");
+
+        test1.set(String::from("xyz"));
+
+        assert_eq!(*test1.get(), "xyz");
+        assert_eq!(*test2.get(), "xyz");
+        assert_eq!(*test3.get(), "xyz");
+
+        test2.set(String::from("mno"));
+
+        assert_eq!(*test1.get(), "mno");
+        assert_eq!(*test2.get(), "mno");
+        assert_eq!(*test3.get(), "mno");
+
+        test3.set(String::from("123"));
+
+        assert_eq!(*test1.get(), "123");
         assert_eq!(*test2.get(), "123");
         assert_eq!(*test3.get(), "123");
     }
+
+    //*********************************************************************************************
+    /// Test that a large boxed value can be used to construct the object.
+    #[test]
+    fn new_boxed()
+    {
+        use std::convert::TryInto;
+
+        let boxed : Box<[u8; 1 << 20]> = vec![7u8; 1 << 20].into_boxed_slice().try_into().unwrap();
+        let mut test1 = super::SharedObject::new_boxed(boxed);
+        let test2 = test1.dup();
+
+        assert_eq!(test1.get().len(), 1 << 20);
+        assert!(test1.get().iter().all(|&b| b == 7));
+        assert_eq!(test2.get()[(1 << 20) - 1], 7);
+    }
+
+    //*********************************************************************************************
+    /// Test that wait_until_unique() blocks until the other instances are dropped.
+    #[test]
+    fn wait_until_unique()
+    {
+        use std::thread;
+        use std::time::Duration;
+
+        let mut test1 = super::SharedObject::new(String::from("abc"));
+        let test2     = test1.dup();
+
+        let thread = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            drop(test2);
+        });
+
+        test1.wait_until_unique();
+
+        assert_eq!(test1.into_inner(), Some(String::from("abc")));
+        thread.join().unwrap();
+    }
+
+    //*********************************************************************************************
+    /// Test that into_inner() fails while the data is still shared.
+    #[test]
+    fn into_inner_shared()
+    {
+        let mut test1 = super::SharedObject::new(String::from("abc"));
+        let test2     = test1.dup();
+
+        assert_eq!(test1.into_inner(), None);
+        assert_eq!(test2.into_inner(), Some(String::from("abc")));
+    }
+
+    //*********************************************************************************************
+    /// Test that share() links the objects and snapshot() does not.
+    #[test]
+    fn share_snapshot()
+    {
+        let mut test1 = super::SharedObject::new(String::from("abc"));
+        let test2     = test1.share();
+        let test3     = test1.snapshot();
+
+        test1.set(String::from("xyz"));
+
+        assert_eq!(*test1.get(), "xyz");
+        assert_eq!(*test2.get(), "xyz");
+        assert_eq!(*test3.get(), "abc");
+    }
+
+    //*********************************************************************************************
+    /// Test that iterating a snapshot is not affected by another thread setting the value.
+    #[test]
+    fn iter_snapshot()
+    {
+        use std::sync::mpsc;
+        use std::thread;
+
+        let mut test1 = super::SharedObject::new(vec![1, 2, 3, 4]);
+        let mut test2 = test1.dup();
+
+        let (tx, rx) = mpsc::channel();
+
+        let mut iter = test1.iter_snapshot();
+
+        assert_eq!(iter.next(), Some(1));
+
+        let thread = thread::spawn(move || {
+            test2.set(vec![9, 9]);
+            tx.send(()).unwrap();
+        });
+
+        rx.recv().unwrap();
+
+        assert_eq!(iter.collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert_eq!(*test1.get(), vec![9, 9]);
+
+        thread.join().unwrap();
+    }
+
+    //*********************************************************************************************
+    /// Test that is_uniquely_owned() tracks the other instances.
+    #[test]
+    fn is_uniquely_owned()
+    {
+        let mut test1 = super::SharedObject::new(String::from("abc"));
+
+        assert!(test1.is_uniquely_owned());
+
+        let test2 = test1.dup();
+
+        assert!(!test1.is_uniquely_owned());
+        assert!(!test2.is_uniquely_owned());
+
+        drop(test2);
+
+        assert!(test1.is_uniquely_owned());
+    }
+
+    //*********************************************************************************************
+    /// Test that a defaulted object creates the default value when needed.
+    #[test]
+    fn default()
+    {
+        let mut test1 : super::SharedObject<Vec<u8>> = Default::default();
+
+        assert!(test1.get().is_empty());
+
+        let mut test2 : super::SharedObject<Vec<u8>> = Default::default();
+        let mut test3 = test2.dup();
+
+        test1.set(vec![1, 2]);
+        test3.set(vec![3]);
+
+        assert_eq!(*test1.get(), vec![1, 2]);
+        assert_eq!(*test2.get(), vec![3]);
+
+        let test4 : super::SharedObject<String> = Default::default();
+
+        assert_eq!(test4.into_inner(), Some(String::new()));
+    }
+
+    //*********************************************************************************************
+    /// Test that swap_with() exchanges the values seen by every object.
+    #[test]
+    fn swap_with()
+    {
+        let mut test1 = super::SharedObject::new(String::from("abc"));
+        let mut test2 = super::SharedObject::new(String::from("xyz"));
+
+        test1.swap_with(&mut test2);
+
+        assert_eq!(*test1.get(), "xyz");
+        assert_eq!(*test2.get(), "abc");
+
+        let mut test3 = test1.dup();
+
+        test1.swap_with(&mut test2);
+
+        assert_eq!(*test3.get(), "abc");
+        assert_eq!(*test2.get(), "xyz");
+
+        let mut test4 = test2.dup();
+
+        test3.swap_with(&mut test4);
+
+        assert_eq!(*test1.get(), "xyz");
+        assert_eq!(*test2.get(), "abc");
+
+        let mut test5 = test1.dup();
+
+        test1.swap_with(&mut test5);
+
+        assert_eq!(*test5.get(), "xyz");
+    }
+
+    //*********************************************************************************************
+    /// Test that swapping the same pair in opposite directions from two threads doesn't
+    /// deadlock.
+    #[test]
+    fn swap_with_threads()
+    {
+        use std::thread;
+
+        let mut test1 = super::SharedObject::new(1);
+        let mut test2 = super::SharedObject::new(2);
+
+        let (mut a1, mut a2) = (test1.dup(), test2.dup());
+        let (mut b1, mut b2) = (test1.dup(), test2.dup());
+
+        let thread1 = thread::spawn(move || for _ in 0..10000 { a1.swap_with(&mut a2); });
+        let thread2 = thread::spawn(move || for _ in 0..10000 { b2.swap_with(&mut b1); });
+
+        thread1.join().unwrap();
+        thread2.join().unwrap();
+
+        assert_eq!(*test1.get() + *test2.get(), 3);
+    }
+
+    //*********************************************************************************************
+    /// Test that swapping with a shared object doesn't lose the updates another thread makes to
+    /// it while the swap is running.
+    #[test]
+    fn swap_with_shared_other()
+    {
+        use std::thread;
+
+        let mut test1  = super::SharedObject::new(0);
+        let mut test2  = super::SharedObject::new(0);
+        let mut writer = test2.dup();
+
+        let thread = thread::spawn(move || for _ in 0..10000 { writer.update(|val| val + 1); });
+
+        for _ in 0..10000
+        {
+            test1.swap_with(&mut test2);
+        }
+
+        thread.join().unwrap();
+
+        assert!(!test1.is_shared());
+        assert_eq!(*test1.get() + *test2.get(), 10000);
+    }
+
+    //*********************************************************************************************
+    /// Test that a reader sees updates from another thread and keeps the data shared.
+    #[test]
+    fn reader()
+    {
+        use std::thread;
+
+        let mut test1 = super::SharedObject::new(String::from("abc"));
+        let reader1   = test1.reader();
+        let reader2   = reader1.clone();
+
+        test1.set(String::from("xyz"));
+
+        let thread = thread::spawn(move || {
+            assert_eq!(*reader2.get(), "xyz");
+        });
+
+        thread.join().unwrap();
+
+        assert_eq!(*reader1.get(), "xyz");
+        assert!(!test1.is_uniquely_owned());
+
+        drop(reader1);
+
+        assert!(test1.is_uniquely_owned());
+    }
+
+    //*********************************************************************************************
+    /// Test that freeze_into_arc_slice() returns the final contents.
+    #[test]
+    fn freeze_into_arc_slice()
+    {
+        let mut test1 = super::SharedObject::new(vec![1, 2, 3]);
+        let mut test2 = test1.dup();
+
+        test2.set(vec![4, 5, 6]);
+
+        let slice = test1.freeze_into_arc_slice();
+
+        assert_eq!(&*slice, &[4, 5, 6]);
+
+        test2.set(vec![7]);
+
+        assert_eq!(&*slice, &[4, 5, 6]);
+        assert_eq!(&*test2.freeze_into_arc_slice(), &[7]);
+    }
+
+    //*********************************************************************************************
+    /// Test that get_with() and set_with() accept any ordering.
+    #[test]
+    fn get_set_with()
+    {
+        use std::sync::atomic::Ordering;
+
+        let mut test1 = super::SharedObject::new(1);
+        let mut test2 = test1.dup();
+
+        test2.set_with(2, Ordering::SeqCst);
+        assert_eq!(*test1.get_with(Ordering::Acquire), 2);
+
+        test1.set_with(3, Ordering::Acquire);
+        assert_eq!(*test2.get_with(Ordering::Release), 3);
+    }
+
+    //*********************************************************************************************
+    /// Test that swap() returns the previous value for unshared, lazy, and shared objects.
+    #[test]
+    fn swap()
+    {
+        let mut test1 = super::SharedObject::new(1);
+
+        assert_eq!(*test1.swap(2), 1);
+        assert_eq!(*test1.get(), 2);
+
+        let mut test2 = super::SharedObject::new_with(|| 3);
+
+        assert_eq!(*test2.swap(4), 3);
+        assert_eq!(*test2.get(), 4);
+
+        let mut test3 = test1.dup();
+
+        assert_eq!(*test3.swap(5), 2);
+        assert_eq!(*test1.swap(6), 5);
+        assert_eq!(*test3.get(), 6);
+    }
+
+    //*********************************************************************************************
+    /// Test that with_value() restores the value after the scope, including after a panic.
+    #[test]
+    fn with_value()
+    {
+        use std::panic::{self, AssertUnwindSafe};
+
+        let mut test1 = super::SharedObject::new(String::from("abc"));
+        let test2     = test1.dup();
+
+        assert_eq!(test1.with_value(String::from("xyz"), || test2.get()).as_str(), "xyz");
+        assert_eq!(*test2.get(), "abc");
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            test1.with_value(String::from("xyz"), || panic!("inside the scope"))
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(*test2.get(), "abc");
+    }
+
+    //*********************************************************************************************
+    /// Test that with() borrows the value in every state.
+    #[test]
+    fn with()
+    {
+        let mut test1 = super::SharedObject::new_with(|| String::from("abc"));
+
+        assert_eq!(test1.with(|val| val.len()), 3);
+
+        let test2 = test1.dup();
+
+        test1.set(String::from("wxyz"));
+
+        assert_eq!(test2.with(|val| val.clone()), "wxyz");
+    }
+
+    //*********************************************************************************************
+    /// Test that serialize_with() serializes the value without cloning the Arc.
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serialize_with()
+    {
+        extern crate serde_json;
+
+        use std::cell::RefCell;
+        use std::sync::{Arc, Weak};
+        use serde::{Serialize, Serializer};
+
+        struct Table(Vec<u64>);
+
+        thread_local! {
+            static TABLE : RefCell<Weak<Table>> = const { RefCell::new(Weak::new()) };
+        }
+
+        impl Serialize for Table
+        {
+            fn serialize<S : Serializer>(
+                &self,
+                serializer : S
+                ) -> Result<S::Ok, S::Error>
+            {
+                assert_eq!(TABLE.with(|table| table.borrow().strong_count()), 1);
+
+                self.0.serialize(serializer)
+            }
+        }
+
+        let mut test1 = super::SharedObject::new(Table((0..10_000).collect()));
+
+        TABLE.with(|table| *table.borrow_mut() = Arc::downgrade(&test1.get()));
+
+        let json = serde_json::to_string(&test1).unwrap();
+
+        assert!(json.starts_with("[0,1,2,"));
+
+        let test2 = test1.dup();
+
+        TABLE.with(|table| *table.borrow_mut() = Arc::downgrade(&test2.get()));
+
+        let mut out = Vec::new();
+
+        test2.serialize_with(&mut serde_json::Serializer::new(&mut out)).unwrap();
+
+        assert_eq!(out, json.into_bytes());
+    }
+
+    //*********************************************************************************************
+    /// Test that a value survives a round trip through serde_json and comes back unshared.
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_round_trip()
+    {
+        extern crate serde_json;
+
+        let mut test1 = super::SharedObject::new(String::from("abc"));
+        let _test2    = test1.dup();
+
+        let json = serde_json::to_string(&test1).unwrap();
+
+        assert_eq!(json, "\"abc\"");
+
+        let test3 : super::SharedObject<String> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(*test3.get(), "abc");
+        assert!(!test3.is_shared());
+    }
+
+    //*********************************************************************************************
+    /// Test that swap_if() only swaps when the predicate passes for the current value.
+    #[test]
+    fn swap_if()
+    {
+        let mut test1 = super::SharedObject::new(5);
+        let mut test2 = test1.dup();
+
+        assert_eq!(test1.swap_if(20, |cur| *cur > 10), None);
+        assert_eq!(*test2.get(), 5);
+
+        assert_eq!(test2.swap_if(20, |cur| *cur < 10).map(|old| *old), Some(5));
+        assert_eq!(*test1.get(), 20);
+
+        let mut test3 = super::SharedObject::new(1);
+
+        assert_eq!(test3.swap_if(2, |cur| *cur == 1).map(|old| *old), Some(1));
+        assert_eq!(test3.swap_if(3, |cur| *cur == 1), None);
+        assert_eq!(*test3.get(), 2);
+    }
+
+    //*********************************************************************************************
+    /// Test that swap_if_not_in() skips values that were already seen.
+    #[test]
+    fn swap_if_not_in()
+    {
+        use std::collections::HashSet;
+
+        let mut seen  = HashSet::new();
+        let mut test1 = super::SharedObject::new(String::from("a"));
+        let test2     = test1.dup();
+
+        seen.insert(String::from("b"));
+
+        assert_eq!(test1.swap_if_not_in(String::from("b"), &seen), None);
+        assert_eq!(*test2.get(), "a");
+
+        assert_eq!(test1.swap_if_not_in(String::from("c"), &seen).unwrap().as_str(), "a");
+        assert_eq!(*test2.get(), "c");
+    }
+
+    //*********************************************************************************************
+    /// Test sharing a large slice of values that can't be cloned.
+    #[test]
+    fn from_boxed_slice()
+    {
+        use std::thread;
+
+        struct Entry(usize);
+
+        let table     = (0..100_000).map(Entry).collect::<Vec<_>>().into_boxed_slice();
+        let mut test1 = super::SharedObject::from_boxed_slice(table);
+        let test2     = test1.dup();
+
+        let thread = thread::spawn(move || {
+            let table = test2.get();
+
+            (table.len(), table[12_345].0)
+        });
+
+        assert_eq!(thread.join().unwrap(), (100_000, 12_345));
+        assert_eq!(test1.get()[99_999].0, 99_999);
+    }
+
+    //*********************************************************************************************
+    /// Test that into_bytes() reuses the buffer when this is the only instance.
+    #[test]
+    fn into_bytes_reuses()
+    {
+        let mut test1 = super::SharedObject::new(String::from("abc"));
+        let test2     = test1.dup();
+        let ptr       = test2.get().as_ptr();
+
+        drop(test1);
+
+        let bytes = test2.into_bytes();
+
+        assert_eq!(*bytes.get(), b"abc");
+        assert_eq!(bytes.get().as_ptr(), ptr);
+    }
+
+    //*********************************************************************************************
+    /// Test that into_bytes() copies the string when it is still shared.
+    #[test]
+    fn into_bytes_copies()
+    {
+        let mut test1 = super::SharedObject::new(String::from("abc"));
+        let test2     = test1.dup();
+        let ptr       = test1.get().as_ptr();
+        let bytes     = test1.into_bytes();
+
+        assert_eq!(*bytes.get(), b"abc");
+        assert_ne!(bytes.get().as_ptr(), ptr);
+        assert_eq!(*test2.get(), "abc");
+    }
+
+    //*********************************************************************************************
+    /// Test that the version counts the sets after the data is shared.
+    #[test]
+    fn read_versioned()
+    {
+        use std::sync::Arc;
+
+        let mut test1 = super::SharedObject::new(String::from("abc"));
+
+        test1.set(String::from("def"));
+
+        assert_eq!(test1.read_versioned(), (Arc::new(String::from("def")), 0));
+
+        let mut test2 = test1.dup();
+
+        test2.set(String::from("xyz"));
+
+        assert_eq!(test1.read_versioned(), (Arc::new(String::from("xyz")), 1));
+
+        let mut test3 = super::SharedObject::new(String::from("123"));
+
+        test1.swap_with(&mut test3);
+
+        assert_eq!(test2.read_versioned(), (Arc::new(String::from("123")), 2));
+    }
+
+    //*********************************************************************************************
+    /// Test that readers never see a value with the wrong version.
+    #[test]
+    fn read_versioned_threads()
+    {
+        use std::sync::Arc;
+        use std::thread;
+
+        let mut writer = super::SharedObject::new(0u64);
+        let mut threads = Vec::new();
+
+        for _ in 0..4
+        {
+            let reader = writer.dup();
+
+            threads.push(thread::spawn(move || {
+                let mut last = 0;
+
+                for _ in 0..10000
+                {
+                    let (val, version) = reader.read_versioned();
+
+                    assert_eq!(*val, version);
+                    assert!(version >= last);
+
+                    last = version;
+                }
+            }));
+        }
+
+        for i in 1..10001
+        {
+            writer.set(i);
+        }
+
+        for thread in threads
+        {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(writer.read_versioned(), (Arc::new(10000), 10000));
+    }
+
+    //*********************************************************************************************
+    /// Test that the alternate Debug output shows which objects share the data, telling a shared
+    /// pair from a forked pair without calling a method.
+    #[test]
+    fn debug_state()
+    {
+        let mut test1 = super::SharedObject::new(String::from("abc"));
+        let test2     = test1.share();
+        let mut fork1 = test1.snapshot();
+
+        assert!(format!("{:#?}", fork1).contains("unshared"));
+
+        let fork2 = fork1.share();
+
+        assert_eq!(format!("{:?}", test1), "\"abc\"");
+        assert_eq!(format!("{:?}", fork1), "\"abc\"");
+        assert_eq!(format!("{:#?}", test1), format!("{:#?}", test2));
+        assert_eq!(format!("{:#?}", fork1), format!("{:#?}", fork2));
+        assert_ne!(format!("{:#?}", test1), format!("{:#?}", fork1));
+        assert!(format!("{:#?}", test1).contains("strong_count: 2"));
+        assert!(format!("{:#?}", fork1).contains("strong_count: 2"));
+    }
+
+    //*********************************************************************************************
+    /// Test that try_get() fails while another thread holds the write lock.
+    #[test]
+    fn try_get()
+    {
+        let mut test1 = super::SharedObject::new(5);
+
+        assert_eq!(test1.try_get().map(|val| *val), Some(5));
+
+        let test2 = test1.dup();
+
+        if let super::Data::Multiple(ref mem, _) = test2.data
+        {
+            let _lock = mem.write().unwrap();
+
+            assert_eq!(test1.try_get(), None);
+        }
+
+        assert_eq!(test1.try_get().map(|val| *val), Some(5));
+    }
+
+    //*********************************************************************************************
+    /// Test that poll() only returns the value once after each set.
+    #[test]
+    fn poll()
+    {
+        let mut test1 = super::SharedObject::new(1);
+        let mut test2 = test1.dup();
+        let mut token = 0;
+
+        assert_eq!(test1.poll(&mut token), None);
+
+        test2.set(2);
+
+        assert_eq!(test1.poll(&mut token).map(|val| *val), Some(2));
+        assert_eq!(test1.poll(&mut token), None);
+        assert_eq!(test1.poll(&mut token), None);
+
+        test2.set(3);
+        test2.set(4);
+
+        assert_eq!(test1.poll(&mut token).map(|val| *val), Some(4));
+        assert_eq!(test1.poll(&mut token), None);
+        assert_eq!(token, 3);
+    }
+
+    //*********************************************************************************************
+    /// Test that the function given to new_with() runs once, on the first read.
+    #[test]
+    fn new_with()
+    {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use std::thread;
+
+        let calls   = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+
+        let test = super::SharedObject::new_with(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+
+            String::from("abc")
+        });
+
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        thread::scope(|scope| {
+            for _ in 0..8
+            {
+                scope.spawn(|| assert_eq!(*test.get(), "abc"));
+            }
+        });
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(test.into_inner(), Some(String::from("abc")));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    //*********************************************************************************************
+    /// Test that adopt() uses the Arc it was given.
+    #[test]
+    fn adopt()
+    {
+        use std::sync::Arc;
+
+        let config    = Arc::new(String::from("abc"));
+        let mut test1 = super::SharedObject::adopt(config.clone());
+
+        assert!(Arc::ptr_eq(&test1.get(), &config));
+
+        let test2 = test1.dup();
+
+        assert!(Arc::ptr_eq(&test2.get(), &config));
+    }
+
+    //*********************************************************************************************
+    /// Test that the audit log records the sets made by all the instances in order.
+    #[test]
+    #[cfg(feature = "audit")]
+    fn audit_log()
+    {
+        let mut test1 = super::SharedObject::new(1);
+
+        test1.set(2);
+
+        let mut test2 = test1.dup();
+
+        test2.set(3);
+        test1.set(4);
+
+        let log = test2.audit_log();
+
+        assert_eq!(log.len(), 3);
+        assert_eq!(log.iter().map(|c| (*c.old, *c.new)).collect::<Vec<_>>(), [(1, 2), (2, 3), (3, 4)]);
+        assert!(log[0].timestamp <= log[1].timestamp && log[1].timestamp <= log[2].timestamp);
+        assert!(test1.snapshot().audit_log().is_empty());
+    }
+
+    //*********************************************************************************************
+    /// Test that swap_with() records the exchanged values in the audit log of both objects.
+    #[test]
+    #[cfg(feature = "audit")]
+    fn audit_log_swap_with()
+    {
+        let mut test1 = super::SharedObject::new(1);
+        let mut test2 = super::SharedObject::new(2);
+        let mut test3 = super::SharedObject::new(3);
+        let test4     = test1.dup();
+        let test5     = test2.dup();
+
+        test1.swap_with(&mut test2);
+        test3.swap_with(&mut test1);
+
+        let changes = |test : &super::SharedObject<i32>| {
+            test.audit_log().iter().map(|c| (*c.old, *c.new)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(changes(&test4), [(1, 2), (2, 3)]);
+        assert_eq!(changes(&test5), [(2, 1)]);
+        assert_eq!(changes(&test3), [(3, 2)]);
+    }
+
+    //*********************************************************************************************
+    /// Test that the audit log records both the override and the restore of with_value().
+    #[test]
+    #[cfg(feature = "audit")]
+    fn audit_log_with_value()
+    {
+        let mut test1 = super::SharedObject::new(1);
+        let test2     = test1.dup();
+
+        test1.with_value(2, || ());
+
+        let log = test2.audit_log();
+
+        assert_eq!(log.iter().map(|c| (*c.old, *c.new)).collect::<Vec<_>>(), [(1, 2), (2, 1)]);
+    }
+
+    //*********************************************************************************************
+    /// Test that will_clone_on_write() is only true while the value is shared.
+    #[test]
+    fn will_clone_on_write()
+    {
+        let mut test1 = super::SharedObject::new(String::from("abc"));
+
+        assert!(!test1.will_clone_on_write());
+
+        let value = test1.get();
+
+        assert!(test1.will_clone_on_write());
+
+        drop(value);
+
+        let test2 = test1.dup();
+
+        assert!(test1.will_clone_on_write());
+        assert!(test2.will_clone_on_write());
+
+        drop(test2);
+
+        assert!(!test1.will_clone_on_write());
+        assert!(!super::SharedObject::<String>::default().will_clone_on_write());
+    }
+
+    //*********************************************************************************************
+    /// Test that the validator fails once another thread sets the value.
+    #[test]
+    fn read_and_validator()
+    {
+        use std::thread;
+
+        let mut test1 = super::SharedObject::new(String::from("abc"));
+        let mut test2 = test1.dup();
+
+        let (val, valid) = test1.read_and_validator();
+
+        assert_eq!(*val, "abc");
+        assert!(valid(&test1));
+
+        thread::spawn(move || test2.set(String::from("abc"))).join().unwrap();
+
+        assert!(!valid(&test1));
+
+        let mut test3  = super::SharedObject::new(1);
+        let (_, valid) = test3.read_and_validator();
+
+        test3.set(1);
+
+        assert!(!valid(&test3));
+    }
+
+    //*********************************************************************************************
+    /// Test that the finalizer runs once, after the last instance is dropped.
+    #[test]
+    fn on_last_drop()
+    {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use std::thread;
+
+        let calls     = Arc::new(AtomicUsize::new(0));
+        let counter   = calls.clone();
+        let mut test1 = super::SharedObject::new(String::from("abc"));
+
+        test1.on_last_drop(Box::new(move |val| {
+            assert_eq!(val, "xyz");
+            counter.fetch_add(1, Ordering::SeqCst);
+        }));
+
+        let mut test2 = test1.dup();
+        let test3     = test1.reader();
+
+        thread::spawn(move || test2.set(String::from("xyz"))).join().unwrap();
+
+        drop(test1);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        drop(test3);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let mut test4 = super::SharedObject::new(1);
+
+        test4.on_last_drop(Box::new(|_| panic!("the value was taken")));
+
+        assert_eq!(test4.into_inner(), Some(1));
+    }
+
+    //*********************************************************************************************
+    /// Test that pre_share() moves the value into the storage used by dup().
+    #[test]
+    fn pre_share()
+    {
+        let mut test1 = super::SharedObject::new(String::from("abc"));
+
+        assert!(!test1.is_shared());
+
+        test1.pre_share();
+
+        assert!(test1.is_shared());
+        assert!(test1.is_uniquely_owned());
+
+        let test2 = test1.dup();
+
+        match (&test1.data, &test2.data)
+        {
+            (super::Data::Multiple(mem1, _), super::Data::Multiple(mem2, _)) => {
+                assert!(::std::sync::Arc::ptr_eq(mem1, mem2))
+            },
+            _ => panic!("the objects are not shared")
+        }
+    }
+
+    //*********************************************************************************************
+    /// Test parsing the value from a string.
+    #[test]
+    fn from_str()
+    {
+        let test : super::SharedObject<u32> = "12".parse().unwrap();
+
+        assert_eq!(*test.get(), 12);
+        assert!("abc".parse::<super::SharedObject<u32>>().is_err());
+    }
+
+    //*********************************************************************************************
+    /// Test that clone() shares the value through a shared reference, like dup() does.
+    #[test]
+    fn clone()
+    {
+        let mut test1 = super::SharedObject::new(1);
+        let holder    = &test1;
+        let mut test2 = holder.clone();
+        let test3     = test2.clone();
+
+        assert!(test1.is_shared());
+        assert!(!test1.is_uniquely_owned());
+
+        test1.set(2);
+        assert_eq!(*test2.get(), 2);
+        assert_eq!(*test3.get(), 2);
+
+        test2.set(3);
+        assert_eq!(*test1.get(), 3);
+
+        drop(test2);
+        drop(test3);
+        assert!(test1.is_uniquely_owned());
+    }
+
+    //*********************************************************************************************
+    /// Test that cloning a lazy object computes the value once and shares it.
+    #[test]
+    fn clone_lazy()
+    {
+        let test1     = super::SharedObject::new_with(|| String::from("abc"));
+        let mut test2 = test1.clone();
+
+        test2.set(String::from("xyz"));
+
+        assert_eq!(*test1.get(), "xyz");
+        assert_eq!(test1.with(|val| val.len()), 3);
+        assert_eq!(test1.into_inner(), None);
+    }
+
+    //*********************************************************************************************
+    /// Test that every subscriber gets the values set by all the instances, and that dropped
+    /// subscribers are skipped.
+    #[test]
+    fn subscribe()
+    {
+        let mut test1 = super::SharedObject::new(0);
+        let rx1       = test1.subscribe();
+        let mut test2 = test1.dup();
+        let rx2       = test2.subscribe();
+        let rx3       = test1.subscribe();
+
+        drop(rx3);
+
+        test1.set(1);
+        test2.set(2);
+        test1.swap(3);
+
+        let values1 : Vec<_> = rx1.try_iter().map(|val| *val).collect();
+        let values2 : Vec<_> = rx2.try_iter().map(|val| *val).collect();
+
+        assert_eq!(values1, vec![1, 2, 3]);
+        assert_eq!(values2, vec![1, 2, 3]);
+
+        drop(test1);
+        drop(test2);
+
+        assert!(rx1.recv().is_err());
+    }
+
+    //*********************************************************************************************
+    /// Test that update() doesn't lose appends made by several threads.
+    #[test]
+    fn update()
+    {
+        use std::thread;
+
+        let mut test = super::SharedObject::new(String::new());
+
+        test.update(|val| format!("{}-", val));
+        assert_eq!(*test.get(), "-");
+
+        let threads : Vec<_> = (0..2).map(|_| {
+            let mut shared = test.dup();
+
+            thread::spawn(move || {
+                for _ in 0..500
+                {
+                    shared.update(|val| format!("{}x", val));
+                }
+            })
+        }).collect();
+
+        for thread in threads
+        {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(test.get().len(), 1001);
+    }
+
+    //*********************************************************************************************
+    /// Test that modify() returns the value it stored, in both the single and shared state.
+    #[test]
+    fn modify()
+    {
+        use std::sync::Arc;
+        use std::thread;
+
+        let mut test = super::SharedObject::new(String::from("a"));
+        let new      = test.modify(|val| format!("{}b", val));
+
+        assert_eq!(*new, "ab");
+        assert!(Arc::ptr_eq(&new, &test.get()));
+
+        let threads : Vec<_> = (0..2).map(|_| {
+            let mut shared = test.dup();
+
+            thread::spawn(move || {
+                for _ in 0..500
+                {
+                    let old = shared.get().len();
+                    let new = shared.modify(|val| format!("{}x", val));
+
+                    assert!(new.len() > old);
+                    assert!(new.ends_with('x'));
+                }
+            })
+        }).collect();
+
+        let new = test.modify(|val| format!("{}-", val));
+
+        assert!(new.ends_with('-'));
+
+        for thread in threads
+        {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(test.get().len(), 1003);
+
+        let new = test.modify(|val| val.clone());
+
+        assert!(Arc::ptr_eq(&new, &test.get()));
+    }
+
+    //*********************************************************************************************
+    /// Test that readers holding the read lock don't block each other.
+    #[test]
+    fn concurrent_readers()
+    {
+        use std::sync::{Arc, Barrier};
+        use std::thread;
+
+        let mut test    = super::SharedObject::new(5);
+        let barrier     = Arc::new(Barrier::new(3));
+        let mut threads = Vec::new();
+
+        for _ in 0..3
+        {
+            let reader  = test.dup();
+            let barrier = barrier.clone();
+
+            // Each thread waits inside with() until all 3 hold the read lock at once, which
+            // would never happen if the readers blocked each other.
+            threads.push(thread::spawn(move || reader.with(|val| { barrier.wait(); *val })));
+        }
+
+        for thread in threads
+        {
+            assert_eq!(thread.join().unwrap(), 5);
+        }
+
+        test.set(6);
+        assert_eq!(*test.get(), 6);
+    }
+
+    //*********************************************************************************************
+    /// Test that equality compares the values of unshared and shared objects.
+    #[test]
+    fn eq()
+    {
+        let mut test1 = super::SharedObject::new(String::from("abc"));
+        let test2     = test1.dup();
+        let test3     = super::SharedObject::new(String::from("abc"));
+
+        assert!(test1.is_shared());
+        assert!(!test3.is_shared());
+        assert_eq!(test1, test3);
+        assert_eq!(test2, String::from("abc"));
+
+        test1.set(String::from("xyz"));
+
+        assert_ne!(test2, test3);
+        assert_eq!(test1, test2);
+    }
+
+    //*********************************************************************************************
+    /// Test that take_if_some() hands each slot to only one of several claimers.
+    #[test]
+    fn take_if_some()
+    {
+        use std::sync::{Arc, Barrier};
+        use std::thread;
+
+        let mut test1 = super::SharedObject::new(None);
+
+        assert_eq!(test1.take_if_some(), None);
+
+        for round in 0..50
+        {
+            test1.set(Some(round));
+
+            let barrier = Arc::new(Barrier::new(4));
+            let threads : Vec<_> = (0..4).map(|_| {
+                let mut test2 = test1.dup();
+                let barrier   = barrier.clone();
+
+                thread::spawn(move || {
+                    barrier.wait();
+                    test2.take_if_some()
+                })
+            }).collect();
+
+            let claimed : Vec<_> = threads.into_iter()
+                .filter_map(|thread| thread.join().unwrap())
+                .collect();
+
+            assert_eq!(claimed, vec![Arc::new(round)]);
+            assert_eq!(*test1.get(), None);
+        }
+    }
+
+    //*********************************************************************************************
+    /// Test that an object can be found in a map through a distinct object with the same value.
+    #[test]
+    #[allow(clippy::mutable_key_type)]
+    fn hash()
+    {
+        use std::collections::HashMap;
+
+        let mut test1 = super::SharedObject::new(String::from("abc"));
+        let test2     = test1.dup();
+        let mut map   = HashMap::new();
+
+        map.insert(super::SharedObject::new(String::from("xyz")), 1);
+        map.insert(test2, 2);
+
+        assert_eq!(map.get(&super::SharedObject::new(String::from("abc"))), Some(&2));
+        assert_eq!(map.get(&test1), Some(&2));
+        assert_eq!(map.get(&super::SharedObject::new(String::from("xyz"))), Some(&1));
+    }
+
+    //*********************************************************************************************
+    /// Test that try_downgrade() only unshares the value once the other objects are dropped.
+    #[test]
+    fn try_downgrade()
+    {
+        let mut test1 = super::SharedObject::new(String::from("abc"));
+        let test2     = test1.dup();
+
+        assert!(!test1.try_downgrade());
+        assert!(test1.is_shared());
+
+        drop(test2);
+
+        assert!(test1.try_downgrade());
+        assert!(!test1.is_shared());
+        assert_eq!(*test1.get(), "abc");
+        assert!(test1.try_downgrade());
+    }
+
+    //*********************************************************************************************
+    /// Test that try_downgrade() keeps the shared storage while a finalizer is registered.
+    #[test]
+    fn try_downgrade_finalizer()
+    {
+        let mut test1 = super::SharedObject::new(5);
+
+        test1.on_last_drop(Box::new(|_| {}));
+
+        assert!(!test1.try_downgrade());
+        assert!(test1.is_shared());
+    }
+
+    //*********************************************************************************************
+    /// Test that accessing_threads() counts each thread using the shared data once.
+    #[test]
+    #[cfg(feature = "thread-tracking")]
+    fn accessing_threads()
+    {
+        use std::thread;
+
+        let mut test1 = super::SharedObject::new(5);
+
+        test1.get();
+        test1.set(6);
+
+        assert_eq!(test1.accessing_threads(), 1);
+
+        let test2 = test1.dup();
+
+        thread::spawn(move || {
+            test2.get();
+        }).join().unwrap();
+
+        test1.get();
+
+        assert_eq!(test1.accessing_threads(), 2);
+    }
+
+    //*********************************************************************************************
+    /// Test that accessing_threads() counts a thread that only used with_value().
+    #[test]
+    #[cfg(feature = "thread-tracking")]
+    fn accessing_threads_with_value()
+    {
+        use std::thread;
+
+        let mut test1 = super::SharedObject::new(5);
+        let mut test2 = test1.dup();
+
+        test1.set(6);
+
+        thread::spawn(move || {
+            test2.with_value(7, || ());
+        }).join().unwrap();
+
+        assert_eq!(test1.accessing_threads(), 2);
+    }
+
+    //*********************************************************************************************
+    /// Test that try_into_inner() only returns the value once nothing else refers to it.
+    #[test]
+    fn try_into_inner()
+    {
+        let test1 = super::SharedObject::new(String::from("abc"));
+
+        assert_eq!(test1.try_into_inner().ok(), Some(String::from("abc")));
+
+        let mut test1 = super::SharedObject::new(String::from("abc"));
+        let test2     = test1.dup();
+        let test1     = test1.try_into_inner().unwrap_err();
+
+        assert_eq!(*test1.get(), "abc");
+
+        drop(test2);
+
+        let value = test1.get();
+        let test1 = test1.try_into_inner().unwrap_err();
+
+        drop(value);
+
+        assert_eq!(test1.try_into_inner().ok(), Some(String::from("abc")));
+    }
+
+    //*********************************************************************************************
+    /// Test that get() and set() keep working after a thread panicked while holding the lock,
+    /// and that checked_get() and checked_set() report it.
+    #[test]
+    fn poisoned()
+    {
+        use std::thread;
+        use Poisoned;
+
+        let mut test1 = super::SharedObject::new(5);
+        let mut test2 = test1.dup();
+
+        assert_eq!(test1.checked_get().map(|val| *val), Ok(5));
+
+        let result = thread::spawn(move || {
+            test2.update(|_| panic!("poison the lock"));
+        }).join();
+
+        assert!(result.is_err());
+        assert_eq!(*test1.get(), 5);
+        assert_eq!(test1.checked_get(), Err(Poisoned));
+        assert_eq!(test1.checked_set(6), Err(Poisoned));
+        assert_eq!(*test1.get(), 5);
+
+        test1.set(7);
+
+        assert_eq!(*test1.get(), 7);
+    }
 }
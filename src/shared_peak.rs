@@ -0,0 +1,202 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+use SharedI64;
+
+//*************************************************************************************************
+/// Shareable i64 data element that tracks the largest value observed.
+///
+/// observe() only ever raises the value, so any number of threads can report values and get()
+/// always returns the largest one so far.  Before anything is observed get() returns i64::MIN.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+/// use shareable::SharedPeak;
+///
+/// let mut peak = SharedPeak::new();
+/// let worker   = peak.dup();
+///
+/// let thread = thread::spawn(move || {
+///     worker.observe(42);
+///     worker.observe(7);
+/// });
+///
+/// thread.join().unwrap();
+///
+/// assert_eq!(peak.get(), 42);
+/// ```
+pub struct SharedPeak
+{
+    //---------------------------------------------------------------------------------------------
+    /// The largest value observed.
+    value : SharedI64
+}
+
+impl SharedPeak
+{
+    //********************************************************************************************
+    /// Construct a new instance of the object that has not observed anything.
+    pub fn new() -> SharedPeak
+    {
+        SharedPeak {
+            value : SharedI64::new(i64::MIN)
+        }
+    }
+
+    //********************************************************************************************
+    /// Records a value, the peak is raised if the value is larger than it.
+    pub fn observe(
+        &self,
+        val : i64
+        )
+    {
+        self.value.fetch_max(val);
+    }
+
+    //********************************************************************************************
+    /// Returns the largest value observed, or i64::MIN if nothing was observed.
+    pub fn get(&self) -> i64
+    {
+        self.value.get()
+    }
+
+    //********************************************************************************************
+    /// Clears the peak, as if nothing was observed.
+    pub fn reset(&mut self)
+    {
+        self.value.set(i64::MIN);
+    }
+
+    //********************************************************************************************
+    /// Clones the object, the new object shares the peak.
+    pub fn dup(&mut self) -> SharedPeak
+    {
+        SharedPeak {
+            value : self.value.dup()
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns true if the peak is in the shared storage, which is the case once dup() has been
+    /// called.
+    pub fn is_shared(&self) -> bool
+    {
+        self.value.is_shared()
+    }
+}
+
+impl Default for SharedPeak
+{
+    //*********************************************************************************************
+    /// Implementation of Default.
+    fn default() -> SharedPeak
+    {
+        SharedPeak::new()
+    }
+}
+
+use std::fmt::{Debug, Display, Formatter, Error};
+
+impl Debug for SharedPeak
+{
+    //*********************************************************************************************
+    /// Implementation of Debug.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{:?}", self.get())
+    }
+}
+
+impl Display for SharedPeak
+{
+    //*********************************************************************************************
+    /// Implementation of Display.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{}", self.get())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::thread;
+
+    //*********************************************************************************************
+    /// Test observing and resetting with 1 instance.
+    #[test]
+    fn test_single()
+    {
+        let mut test = super::SharedPeak::new();
+
+        assert_eq!(test.get(), i64::MIN);
+
+        test.observe(-3);
+        test.observe(-9);
+        assert_eq!(test.get(), -3);
+
+        test.reset();
+        assert_eq!(test.get(), i64::MIN);
+    }
+
+    //*********************************************************************************************
+    /// Test that the peak is the maximum observed by all the threads.
+    #[test]
+    fn test_threads()
+    {
+        let mut test    = super::SharedPeak::new();
+        let mut threads = Vec::new();
+
+        for t in 0..4
+        {
+            let peak = test.dup();
+
+            threads.push(thread::spawn(move || {
+                for i in 0..1000
+                {
+                    peak.observe((i * 7 + t * 13) % 997);
+                }
+            }));
+        }
+
+        for thread in threads
+        {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(test.get(), 996);
+
+        test.reset();
+        test.observe(5);
+
+        assert_eq!(test.get(), 5);
+    }
+
+    //*********************************************************************************************
+    /// Test that is_shared() is false until dup() is called.
+    #[test]
+    fn test_is_shared()
+    {
+        let mut test1 = super::SharedPeak::new();
+
+        assert!(!test1.is_shared());
+
+        let test2 = test1.dup();
+
+        assert!(test1.is_shared());
+        assert!(test2.is_shared());
+    }
+}
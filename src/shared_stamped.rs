@@ -0,0 +1,187 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+use std::sync::Arc;
+use std::time::Instant;
+
+use SharedObject;
+
+//*************************************************************************************************
+/// Shareable value paired with the time it was last set.
+///
+/// The value and the timestamp are replaced together, so get() always returns the timestamp of
+/// the value it returns.  This makes it usable for freshness checks on cache entries, where
+/// reading the value and its timestamp separately could pair a new value with an old time.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use shareable::SharedStamped;
+///
+/// let mut entry = SharedStamped::new(String::from("abc"));
+/// let reader    = entry.dup();
+///
+/// entry.set(String::from("xyz"));
+///
+/// let (value, stamp) = reader.get();
+///
+/// assert_eq!(*value, "xyz");
+/// assert!(stamp.elapsed() < Duration::from_secs(60));
+/// ```
+pub struct SharedStamped<V>
+{
+    //---------------------------------------------------------------------------------------------
+    /// The value and the time it was set.
+    data : SharedObject<(Arc<V>, Instant)>
+}
+
+impl<V> SharedStamped<V>
+{
+    //********************************************************************************************
+    /// Construct a new instance of the object, stamped with the current time.
+    pub fn new(
+        value : V
+        ) -> SharedStamped<V>
+    {
+        SharedStamped {
+            data : SharedObject::new((Arc::new(value), Instant::now()))
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns the value and the time it was set.
+    pub fn get(&self) -> (Arc<V>, Instant)
+    {
+        let data = self.data.get();
+
+        (data.0.clone(), data.1)
+    }
+
+    //********************************************************************************************
+    /// Set the value, stamped with the current time.
+    pub fn set(
+        &mut self,
+        value : V
+        )
+    {
+        self.data.set((Arc::new(value), Instant::now()));
+    }
+
+    //********************************************************************************************
+    /// Clones the object, the new object shares the value and the timestamp.
+    pub fn dup(&mut self) -> SharedStamped<V>
+    {
+        SharedStamped {
+            data : self.data.dup()
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns true if the value is in the shared storage, which is the case once dup() has been
+    /// called.
+    pub fn is_shared(&self) -> bool
+    {
+        self.data.is_shared()
+    }
+}
+
+use std::fmt::{Debug, Formatter, Error};
+
+impl<V : Debug> Debug for SharedStamped<V>
+{
+    //*********************************************************************************************
+    /// Implementation of Debug.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        let (value, stamp) = self.get();
+
+        write!(f, "{:?} at {:?}", value, stamp)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::collections::HashMap;
+    use std::thread;
+    use std::time::Instant;
+
+    //*********************************************************************************************
+    /// Test that set() replaces the value and moves the timestamp forward.
+    #[test]
+    fn test_single()
+    {
+        let mut test         = super::SharedStamped::new(1);
+        let (value1, stamp1) = test.get();
+
+        test.set(2);
+
+        let (value2, stamp2) = test.get();
+
+        assert_eq!((*value1, *value2), (1, 2));
+        assert!(stamp2 >= stamp1);
+    }
+
+    //*********************************************************************************************
+    /// Test that the readers always see the timestamp that was set with the value.
+    #[test]
+    fn test_threads()
+    {
+        let mut test    = super::SharedStamped::new((0, Instant::now()));
+        let mut writer  = test.dup();
+        let mut threads = Vec::new();
+
+        for _ in 0..4
+        {
+            let reader = test.dup();
+
+            threads.push(thread::spawn(move || {
+                (0..2000).map(|_| {
+                    let (value, stamp) = reader.get();
+
+                    (value.0, value.1, stamp)
+                }).collect::<Vec<_>>()
+            }));
+        }
+
+        for i in 1..2000
+        {
+            writer.set((i, Instant::now()));
+        }
+
+        let mut stamps = HashMap::new();
+
+        for thread in threads
+        {
+            for (seq, before, stamp) in thread.join().unwrap()
+            {
+                assert!(stamp >= before);
+                assert_eq!(*stamps.entry(seq).or_insert(stamp), stamp);
+            }
+        }
+    }
+
+    //*********************************************************************************************
+    /// Test that is_shared() is false until dup() is called.
+    #[test]
+    fn test_is_shared()
+    {
+        let mut test1 = super::SharedStamped::new(5);
+
+        assert!(!test1.is_shared());
+
+        let test2 = test1.dup();
+
+        assert!(test1.is_shared());
+        assert!(test2.is_shared());
+    }
+}
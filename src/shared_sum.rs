@@ -0,0 +1,138 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+use SharedU64;
+
+//*************************************************************************************************
+/// Sum of a set of SharedU64 counters.
+///
+/// The object holds instances of the leaf counters and adds their values up every time get() is
+/// called, nothing is updated when a leaf changes.  The leaves are read one after the other, so
+/// the sum is not an atomic snapshot: a leaf that changes while the sum is being computed may or
+/// may not be included.  Sums can be nested by summing the leaves of several objects.
+///
+/// # Examples
+///
+/// ```
+/// use shareable::{SharedSum, SharedU64};
+///
+/// let mut reads  = SharedU64::new(0);
+/// let mut writes = SharedU64::new(0);
+///
+/// let total = SharedSum::of(vec![reads.dup(), writes.dup()]);
+///
+/// reads.fetch_add(3);
+/// writes.fetch_add(2);
+///
+/// assert_eq!(total.get(), 5);
+/// ```
+pub struct SharedSum
+{
+    //---------------------------------------------------------------------------------------------
+    /// The counters being added up.
+    leaves : Vec<SharedU64>
+}
+
+impl SharedSum
+{
+    //********************************************************************************************
+    /// Construct a new instance of the object adding up the counters.
+    pub fn of(
+        leaves : Vec<SharedU64>
+        ) -> SharedSum
+    {
+        SharedSum { leaves }
+    }
+
+    //********************************************************************************************
+    /// Returns the sum of the counters (wrapping on overflow).
+    pub fn get(&self) -> u64
+    {
+        self.leaves.iter().fold(0, |sum, leaf| sum.wrapping_add(leaf.get()))
+    }
+
+    //********************************************************************************************
+    /// Returns the counters being added up.
+    pub fn leaves(&self) -> &[SharedU64]
+    {
+        &self.leaves
+    }
+}
+
+use std::fmt::{Debug, Display, Formatter, Error};
+
+impl Debug for SharedSum
+{
+    //*********************************************************************************************
+    /// Implementation of Debug.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{:?}", self.get())
+    }
+}
+
+impl Display for SharedSum
+{
+    //*********************************************************************************************
+    /// Implementation of Display.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{}", self.get())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::thread;
+    use SharedU64;
+
+    //*********************************************************************************************
+    /// Test that the sum reflects the leaves incremented by several threads.
+    #[test]
+    fn test_sum()
+    {
+        let mut leaves  = Vec::new();
+        let mut handles = Vec::new();
+
+        for _ in 0..4
+        {
+            let mut leaf = SharedU64::new(0);
+
+            handles.push(leaf.dup());
+            leaves.push(leaf);
+        }
+
+        let total = super::SharedSum::of(handles);
+
+        assert_eq!(total.get(), 0);
+
+        let threads = leaves.into_iter().enumerate().map(|(i, leaf)| {
+            thread::spawn(move || {
+                for _ in 0..1000
+                {
+                    leaf.fetch_add(i as u64 + 1);
+                }
+            })
+        }).collect::<Vec<_>>();
+
+        for thread in threads
+        {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(total.get(), 10000);
+        assert_eq!(total.leaves()[3].get(), 4000);
+    }
+}
@@ -6,28 +6,20 @@
  * option. This file may not be copied, modified, or distributed
  * except according to those terms.
  */
-use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use core::sync::atomic::{AtomicU32, Ordering};
 
-//*************************************************************************************************
-/// Internal data structure that identifies how we are accessing the data.
-enum Data
-{
-    //---------------------------------------------------------------------------------------------
-    /// There is only 1 instance of the element.
-    Single(u32),
+use Shared;
 
-    //---------------------------------------------------------------------------------------------
-    /// There are or were multiple instances of the element.
-    Multiple(Arc<AtomicUsize>)
-}
+lock_free!(u32, AtomicU32);
+byte_order!(u32);
 
 //*************************************************************************************************
 /// Shareable u32 data element.
 ///
-/// If only 1 instance of the element is needed then that data is just saved as a normal memory
-/// location.  If multiple instances are needed then the value is saved in an AtomicUsize so it
-/// can be safely shared between threads.
+/// If only 1 instance of the element is needed then the value is saved in an AtomicU32 owned by
+/// the object, reading and writing it costs the same as a normal memory location.  If multiple
+/// instances are needed then the AtomicU32 is moved into an Arc so it can be safely shared between
+/// threads.
 ///
 /// # Examples
 ///
@@ -45,6 +37,8 @@ enum Data
 /// ```
 ///
 /// ```
+/// # #[cfg(not(all(feature = "strict-ordering", debug_assertions)))]
+/// # {
 /// use std::sync::mpsc;
 /// use std::thread;
 /// use shareable::SharedU32;
@@ -64,97 +58,17 @@ enum Data
 ///
 /// tx.send(());
 /// thread.join().unwrap();
+/// # }
 /// ```
-pub struct SharedU32
-{
-    //---------------------------------------------------------------------------------------------
-    /// The internal data element.
-    data : Data
-}
+pub type SharedU32 = Shared<u32>;
 
 impl SharedU32
 {
     //********************************************************************************************
-    /// Construct a new instance of the object.
-    pub fn new(
-        value : u32
-        ) -> SharedU32
-    {
-        SharedU32 {
-            data : Data::Single(value)
-        }
-    }
-
-    //********************************************************************************************
-    /// Set the value of the object.
-    pub fn set(
-        &mut self,
-        val : u32
-        )
-    {
-        match self.data
-        {
-            Data::Single(_)         => self.data = Data::Single(val),
-            Data::Multiple(ref mem) => mem.store(val as usize, Ordering::Relaxed)
-        }
-    }
-
-    //********************************************************************************************
-    /// Returns the value of the object.
-    pub fn get(&self) -> u32
-    {
-        match self.data
-        {
-            Data::Single(val)       => val,
-            Data::Multiple(ref mem) => mem.load(Ordering::Relaxed) as u32
-        }
-    }
-
-    //********************************************************************************************
-    /// Clones the object.  After this call all access to the data will be done via an
-    /// AtomicUsize element.
-    pub fn dup(&mut self) -> SharedU32
+    /// Returns the value with its bits reinterpreted as an f32, the stored value is unchanged.
+    pub fn as_f32_bits(&self) -> f32
     {
-        match self.data
-        {
-            Data::Single(val) => {
-                let data = Arc::new(AtomicUsize::new(val as usize));
-                self.data = Data::Multiple(data.clone());
-
-                SharedU32 { data : Data::Multiple(data) }
-            },
-            Data::Multiple(ref val) => {
-                SharedU32 { data : Data::Multiple(val.clone()) }
-            }
-        }
-    }
-}
-
-use std::fmt::{Debug, Display, Formatter, Error};
-
-impl Debug for SharedU32
-{
-    //*********************************************************************************************
-    /// Implementation of Debug.
-    fn fmt(
-        &self,
-        f : &mut Formatter
-        ) -> Result<(), Error>
-    {
-        write!(f, "{:?}", self.get())
-    }
-}
-
-impl Display for SharedU32
-{
-    //*********************************************************************************************
-    /// Implementation of Display.
-    fn fmt(
-        &self,
-        f : &mut Formatter
-        ) -> Result<(), Error>
-    {
-        write!(f, "{}", self.get())
+        f32::from_bits(self.get())
     }
 }
 
@@ -205,4 +119,647 @@ mod tests
         assert_eq!(test2.get(), 11);
         assert_eq!(test3.get(), 11);
     }
+
+    //*********************************************************************************************
+    /// Test that share() links the objects and snapshot() does not.
+    #[test]
+    fn test_share_snapshot()
+    {
+        let mut test1 = super::SharedU32::new(79);
+        let test2     = test1.share();
+        let test3     = test1.snapshot();
+
+        test1.set(41);
+
+        assert_eq!(test1.get(), 41);
+        assert_eq!(test2.get(), 41);
+        assert_eq!(test3.get(), 79);
+    }
+
+    //*********************************************************************************************
+    /// Test the checked and saturating conversions.
+    #[test]
+    fn test_convert()
+    {
+        let mut test = super::SharedU32::new(79);
+
+        assert_eq!(test.get_checked::<i8>(), Some(79));
+        assert_eq!(test.get_saturating::<i8>(), 79);
+
+        test.set(u32::MAX);
+
+        assert_eq!(test.get_checked::<i8>(), None);
+        assert_eq!(test.get_saturating::<i8>(), i8::MAX);
+    }
+
+    //*********************************************************************************************
+    /// Test that is_uniquely_owned() tracks the other instances.
+    #[test]
+    fn test_is_uniquely_owned()
+    {
+        let mut test1 = super::SharedU32::new(79);
+
+        assert!(test1.is_uniquely_owned());
+
+        let test2 = test1.dup();
+
+        assert!(!test1.is_uniquely_owned());
+        assert!(!test2.is_uniquely_owned());
+
+        drop(test2);
+
+        assert!(test1.is_uniquely_owned());
+    }
+
+    //*********************************************************************************************
+    /// Test that range_to() starts at the value when it was called.
+    #[test]
+    fn test_range_to()
+    {
+        let mut test1 = super::SharedU32::new(3);
+        let test2     = test1.dup();
+        let range     = test2.range_to(7);
+
+        test1.set(5);
+
+        assert_eq!(range.collect::<Vec<_>>(), vec![3, 4, 5, 6]);
+        assert_eq!(test2.range_to(7).count(), 2);
+        assert_eq!(test2.range_to(2).count(), 0);
+    }
+
+    //*********************************************************************************************
+    /// Test converting to and from Wrapping.
+    #[test]
+    fn test_wrapping()
+    {
+        use std::num::Wrapping;
+
+        let test = super::SharedU32::from(Wrapping(u32::MAX));
+
+        assert_eq!(test.get(), u32::MAX);
+        assert_eq!(test.wrapping() + Wrapping(1), Wrapping(u32::MIN));
+    }
+
+    //*********************************************************************************************
+    /// Test compare_exchange_value() when the exchange succeeds and fails.
+    #[test]
+    fn test_compare_exchange_value()
+    {
+        let mut test1 = super::SharedU32::new(5);
+        let test2     = test1.dup();
+
+        assert_eq!(test1.compare_exchange_value(5, 9), 5);
+        assert_eq!(test2.get(), 9);
+
+        assert_eq!(test2.compare_exchange_value(5, 1), 9);
+        assert_eq!(test1.get(), 9);
+    }
+
+    //*********************************************************************************************
+    /// Test that fetch_add() and fetch_sub() wrap around instead of panicking in debug builds.
+    #[test]
+    fn test_fetch_add_wraps()
+    {
+        let mut test1 = super::SharedU32::new(u32::MAX - 1);
+        let test2     = test1.dup();
+
+        assert_eq!(test1.fetch_add(1), u32::MAX - 1);
+        assert_eq!(test2.fetch_add(1), u32::MAX);
+        assert_eq!(test1.get(), u32::MIN);
+
+        assert_eq!(test2.fetch_sub(2), u32::MIN);
+        assert_eq!(test1.get(), u32::MAX - 1);
+    }
+
+    //*********************************************************************************************
+    /// Test that only one of the threads racing to replace a small value succeeds.
+    #[test]
+    fn test_replace_if()
+    {
+        use std::thread;
+
+        let mut test    = super::SharedU32::new(10);
+        let mut threads = Vec::new();
+
+        for _ in 0..4
+        {
+            let handle = test.dup();
+
+            threads.push(thread::spawn(move || handle.replace_if(100, |old| old < 50)));
+        }
+
+        let results = threads.into_iter().map(|thread| thread.join().unwrap()).collect::<Vec<_>>();
+
+        assert_eq!(results.iter().filter(|result| **result == Ok(10)).count(), 1);
+        assert_eq!(results.iter().filter(|result| **result == Err(100)).count(), 3);
+        assert_eq!(test.get(), 100);
+        assert_eq!(test.replace_if(5, |old| old == 100), Ok(100));
+    }
+
+    //*********************************************************************************************
+    /// Test that pre_share() moves the value into the storage used by dup().
+    #[test]
+    fn test_pre_share()
+    {
+        let mut test1 = super::SharedU32::new(1);
+
+        assert!(!test1.is_shared());
+
+        test1.pre_share();
+
+        assert!(test1.is_shared());
+        assert!(test1.is_uniquely_owned());
+        assert_eq!(test1.get(), 1);
+
+        let storage = test1.storage() as *const _;
+        let test2   = test1.dup();
+
+        assert!(test2.is_shared());
+        assert_eq!(test2.storage() as *const _, storage);
+    }
+
+    //*********************************************************************************************
+    /// Test that the byte order methods store the swapped bytes.
+    #[test]
+    fn test_byte_order()
+    {
+        let val      = 0x1234_5678u32;
+        let mut test = super::SharedU32::new(0);
+
+        test.set_be(val);
+
+        assert_eq!(test.get(), val.to_be());
+        assert_eq!(test.get_be(), val);
+
+        test.set_le(val);
+
+        assert_eq!(test.get(), val.to_le());
+        assert_eq!(test.get_le(), val);
+        assert_eq!(test.get_be(), u32::from_be(val.to_le()));
+    }
+
+    //*********************************************************************************************
+    /// Test that fetch_max_with() only ever raises the value.
+    #[test]
+    fn test_fetch_max_with()
+    {
+        let test     = super::SharedU32::new(10);
+        let mut prev = 10;
+
+        for i in 0..10
+        {
+            let old = test.fetch_max_with(|old| if i & 1 == 0 { old - 3 } else { old + 2 });
+
+            assert_eq!(old, prev);
+            assert!(test.get() >= prev);
+
+            prev = test.get();
+        }
+
+        assert_eq!(test.get(), 20);
+    }
+
+    //*********************************************************************************************
+    /// Test parsing the value from a string.
+    #[test]
+    fn test_from_str()
+    {
+        let test : super::SharedU32 = "12".parse().unwrap();
+
+        assert_eq!(test.get(), "12".parse::<u32>().unwrap());
+        assert!("abc".parse::<super::SharedU32>().is_err());
+    }
+
+    //*********************************************************************************************
+    /// Test that saturating_add_flagged() only sets the flag when the result is clamped.
+    #[test]
+    fn test_saturating_add_flagged()
+    {
+        let test = super::SharedU32::new(u32::MAX - 2);
+
+        assert_eq!(test.saturating_add_flagged(2), (u32::MAX, false));
+        assert_eq!(test.saturating_add_flagged(0), (u32::MAX, false));
+        assert_eq!(test.saturating_add_flagged(1), (u32::MAX, true));
+        assert_eq!(test.get(), u32::MAX);
+    }
+
+    //*********************************************************************************************
+    /// Test a SeqCst store seen by an Acquire load on another object.
+    #[test]
+    fn test_get_set_with()
+    {
+        use std::sync::atomic::Ordering;
+        use std::thread;
+
+        let mut test1 = super::SharedU32::new(0);
+        let mut test2 = test1.dup();
+
+        let thread = thread::spawn(move || test2.set_with(7, Ordering::SeqCst));
+
+        thread.join().unwrap();
+
+        assert_eq!(test1.get_with(Ordering::Acquire), 7);
+
+        test1.set_with(0, Ordering::Release);
+        assert_eq!(test1.get_with(Ordering::SeqCst), 0);
+    }
+
+    //*********************************************************************************************
+    /// Test that exactly the callers that lowered the minimum see the flag.
+    #[test]
+    fn test_fetch_min_flagged()
+    {
+        use std::collections::HashSet;
+        use std::thread;
+
+        let mut test    = super::SharedU32::new(100);
+        let mut threads = Vec::new();
+
+        for t in 0..4
+        {
+            let low = test.dup();
+
+            threads.push(thread::spawn(move || {
+                (0..20).map(|i| {
+                    let val         = 99 - ((i % 5) * 16 + t * 4 + i / 5);
+                    let (old, lower) = low.fetch_min_flagged(val);
+
+                    (val, old, lower)
+                }).collect::<Vec<_>>()
+            }));
+        }
+
+        let results  = threads.into_iter().flat_map(|thread| thread.join().unwrap());
+        let results  = results.collect::<Vec<_>>();
+        let lowered  = results.iter().filter(|r| r.2).map(|r| r.0).collect::<HashSet<_>>();
+        let mut mins = results.iter().map(|r| r.1).collect::<HashSet<_>>();
+
+        mins.insert(test.get());
+        mins.remove(&100);
+
+        assert!(results.iter().all(|&(val, old, lower)| lower == (val < old)));
+        assert_eq!(lowered, mins);
+        assert_eq!(test.get(), 20);
+    }
+
+    //*********************************************************************************************
+    /// Test that with_value() restores the value after the scope, including after a panic.
+    #[test]
+    fn test_with_value()
+    {
+        use std::panic::{self, AssertUnwindSafe};
+
+        let mut test1 = super::SharedU32::new(1);
+        let test2     = test1.dup();
+
+        assert_eq!(test1.with_value(2, || test2.get()), 2);
+        assert_eq!(test2.get(), 1);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            test1.with_value(2, || panic!("inside the scope"))
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(test2.get(), 1);
+    }
+
+    //*********************************************************************************************
+    /// Test compare_exchange() succeeding and failing with 1 and 2 instances.
+    #[test]
+    fn test_compare_exchange()
+    {
+        use std::sync::atomic::Ordering;
+
+        let mut test1 = super::SharedU32::new(5);
+
+        assert_eq!(test1.compare_exchange(6, 7, Ordering::AcqRel, Ordering::Acquire), Err(5));
+        assert_eq!(test1.compare_exchange(5, 7, Ordering::AcqRel, Ordering::Acquire), Ok(5));
+        assert_eq!(test1.get(), 7);
+
+        let test2 = test1.dup();
+
+        assert_eq!(test2.compare_exchange(5, 9, Ordering::SeqCst, Ordering::Relaxed), Err(7));
+        assert_eq!(test2.compare_exchange(7, 9, Ordering::SeqCst, Ordering::Relaxed), Ok(7));
+        assert_eq!(test1.get(), 9);
+    }
+
+    //*********************************************************************************************
+    /// Test that swap() returns the previous value with 1 and 2 instances.
+    #[test]
+    fn test_swap()
+    {
+        let mut test1 = super::SharedU32::new(1);
+
+        assert_eq!(test1.swap(2), 1);
+        assert_eq!(test1.get(), 2);
+
+        let mut test2 = test1.dup();
+
+        assert_eq!(test2.swap(3), 2);
+        assert_eq!(test1.swap(1), 3);
+        assert_eq!(test2.get(), 1);
+    }
+
+    //*********************************************************************************************
+    /// Test that as_f32_bits() reinterprets the bits without changing the value.
+    #[test]
+    fn test_as_f32_bits()
+    {
+        let mut test1 = super::SharedU32::new(1.5f32.to_bits());
+        let test2     = test1.dup();
+
+        assert_eq!(test2.as_f32_bits(), 1.5);
+
+        test1.set(0x7fc0_0001);
+
+        assert_eq!(test2.as_f32_bits().to_bits(), f32::from_bits(test2.get()).to_bits());
+        assert_eq!(test1.get(), 0x7fc0_0001);
+    }
+
+    //*********************************************************************************************
+    /// Test that clone() shares the value through a shared reference, like dup() does.
+    #[test]
+    fn test_clone()
+    {
+        let mut test1 = super::SharedU32::new(1);
+        let holder    = &test1;
+        let mut test2 = holder.clone();
+        let test3     = test2.clone();
+
+        assert!(test1.is_shared());
+        assert!(!test1.is_uniquely_owned());
+
+        test1.set(2);
+        assert_eq!(test2.get(), 2);
+        assert_eq!(test3.get(), 2);
+
+        test2.set(3);
+        assert_eq!(test1.get(), 3);
+
+        drop(test2);
+        drop(test3);
+        assert!(test1.is_uniquely_owned());
+    }
+
+    //*********************************************************************************************
+    /// Test that cloning through a shared reference while another thread updates the value
+    /// through one doesn't lose any of the updates.
+    #[test]
+    fn test_clone_threads()
+    {
+        use std::thread;
+
+        let test1 = super::SharedU32::new(0);
+
+        let clones = thread::scope(|scope| {
+            scope.spawn(|| for _ in 0..10000 { test1.fetch_add(1); });
+
+            (0..100).map(|_| test1.clone()).collect::<Vec<_>>()
+        });
+
+        assert_eq!(test1.get(), 10000);
+        assert!(clones.iter().all(|clone| clone.get() == 10000));
+    }
+
+    //*********************************************************************************************
+    /// Test that update_full() reports the old and new values and whether the value changed.
+    #[test]
+    fn test_update_full()
+    {
+        let mut test1 = super::SharedU32::new(5);
+        let test2     = test1.dup();
+        let result    = test1.update_full(|val| val + 2);
+
+        assert_eq!((result.old, result.new, result.changed), (5, 7, true));
+
+        let result = test2.update_full(|val| val);
+
+        assert_eq!(result, ::UpdateResult { old : 7, new : 7, changed : false });
+        assert_eq!(test2.update_full(|val| val - 7).new, 0);
+        assert_eq!(test1.get(), 0);
+    }
+
+    //*********************************************************************************************
+    /// Test that the value survives a round trip through serde_json and comes back unshared.
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde()
+    {
+        extern crate serde_json;
+
+        let mut test1 = super::SharedU32::new(63);
+        let test2     = test1.dup();
+
+        test1.set(31);
+
+        let json = serde_json::to_string(&test2).unwrap();
+
+        assert_eq!(json, "31");
+
+        let test3 : super::SharedU32 = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(test3.get(), 31);
+        assert!(!test3.is_shared());
+    }
+
+    //*********************************************************************************************
+    /// Test that equality compares the current values of unshared and shared objects.
+    #[test]
+    fn test_eq()
+    {
+        let mut test1 = super::SharedU32::new(31);
+        let test2     = test1.dup();
+        let test3     = super::SharedU32::new(31);
+
+        assert!(test1.is_shared());
+        assert!(!test3.is_shared());
+        assert_eq!(test1, test3);
+        assert_eq!(test2, 31);
+
+        test1.set(63);
+
+        assert_ne!(test2, test3);
+        assert_eq!(test2, test1);
+    }
+
+    //*********************************************************************************************
+    /// Test that fetch_update() can clamp the value at a ceiling.
+    #[test]
+    fn test_fetch_update()
+    {
+        use std::sync::atomic::Ordering;
+
+        let mut test1 = super::SharedU32::new(3);
+        let test2     = test1.dup();
+        let clamp     = |val| if val < 5 { Some(val + 1) } else { None };
+
+        assert_eq!(test1.fetch_update(Ordering::AcqRel, Ordering::Acquire, clamp), Ok(3));
+        assert_eq!(test2.fetch_update(Ordering::AcqRel, Ordering::Acquire, clamp), Ok(4));
+        assert_eq!(test1.fetch_update(Ordering::AcqRel, Ordering::Acquire, clamp), Err(5));
+        assert_eq!(test2.get(), 5);
+
+        let test3 = super::SharedU32::new(5);
+
+        assert_eq!(test3.fetch_update(Ordering::AcqRel, Ordering::Acquire, clamp), Err(5));
+        assert_eq!(test3.get(), 5);
+    }
+
+    //*********************************************************************************************
+    /// Test that the value survives a round trip through an atomic.
+    #[test]
+    fn test_from_atomic()
+    {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let mut test1 = super::SharedU32::from(AtomicU32::new(31));
+        let test2     = test1.dup();
+
+        test1.set(63);
+
+        let test3 = AtomicU32::from(test2);
+
+        assert_eq!(test3.load(Ordering::Relaxed), 63);
+        assert_eq!(test1.get(), 63);
+
+        let test4 = super::SharedU32::from(test3);
+
+        assert!(!test4.is_shared());
+        assert_eq!(test4.get(), 63);
+    }
+
+    //*********************************************************************************************
+    /// Test that try_downgrade() only unshares the value once the other objects are dropped.
+    #[test]
+    fn test_try_downgrade()
+    {
+        let mut test1 = super::SharedU32::new(31);
+        let test2     = test1.dup();
+
+        assert!(!test1.try_downgrade());
+        assert!(test1.is_shared());
+
+        drop(test2);
+
+        assert!(test1.try_downgrade());
+        assert!(!test1.is_shared());
+        assert_eq!(test1.get(), 31);
+        assert!(test1.try_downgrade());
+    }
+
+    //*********************************************************************************************
+    /// Test that auto downgrade mode leaves the shared storage once the workers are done.
+    #[test]
+    fn test_auto_downgrade()
+    {
+        use std::thread;
+
+        let mut test1 = super::SharedU32::new(0);
+
+        test1.set_auto_downgrade(true);
+
+        let threads : Vec<_> = (0..4).map(|_| {
+            let test2 = test1.dup();
+
+            thread::spawn(move || {
+                for _ in 0..100
+                {
+                    test2.fetch_add(1);
+                }
+            })
+        }).collect();
+
+        for thread in threads
+        {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(test1.get(), 400);
+        assert!(test1.as_atomic().is_some());
+
+        for i in 0..64
+        {
+            test1.set(i);
+        }
+
+        assert!(test1.as_atomic().is_none());
+        assert_eq!(test1.get(), 63);
+    }
+
+    //*********************************************************************************************
+    /// Test that accessing_threads() counts each thread using the shared data once.
+    #[test]
+    #[cfg(feature = "thread-tracking")]
+    fn test_accessing_threads()
+    {
+        use std::sync::atomic::Ordering;
+        use std::thread;
+
+        let mut test1 = super::SharedU32::new(31);
+
+        test1.get();
+        test1.set(63);
+
+        assert_eq!(test1.accessing_threads(), 1);
+
+        let test2 = test1.dup();
+
+        thread::spawn(move || {
+            test2.get_with(Ordering::Acquire);
+        }).join().unwrap();
+
+        test1.get_with(Ordering::Acquire);
+
+        assert_eq!(test1.accessing_threads(), 2);
+    }
+
+    //*********************************************************************************************
+    /// Test that try_into_inner() only returns the value once the other objects are dropped.
+    #[test]
+    fn test_try_into_inner()
+    {
+        assert_eq!(super::SharedU32::new(31).try_into_inner().ok(), Some(31));
+
+        let mut test1 = super::SharedU32::new(31);
+        let test2     = test1.dup();
+        let test1     = test1.try_into_inner().unwrap_err();
+
+        assert_eq!(test1.get(), 31);
+
+        drop(test2);
+
+        assert_eq!(test1.try_into_inner().ok(), Some(31));
+    }
+
+    //*********************************************************************************************
+    /// Test that the radix formats match the same formats on the value.
+    #[test]
+    fn test_radix_fmt()
+    {
+        let mut test1 = super::SharedU32::new(0xbeef);
+        let test2     = test1.dup();
+
+        assert_eq!(format!("{:#06x}", test1), format!("{:#06x}", 0xbeefu32));
+        assert_eq!(format!("{:X}", test2), "BEEF");
+        assert_eq!(format!("{:08b}", super::SharedU32::new(5)), "00000101");
+        assert_eq!(format!("{:#o}", test2), "0o137357");
+        assert_eq!(format!("{:>8x}", test2), "    beef");
+    }
+
+    //*********************************************************************************************
+    /// Test that fetch_and(), fetch_or() and fetch_xor() combine bits before and after dup().
+    #[test]
+    fn test_fetch_bits()
+    {
+        let mut test1 = super::SharedU32::new(0b0001);
+
+        assert_eq!(test1.fetch_or(0b0100), 0b0001);
+        assert_eq!(test1.get(), 0b0101);
+
+        let test2 = test1.dup();
+
+        assert_eq!(test2.fetch_or(0b1000), 0b0101);
+        assert_eq!(test1.fetch_and(0b1100), 0b1101);
+        assert_eq!(test2.fetch_xor(0b0110), 0b1100);
+        assert_eq!(test1.get(), 0b1010);
+    }
 }
@@ -0,0 +1,187 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+use std::fmt::{Debug, Display, Formatter, Error};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+//*************************************************************************************************
+/// Borrowed view of a u64 counter owned by someone else, like a C library.
+///
+/// The view reads and writes the atomic in place, it never takes ownership of it, so the
+/// atomic isn't freed when the view is dropped.  The lifetime ties the view to the owner's
+/// promise that the atomic stays valid.  Like the other shareable elements the accesses are
+/// Relaxed.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::atomic::AtomicU64;
+/// use shareable::SharedU64Ref;
+///
+/// let counter = AtomicU64::new(1);
+/// let view    = unsafe { SharedU64Ref::borrow_raw(&counter) };
+///
+/// view.fetch_add(2);
+///
+/// assert_eq!(view.get(), 3);
+/// ```
+#[derive(Clone, Copy)]
+pub struct SharedU64Ref<'a>
+{
+    //---------------------------------------------------------------------------------------------
+    /// The borrowed atomic.
+    data : &'a AtomicU64
+}
+
+impl<'a> SharedU64Ref<'a>
+{
+    //********************************************************************************************
+    /// Construct a view of the atomic the pointer points to.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must be non-null, aligned, and point to an AtomicU64 (or a C `_Atomic
+    /// uint64_t`) that stays valid for 'a.  Everything else accessing it during 'a must do so
+    /// atomically.
+    pub unsafe fn borrow_raw(
+        ptr : *const AtomicU64
+        ) -> SharedU64Ref<'a>
+    {
+        SharedU64Ref {
+            data : &*ptr
+        }
+    }
+
+    //********************************************************************************************
+    /// Returns the value.
+    pub fn get(&self) -> u64
+    {
+        self.data.load(Ordering::Relaxed)
+    }
+
+    //********************************************************************************************
+    /// Set the value.
+    pub fn set(
+        &self,
+        val : u64
+        )
+    {
+        self.data.store(val, Ordering::Relaxed);
+    }
+
+    //********************************************************************************************
+    /// Adds to the value and returns the previous value.  The addition wraps around on overflow.
+    pub fn fetch_add(
+        &self,
+        val : u64
+        ) -> u64
+    {
+        self.data.fetch_add(val, Ordering::Relaxed)
+    }
+
+    //********************************************************************************************
+    /// Subtracts from the value and returns the previous value.  The subtraction wraps around on
+    /// overflow.
+    pub fn fetch_sub(
+        &self,
+        val : u64
+        ) -> u64
+    {
+        self.data.fetch_sub(val, Ordering::Relaxed)
+    }
+}
+
+impl<'a> From<&'a AtomicU64> for SharedU64Ref<'a>
+{
+    //*********************************************************************************************
+    /// Construct a view of an atomic borrowed from Rust code, which is always safe.
+    fn from(
+        data : &'a AtomicU64
+        ) -> SharedU64Ref<'a>
+    {
+        SharedU64Ref { data }
+    }
+}
+
+impl Debug for SharedU64Ref<'_>
+{
+    //*********************************************************************************************
+    /// Implementation of Debug.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{:?}", self.get())
+    }
+}
+
+impl Display for SharedU64Ref<'_>
+{
+    //*********************************************************************************************
+    /// Implementation of Display.
+    fn fmt(
+        &self,
+        f : &mut Formatter
+        ) -> Result<(), Error>
+    {
+        write!(f, "{}", self.get())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::thread;
+    use super::SharedU64Ref;
+
+    //*********************************************************************************************
+    /// Test that the view reads and writes the borrowed atomic.
+    #[test]
+    fn test_borrow_raw()
+    {
+        let counter = AtomicU64::new(5);
+        let view    = unsafe { SharedU64Ref::borrow_raw(&counter as *const AtomicU64) };
+
+        assert_eq!(view.get(), 5);
+
+        view.set(10);
+        assert_eq!(counter.load(Ordering::Relaxed), 10);
+
+        counter.store(20, Ordering::Relaxed);
+        assert_eq!(view.get(), 20);
+
+        assert_eq!(view.fetch_add(2), 20);
+        assert_eq!(view.fetch_sub(1), 22);
+        assert_eq!(counter.load(Ordering::Relaxed), 21);
+    }
+
+    //*********************************************************************************************
+    /// Test that copies of the view can be used from several threads.
+    #[test]
+    fn test_threads()
+    {
+        let counter = AtomicU64::new(0);
+        let view    = SharedU64Ref::from(&counter);
+
+        thread::scope(|scope| {
+            for _ in 0..4
+            {
+                scope.spawn(move || {
+                    for _ in 0..100
+                    {
+                        view.fetch_add(1);
+                    }
+                });
+            }
+        });
+
+        assert_eq!(counter.load(Ordering::Relaxed), 400);
+    }
+}
@@ -6,30 +6,25 @@
  * option. This file may not be copied, modified, or distributed
  * except according to those terms.
  */
-use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use alloc::vec::Vec;
+use core::sync::atomic::{self, AtomicU64, Ordering};
 
-//*************************************************************************************************
-/// Internal data structure that identifies how we are accessing the data.
-enum Data
-{
-    //---------------------------------------------------------------------------------------------
-    /// There is only 1 instance of the element.
-    Single(u64),
+use Poisoned;
+use Shared;
 
-    //---------------------------------------------------------------------------------------------
-    /// There are or were multiple instances of the element.
-    Multiple(Arc<AtomicUsize>)
-}
+lock_free!(u64, AtomicU64);
+byte_order!(u64);
 
 //*************************************************************************************************
 /// Shareable u64 data element.
 ///
-/// If only 1 instance of the element is needed then that data is just saved as a normal memory
-/// location.  If multiple instances are needed then the value is saved in an AtomicUsize if the
-/// application is 64bit or a Mutex if the application is 32bit so it can be safely shared between
+/// If only 1 instance of the element is needed then the value is saved in an AtomicU64 owned by
+/// the object, reading and writing it costs the same as a normal memory location.  If multiple
+/// instances are needed then the AtomicU64 is moved into an Arc so it can be safely shared between
 /// threads.
 ///
+/// On 32 bit applications the value is saved in a Mutex instead of an AtomicU64.
+///
 /// # Examples
 ///
 /// ```
@@ -46,6 +41,8 @@ enum Data
 /// ```
 ///
 /// ```
+/// # #[cfg(not(all(feature = "strict-ordering", debug_assertions)))]
+/// # {
 /// use std::sync::mpsc;
 /// use std::thread;
 /// use shareable::SharedU64;
@@ -65,97 +62,245 @@ enum Data
 ///
 /// tx.send(());
 /// thread.join().unwrap();
+/// # }
 /// ```
-pub struct SharedU64
-{
-    //---------------------------------------------------------------------------------------------
-    /// The internal data element.
-    data : Data
-}
+///
+/// Scoped threads don't need their own instance, the methods that take &self (fetch_add(),
+/// store_release(), and the other atomic updates) can be called through a shared reference
+/// from every thread in the scope.  set() still needs &mut self.
+///
+/// ```
+/// use std::thread;
+/// use shareable::SharedU64;
+///
+/// let counter = SharedU64::new(0);
+///
+/// thread::scope(|scope| {
+///     for _ in 0..4
+///     {
+///         scope.spawn(|| counter.fetch_add(1));
+///     }
+/// });
+///
+/// assert_eq!(counter.get(), 4);
+/// ```
+pub type SharedU64 = Shared<u64>;
 
 impl SharedU64
 {
     //********************************************************************************************
-    /// Construct a new instance of the object.
-    pub fn new(
+    /// Construct two objects already sharing the value, like calling new() and then dup().  The
+    /// objects are interchangeable, for example one can be handed to a writer and the other to
+    /// a reader.
+    pub fn pair(
         value : u64
-        ) -> SharedU64
+        ) -> (SharedU64, SharedU64)
     {
-        SharedU64 {
-            data : Data::Single(value)
-        }
+        let mut first = SharedU64::new(value);
+        let second    = first.dup();
+
+        (first, second)
+    }
+
+    //********************************************************************************************
+    /// Returns the value.  The value is atomic on this target and can't be poisoned, so this
+    /// always returns Ok.  It matches checked_get() on 32 bit targets, where a Mutex is used.
+    pub fn checked_get(&self) -> Result<u64, Poisoned>
+    {
+        Ok(self.get())
     }
 
     //********************************************************************************************
-    /// Set the value of the object.
-    pub fn set(
+    /// Set the value.  The value is atomic on this target and can't be poisoned, so this always
+    /// returns Ok.  It matches checked_set() on 32 bit targets, where a Mutex is used.
+    pub fn checked_set(
         &mut self,
         val : u64
+        ) -> Result<(), Poisoned>
+    {
+        self.set(val);
+
+        Ok(())
+    }
+
+    //********************************************************************************************
+    /// Links this object with another object that was created separately so both objects share
+    /// one storage location from now on.  The shared storage holds the sum of the two values
+    /// (wrapping on overflow), so counters that were tracked separately can be unified without
+    /// losing counts.
+    ///
+    /// If one of the objects is shared its storage is kept, the count of the other object is
+    /// added to it and the other object joins it, so the objects already sharing the data see
+    /// the merged count and their later counts aren't lost.
+    ///
+    /// Panics if both objects are shared, the objects sharing with them can't all be moved to
+    /// one storage.
+    pub fn merge_into(
+        &mut self,
+        other : &mut SharedU64
         )
     {
-        match self.data
+        match (self.is_uniquely_owned(), other.is_uniquely_owned())
         {
-            Data::Single(_)         => self.data = Data::Single(val),
-            Data::Multiple(ref mem) => mem.store(val as usize, Ordering::Relaxed)
+            (true, true)   => {
+                let val = self.swap(0).wrapping_add(other.swap(0));
+
+                self.link(other, val);
+            },
+            (true, false)  => {
+                other.fetch_add(self.swap(0));
+
+                *self = other.dup();
+            },
+            (false, true)  => {
+                self.fetch_add(other.swap(0));
+
+                *other = self.dup();
+            },
+            (false, false) => panic!("merge_into() can't merge two objects that are both shared")
         }
     }
 
     //********************************************************************************************
-    /// Returns the value of the object.
-    pub fn get(&self) -> u64
+    /// Atomically advances the value modulo n and returns the value before it was advanced,
+    /// which is always in the range [0, n).  Calling this from multiple threads hands out the
+    /// values 0, 1, ..., n - 1, 0, 1, ... in round robin order.
+    ///
+    /// The stored value is kept in the range [0, n) instead of being incremented forever and
+    /// reduced on every read, so the sequence never jumps when the counter would wrap at
+    /// u64::MAX.  A stored value that is already out of range is reduced modulo n first.
+    ///
+    /// Panics if n is zero.
+    pub fn next_mod(
+        &self,
+        n : u64
+        ) -> u64
     {
-        match self.data
-        {
-            Data::Single(val)       => val,
-            Data::Multiple(ref mem) => mem.load(Ordering::Relaxed) as u64
-        }
+        assert!(n != 0, "the modulus must not be zero");
+
+        let old = self.storage()
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |old| Some((old % n + 1) % n))
+            .unwrap();
+
+        old % n
     }
 
     //********************************************************************************************
-    /// Clones the object.  After this call all access to the data will be done via an
-    /// AtomicUsize element.
-    pub fn dup(&mut self) -> SharedU64
+    /// Resets every object to zero and returns the values they held, in the same order.
+    ///
+    /// Each object is reset atomically but the objects are reset one after another, so the
+    /// returned values are not a consistent snapshot if other threads are updating the objects
+    /// at the same time.  Use SharedCounterSet if the values must be drained together.
+    pub fn drain_all(handles : &[SharedU64]) -> Vec<u64>
     {
-        match self.data
-        {
-            Data::Single(val) => {
-                let data = Arc::new(AtomicUsize::new(val as usize));
-                self.data = Data::Multiple(data.clone());
+        handles.iter().map(|handle| handle.storage().swap(0, Ordering::Relaxed)).collect()
+    }
 
-                SharedU64 { data : Data::Multiple(data) }
-            },
-            Data::Multiple(ref val) => {
-                SharedU64 { data : Data::Multiple(val.clone()) }
-            }
+    //********************************************************************************************
+    /// Atomically adds n to the value only if the result doesn't exceed capacity.  Returns the
+    /// new value if n was added, or the current value if adding n would exceed capacity (or
+    /// overflow), in which case the value is left unchanged.
+    ///
+    /// This is the core of a token bucket, concurrent callers can never push the value past the
+    /// capacity.
+    pub fn try_acquire(
+        &self,
+        n        : u64,
+        capacity : u64
+        ) -> Result<u64, u64>
+    {
+        self.storage()
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |old| {
+                old.checked_add(n).filter(|&new| new <= capacity)
+            })
+            .map(|old| old + n)
+    }
+
+    //********************************************************************************************
+    /// Returns the value using an Acquire load.  Reads made after this call can't be moved before
+    /// it, pair it with store_release() when the value guards other data, like the sequence
+    /// number of a sequence lock.
+    pub fn load_acquire(&self) -> u64
+    {
+        self.get_with(Ordering::Acquire)
+    }
+
+    //********************************************************************************************
+    /// Sets the value using a Release store.  Writes made before this call can't be moved after
+    /// it, a thread that sees the value with load_acquire() also sees those writes.
+    pub fn store_release(
+        &self,
+        val : u64
+        )
+    {
+        self.storage().store(val, Ordering::Release);
+    }
+
+    //********************************************************************************************
+    /// Decrements the value and returns true if it reached zero, the release step of a reference
+    /// count.  The decrement is a Release and reaching zero is followed by an Acquire fence, so
+    /// the caller that gets true sees every write the other holders made before their decrement
+    /// and can safely clean up.  Calling this when the value is already zero wraps it around.
+    pub fn dec_and_test(&self) -> bool
+    {
+        if self.storage().fetch_sub(1, Ordering::Release) != 1
+        {
+            return false;
         }
+
+        atomic::fence(Ordering::Acquire);
+
+        true
     }
-}
 
-use std::fmt::{Debug, Display, Formatter, Error};
+    //********************************************************************************************
+    /// Sets the bits in the mask, returns true if any of them were not already set, that is if
+    /// this call changed the value.
+    pub fn set_bits(
+        &self,
+        mask : u64
+        ) -> bool
+    {
+        self.storage().fetch_or(mask, Ordering::Relaxed) & mask != mask
+    }
 
-impl Debug for SharedU64
-{
-    //*********************************************************************************************
-    /// Implementation of Debug.
-    fn fmt(
+    //********************************************************************************************
+    /// Clears the bits in the mask, returns true if any of them were set, that is if this call
+    /// changed the value.
+    pub fn clear_bits(
         &self,
-        f : &mut Formatter
-        ) -> Result<(), Error>
+        mask : u64
+        ) -> bool
     {
-        write!(f, "{:?}", self.get())
+        self.storage().fetch_and(!mask, Ordering::Relaxed) & mask != 0
     }
-}
 
-impl Display for SharedU64
-{
-    //*********************************************************************************************
-    /// Implementation of Display.
-    fn fmt(
+    //********************************************************************************************
+    /// Increments the value and returns the previous value as the caller's ticket.  Every call
+    /// gets a different ticket, in increasing order, until the value wraps around after 2^64
+    /// calls.
+    pub fn take_ticket(&self) -> u64
+    {
+        self.storage().fetch_add(1, Ordering::Relaxed)
+    }
+
+    //********************************************************************************************
+    /// Increments the value and calls on_milestone with the new value when it is a multiple of
+    /// every.  Each increment returns a different value, so when several threads are counting
+    /// each milestone is reported by exactly one of them.  on_milestone is never called if every
+    /// is 0.
+    pub fn inc_report<F : Fn(u64)>(
         &self,
-        f : &mut Formatter
-        ) -> Result<(), Error>
+        every        : u64,
+        on_milestone : F
+        )
     {
-        write!(f, "{}", self.get())
+        let new = self.fetch_add(1).wrapping_add(1);
+
+        if every != 0 && new.is_multiple_of(every)
+        {
+            on_milestone(new);
+        }
     }
 }
 
@@ -206,4 +351,908 @@ mod tests
         assert_eq!(test2.get(), 11);
         assert_eq!(test3.get(), 11);
     }
+
+    //*********************************************************************************************
+    /// Test that share() links the objects and snapshot() does not.
+    #[test]
+    fn test_share_snapshot()
+    {
+        let mut test1 = super::SharedU64::new(79);
+        let test2     = test1.share();
+        let test3     = test1.snapshot();
+
+        test1.set(41);
+
+        assert_eq!(test1.get(), 41);
+        assert_eq!(test2.get(), 41);
+        assert_eq!(test3.get(), 79);
+    }
+
+    //*********************************************************************************************
+    /// Test the checked and saturating conversions.
+    #[test]
+    fn test_convert()
+    {
+        let mut test = super::SharedU64::new(79);
+
+        assert_eq!(test.get_checked::<i8>(), Some(79));
+        assert_eq!(test.get_saturating::<i8>(), 79);
+
+        test.set(u64::MAX);
+
+        assert_eq!(test.get_checked::<i8>(), None);
+        assert_eq!(test.get_saturating::<i8>(), i8::MAX);
+    }
+
+    //*********************************************************************************************
+    /// Test that merged objects share the same storage.
+    #[test]
+    fn test_merge_into()
+    {
+        let mut test1 = super::SharedU64::new(79);
+        let mut test2 = super::SharedU64::new(41);
+        let mut test3 = test2.dup();
+
+        test1.merge_into(&mut test2);
+
+        assert_eq!(test1.get(), 120);
+        assert_eq!(test2.get(), 120);
+        assert_eq!(test3.get(), 120);
+
+        test2.set(11);
+
+        assert_eq!(test1.get(), 11);
+        assert_eq!(test3.get(), 11);
+
+        test3.set(31);
+
+        assert_eq!(test1.get(), 31);
+        assert_eq!(test2.get(), 31);
+    }
+
+    //*********************************************************************************************
+    /// Test that merging two unshared objects links them.
+    #[test]
+    fn test_merge_into_unshared()
+    {
+        let mut test1 = super::SharedU64::new(79);
+        let mut test2 = super::SharedU64::new(41);
+
+        test1.merge_into(&mut test2);
+
+        assert_eq!(test1.get(), 120);
+
+        test1.fetch_add(5);
+
+        assert_eq!(test2.get(), 125);
+    }
+
+    //*********************************************************************************************
+    /// Test that counts added by other threads while merging aren't lost.
+    #[test]
+    fn test_merge_into_threads()
+    {
+        use std::thread;
+
+        let mut test1 = super::SharedU64::new(0);
+        let mut test2 = super::SharedU64::new(0);
+        let other     = test2.dup();
+
+        let thread = thread::spawn(move || {
+            for _ in 0..10000
+            {
+                other.fetch_add(1);
+            }
+        });
+
+        for _ in 0..100
+        {
+            test1.fetch_add(1);
+        }
+
+        test1.merge_into(&mut test2);
+        thread.join().unwrap();
+
+        assert_eq!(test1.get(), 10100);
+        assert_eq!(test2.get(), 10100);
+    }
+
+    //*********************************************************************************************
+    /// Test that merging two shared objects panics.
+    #[test]
+    #[should_panic]
+    fn test_merge_into_both_shared()
+    {
+        let mut test1 = super::SharedU64::new(79);
+        let mut test2 = super::SharedU64::new(41);
+        let _copy1    = test1.dup();
+        let _copy2    = test2.dup();
+
+        test1.merge_into(&mut test2);
+    }
+
+    //*********************************************************************************************
+    /// Test that is_uniquely_owned() tracks the other instances.
+    #[test]
+    fn test_is_uniquely_owned()
+    {
+        let mut test1 = super::SharedU64::new(79);
+
+        assert!(test1.is_uniquely_owned());
+
+        let test2 = test1.dup();
+
+        assert!(!test1.is_uniquely_owned());
+        assert!(!test2.is_uniquely_owned());
+
+        drop(test2);
+
+        assert!(test1.is_uniquely_owned());
+    }
+
+    //*********************************************************************************************
+    /// Test that next_mod() hands out every index in round robin order across threads.
+    #[test]
+    fn test_next_mod()
+    {
+        use std::thread;
+
+        let mut test = super::SharedU64::new(0);
+
+        let threads : Vec<_> = (0..4).map(|_| {
+            let counter = test.dup();
+
+            thread::spawn(move || {
+                (0..300).map(|_| counter.next_mod(7)).collect::<Vec<_>>()
+            })
+        }).collect();
+
+        let mut counts = [0; 7];
+
+        for thread in threads
+        {
+            for index in thread.join().unwrap()
+            {
+                counts[index as usize] += 1;
+            }
+        }
+
+        assert_eq!(counts, [172, 172, 172, 171, 171, 171, 171]);
+        assert_eq!(test.get(), 1200 % 7);
+
+        test.set(u64::MAX);
+
+        assert_eq!(test.next_mod(7), u64::MAX % 7);
+        assert!(test.get() < 7);
+    }
+
+    //*********************************************************************************************
+    /// Test that next_mod() on an unshared object moves the value into shared storage that isn't
+    /// shared with anything, and that set() and get() keep working on it.
+    #[test]
+    fn test_next_mod_unshared()
+    {
+        let mut test = super::SharedU64::new(5);
+
+        assert!(!test.is_shared());
+        assert_eq!(test.next_mod(3), 2);
+        assert!(test.is_shared());
+        assert!(test.is_uniquely_owned());
+        assert_eq!(test.get(), 0);
+
+        test.set(1);
+
+        assert_eq!(test.next_mod(3), 1);
+        assert_eq!(test.get(), 2);
+    }
+
+    //*********************************************************************************************
+    /// Test that drain_all() resets every object and returns the old values.
+    #[test]
+    fn test_drain_all()
+    {
+        let mut test1 = super::SharedU64::new(79);
+        let test2     = test1.dup();
+        let handles   = vec![test1, super::SharedU64::new(41), super::SharedU64::new(0)];
+
+        assert_eq!(super::SharedU64::drain_all(&handles), vec![79, 41, 0]);
+        assert_eq!(super::SharedU64::drain_all(&handles), vec![0, 0, 0]);
+        assert_eq!(test2.get(), 0);
+    }
+
+    //*********************************************************************************************
+    /// Test that range_to() starts at the value when it was called.
+    #[test]
+    fn test_range_to()
+    {
+        let mut test1 = super::SharedU64::new(3);
+        let test2     = test1.dup();
+        let range     = test2.range_to(7);
+
+        test1.set(5);
+
+        assert_eq!(range.collect::<Vec<_>>(), vec![3, 4, 5, 6]);
+        assert_eq!(test2.range_to(7).count(), 2);
+        assert_eq!(test2.range_to(2).count(), 0);
+    }
+
+    //*********************************************************************************************
+    /// Test that try_acquire() never exceeds the capacity with concurrent callers.
+    #[test]
+    fn test_try_acquire()
+    {
+        use std::thread;
+
+        let mut test = super::SharedU64::new(0);
+
+        assert_eq!(test.try_acquire(5, 10), Ok(5));
+        assert_eq!(test.try_acquire(6, 10), Err(5));
+        assert_eq!(test.try_acquire(u64::MAX, u64::MAX), Err(5));
+
+        test.set(0);
+
+        let threads : Vec<_> = (0..8).map(|_| {
+            let bucket = test.dup();
+
+            thread::spawn(move || {
+                (0..100).filter(|_| bucket.try_acquire(1, 250).is_ok()).count()
+            })
+        }).collect();
+
+        let acquired : usize = threads.into_iter().map(|thread| thread.join().unwrap()).sum();
+
+        assert_eq!(acquired, 250);
+        assert_eq!(test.get(), 250);
+    }
+
+    //*********************************************************************************************
+    /// Test converting to and from Wrapping.
+    #[test]
+    fn test_wrapping()
+    {
+        use std::num::Wrapping;
+
+        let test = super::SharedU64::from(Wrapping(u64::MAX));
+
+        assert_eq!(test.get(), u64::MAX);
+        assert_eq!(test.wrapping() + Wrapping(1), Wrapping(u64::MIN));
+    }
+
+    //*********************************************************************************************
+    /// Test that concurrent checked_sub() calls never take the value below zero.
+    #[test]
+    fn test_checked_sub()
+    {
+        use std::thread;
+
+        let mut test = super::SharedU64::new(1000);
+
+        assert_eq!(test.checked_sub(1001), None);
+        assert_eq!(test.get(), 1000);
+
+        let mut threads = Vec::new();
+
+        for _ in 0..4
+        {
+            let handle = test.dup();
+
+            threads.push(thread::spawn(move || {
+                let mut success = 0;
+                let mut failed  = 0;
+
+                for _ in 0..300
+                {
+                    match handle.checked_sub(1)
+                    {
+                        Some(_) => success += 1,
+                        None    => failed += 1
+                    }
+                }
+
+                (success, failed)
+            }));
+        }
+
+        let (success, failed) = threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .fold((0, 0), |acc, val| (acc.0 + val.0, acc.1 + val.1));
+
+        assert_eq!(success, 1000);
+        assert_eq!(failed, 200);
+        assert_eq!(test.get(), 0);
+        assert_eq!(test.checked_sub(0), Some(0));
+    }
+
+    //*********************************************************************************************
+    /// Test load_acquire() and store_release() protecting a two word payload as a sequence lock.
+    /// The payload is accessed with Relaxed and ordered by fences, which strict-ordering can't
+    /// see, so with that feature the payload is accessed with Acquire and Release instead.
+    #[test]
+    fn test_seqlock()
+    {
+        use std::sync::atomic::{fence, Ordering};
+        use std::thread;
+
+        #[cfg(not(all(feature = "strict-ordering", debug_assertions)))]
+        const LOAD : Ordering = Ordering::Relaxed;
+        #[cfg(not(all(feature = "strict-ordering", debug_assertions)))]
+        const STORE : Ordering = Ordering::Relaxed;
+        #[cfg(all(feature = "strict-ordering", debug_assertions))]
+        const LOAD : Ordering = Ordering::Acquire;
+        #[cfg(all(feature = "strict-ordering", debug_assertions))]
+        const STORE : Ordering = Ordering::Release;
+
+        let mut seq    = super::SharedU64::new(0);
+        let mut first  = super::SharedU64::new(0);
+        let mut second = super::SharedU64::new(0);
+
+        let writer_seq        = seq.dup();
+        let mut writer_first  = first.dup();
+        let mut writer_second = second.dup();
+
+        let thread = thread::spawn(move || {
+            for i in 1..10001
+            {
+                let start = writer_seq.get_with(LOAD);
+
+                writer_seq.store_release(start + 1);
+                fence(Ordering::Release);
+
+                writer_first.set_with(i, STORE);
+                writer_second.set_with(i * 3, STORE);
+
+                writer_seq.store_release(start + 2);
+            }
+        });
+
+        let mut consistent = 0;
+
+        while consistent < 1000
+        {
+            let start = seq.load_acquire();
+            let a     = first.get_with(LOAD);
+            let b     = second.get_with(LOAD);
+
+            fence(Ordering::Acquire);
+
+            if start & 1 == 0 && seq.get_with(LOAD) == start
+            {
+                assert_eq!(a * 3, b);
+
+                consistent += 1;
+            }
+        }
+
+        thread.join().unwrap();
+
+        assert_eq!(seq.load_acquire(), 20000);
+        assert_eq!(second.get_with(LOAD), 30000);
+    }
+
+    //*********************************************************************************************
+    /// Test setting and clearing overlapping masks.
+    #[test]
+    fn test_set_clear_bits()
+    {
+        let test = super::SharedU64::new(0);
+
+        assert!(test.set_bits(0b0011));
+        assert!(test.set_bits(0b0110));
+        assert!(!test.set_bits(0b0111));
+        assert_eq!(test.get(), 0b0111);
+
+        assert!(test.clear_bits(0b1100));
+        assert!(!test.clear_bits(0b1100));
+        assert_eq!(test.get(), 0b0011);
+    }
+
+    //*********************************************************************************************
+    /// Test that when threads set overlapping ranges of bits, each bit is reported as newly set
+    /// by exactly one thread.
+    #[test]
+    fn test_set_bits_threads()
+    {
+        use std::thread;
+
+        let mut test    = super::SharedU64::new(0);
+        let mut threads = Vec::new();
+
+        for t in 0..4
+        {
+            let handle = test.dup();
+
+            threads.push(thread::spawn(move || {
+                (t * 8..t * 8 + 24).filter(|&bit| handle.set_bits(1 << bit)).count()
+            }));
+        }
+
+        let newly_set : usize = threads.into_iter().map(|thread| thread.join().unwrap()).sum();
+
+        assert_eq!(newly_set, 48);
+        assert_eq!(test.get(), (1 << 48) - 1);
+    }
+
+    //*********************************************************************************************
+    /// Test compare_exchange_value() when the exchange succeeds and fails.
+    #[test]
+    fn test_compare_exchange_value()
+    {
+        let mut test1 = super::SharedU64::new(5);
+        let test2     = test1.dup();
+
+        assert_eq!(test1.compare_exchange_value(5, 9), 5);
+        assert_eq!(test2.get(), 9);
+
+        assert_eq!(test2.compare_exchange_value(5, 1), 9);
+        assert_eq!(test1.get(), 9);
+    }
+
+    //*********************************************************************************************
+    /// Test that the tickets taken by several threads are unique and contiguous.
+    #[test]
+    fn test_take_ticket()
+    {
+        use std::thread;
+
+        let mut test    = super::SharedU64::new(100);
+        let mut threads = Vec::new();
+
+        for _ in 0..4
+        {
+            let handle = test.dup();
+
+            threads.push(thread::spawn(move || {
+                (0..1000).map(|_| handle.take_ticket()).collect::<Vec<_>>()
+            }));
+        }
+
+        let mut tickets = Vec::new();
+
+        for thread in threads
+        {
+            tickets.extend(thread.join().unwrap());
+        }
+
+        tickets.sort_unstable();
+
+        assert_eq!(tickets, (100..4100).collect::<Vec<_>>());
+        assert_eq!(test.take_ticket(), 4100);
+    }
+
+    //*********************************************************************************************
+    /// Test that fetch_add() and fetch_sub() wrap around instead of panicking in debug builds.
+    #[test]
+    fn test_fetch_add_wraps()
+    {
+        let mut test1 = super::SharedU64::new(u64::MAX - 1);
+        let test2     = test1.dup();
+
+        assert_eq!(test1.fetch_add(1), u64::MAX - 1);
+        assert_eq!(test2.fetch_add(1), u64::MAX);
+        assert_eq!(test1.get(), u64::MIN);
+
+        assert_eq!(test2.fetch_sub(2), u64::MIN);
+        assert_eq!(test1.get(), u64::MAX - 1);
+    }
+
+    //*********************************************************************************************
+    /// Test using a counter from scoped threads through a shared reference.
+    #[test]
+    fn test_scoped_threads()
+    {
+        use std::thread;
+
+        let mut test1 = super::SharedU64::new(0);
+        let test2     = test1.dup();
+
+        thread::scope(|scope| {
+            for _ in 0..4
+            {
+                scope.spawn(|| {
+                    for _ in 0..1000
+                    {
+                        test1.fetch_add(1);
+                        test2.fetch_add(1);
+                    }
+                });
+            }
+        });
+
+        assert_eq!(test1.get(), 8000);
+
+        test2.store_release(5);
+
+        assert_eq!(test1.get(), 5);
+    }
+
+    //*********************************************************************************************
+    /// Test that only one of the threads racing to replace a small value succeeds.
+    #[test]
+    fn test_replace_if()
+    {
+        use std::thread;
+
+        let mut test    = super::SharedU64::new(10);
+        let mut threads = Vec::new();
+
+        for _ in 0..4
+        {
+            let handle = test.dup();
+
+            threads.push(thread::spawn(move || handle.replace_if(100, |old| old < 50)));
+        }
+
+        let results = threads.into_iter().map(|thread| thread.join().unwrap()).collect::<Vec<_>>();
+
+        assert_eq!(results.iter().filter(|result| **result == Ok(10)).count(), 1);
+        assert_eq!(results.iter().filter(|result| **result == Err(100)).count(), 3);
+        assert_eq!(test.get(), 100);
+        assert_eq!(test.replace_if(5, |old| old == 100), Ok(100));
+    }
+
+    //*********************************************************************************************
+    /// Test that pre_share() moves the value into the storage used by dup().
+    #[test]
+    fn test_pre_share()
+    {
+        let mut test1 = super::SharedU64::new(1);
+
+        assert!(!test1.is_shared());
+
+        test1.pre_share();
+
+        assert!(test1.is_shared());
+        assert!(test1.is_uniquely_owned());
+        assert_eq!(test1.get(), 1);
+
+        let storage = test1.storage() as *const _;
+        let test2   = test1.dup();
+
+        assert!(test2.is_shared());
+        assert_eq!(test2.storage() as *const _, storage);
+    }
+
+    //*********************************************************************************************
+    /// Test that the byte order methods store the swapped bytes.
+    #[test]
+    fn test_byte_order()
+    {
+        let val      = 0x1234_5678_9ABC_DEF0u64;
+        let mut test = super::SharedU64::new(0);
+
+        test.set_be(val);
+
+        assert_eq!(test.get(), val.to_be());
+        assert_eq!(test.get_be(), val);
+
+        test.set_le(val);
+
+        assert_eq!(test.get(), val.to_le());
+        assert_eq!(test.get_le(), val);
+        assert_eq!(test.get_be(), u64::from_be(val.to_le()));
+    }
+
+    //*********************************************************************************************
+    /// Test that fetch_max_with() only ever raises the value.
+    #[test]
+    fn test_fetch_max_with()
+    {
+        let test     = super::SharedU64::new(10);
+        let mut prev = 10;
+
+        for i in 0..10
+        {
+            let old = test.fetch_max_with(|old| if i & 1 == 0 { old - 3 } else { old + 2 });
+
+            assert_eq!(old, prev);
+            assert!(test.get() >= prev);
+
+            prev = test.get();
+        }
+
+        assert_eq!(test.get(), 20);
+    }
+
+    //*********************************************************************************************
+    /// Test parsing the value from a string.
+    #[test]
+    fn test_from_str()
+    {
+        let test : super::SharedU64 = "12".parse().unwrap();
+
+        assert_eq!(test.get(), "12".parse::<u64>().unwrap());
+        assert!("abc".parse::<super::SharedU64>().is_err());
+    }
+
+    //*********************************************************************************************
+    /// Test that saturating_add_flagged() only sets the flag when the result is clamped.
+    #[test]
+    fn test_saturating_add_flagged()
+    {
+        let test = super::SharedU64::new(u64::MAX - 2);
+
+        assert_eq!(test.saturating_add_flagged(2), (u64::MAX, false));
+        assert_eq!(test.saturating_add_flagged(0), (u64::MAX, false));
+        assert_eq!(test.saturating_add_flagged(1), (u64::MAX, true));
+        assert_eq!(test.get(), u64::MAX);
+    }
+
+    //*********************************************************************************************
+    /// Test that each milestone is reported exactly once by the racing threads.
+    #[test]
+    fn test_inc_report()
+    {
+        use std::sync::{Arc, Mutex};
+        use std::thread;
+
+        let mut test    = super::SharedU64::new(0);
+        let milestones  = Arc::new(Mutex::new(Vec::new()));
+        let mut threads = Vec::new();
+
+        for _ in 0..8
+        {
+            let counter    = test.dup();
+            let milestones = milestones.clone();
+
+            threads.push(thread::spawn(move || {
+                for _ in 0..1000
+                {
+                    counter.inc_report(100, |count| milestones.lock().unwrap().push(count));
+                }
+            }));
+        }
+
+        for thread in threads
+        {
+            thread.join().unwrap();
+        }
+
+        let mut milestones = milestones.lock().unwrap().clone();
+
+        milestones.sort();
+
+        assert_eq!(milestones, (1..81).map(|i| i * 100).collect::<Vec<u64>>());
+        assert_eq!(test.get(), 8000);
+
+        test.inc_report(0, |_| panic!("every of 0 reported a milestone"));
+    }
+
+    //*********************************************************************************************
+    /// Test that exactly one holder sees the count reach zero, after all the others are done.
+    #[test]
+    fn test_dec_and_test()
+    {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::thread;
+
+        let mut count   = super::SharedU64::new(8);
+        let done        = Arc::new(AtomicUsize::new(0));
+        let mut threads = Vec::new();
+
+        for _ in 0..8
+        {
+            let count = count.dup();
+            let done  = done.clone();
+
+            threads.push(thread::spawn(move || {
+                done.fetch_add(1, Ordering::Relaxed);
+
+                if count.dec_and_test()
+                {
+                    assert_eq!(done.load(Ordering::Relaxed), 8);
+
+                    return true;
+                }
+
+                false
+            }));
+        }
+
+        let zeros = threads.into_iter().map(|thread| thread.join().unwrap()).filter(|zero| *zero);
+
+        assert_eq!(zeros.count(), 1);
+        assert_eq!(count.get(), 0);
+    }
+
+    //*********************************************************************************************
+    /// Test that the objects returned by pair() share the value.
+    #[test]
+    fn test_pair()
+    {
+        let (mut test1, mut test2) = super::SharedU64::pair(3);
+
+        assert!(test1.is_shared() && test2.is_shared());
+        assert_eq!((test1.get(), test2.get()), (3, 3));
+
+        test1.set(4);
+        assert_eq!(test2.get(), 4);
+
+        test2.set(5);
+        assert_eq!(test1.get(), 5);
+    }
+
+    //*********************************************************************************************
+    /// Test a SeqCst store seen by an Acquire load on another object.
+    #[test]
+    fn test_get_set_with()
+    {
+        use std::sync::atomic::Ordering;
+        use std::thread;
+
+        let mut test1 = super::SharedU64::new(0);
+        let mut test2 = test1.dup();
+
+        let thread = thread::spawn(move || test2.set_with(7, Ordering::SeqCst));
+
+        thread.join().unwrap();
+
+        assert_eq!(test1.get_with(Ordering::Acquire), 7);
+
+        test1.set_with(0, Ordering::Release);
+        assert_eq!(test1.get_with(Ordering::SeqCst), 0);
+    }
+
+    //*********************************************************************************************
+    /// Test that exactly the callers that lowered the minimum see the flag.
+    #[test]
+    fn test_fetch_min_flagged()
+    {
+        use std::collections::HashSet;
+        use std::thread;
+
+        let mut test    = super::SharedU64::new(100);
+        let mut threads = Vec::new();
+
+        for t in 0..4
+        {
+            let low = test.dup();
+
+            threads.push(thread::spawn(move || {
+                (0..20).map(|i| {
+                    let val         = 99 - ((i % 5) * 16 + t * 4 + i / 5);
+                    let (old, lower) = low.fetch_min_flagged(val);
+
+                    (val, old, lower)
+                }).collect::<Vec<_>>()
+            }));
+        }
+
+        let results  = threads.into_iter().flat_map(|thread| thread.join().unwrap());
+        let results  = results.collect::<Vec<_>>();
+        let lowered  = results.iter().filter(|r| r.2).map(|r| r.0).collect::<HashSet<_>>();
+        let mut mins = results.iter().map(|r| r.1).collect::<HashSet<_>>();
+
+        mins.insert(test.get());
+        mins.remove(&100);
+
+        assert!(results.iter().all(|&(val, old, lower)| lower == (val < old)));
+        assert_eq!(lowered, mins);
+        assert_eq!(test.get(), 20);
+    }
+
+    //*********************************************************************************************
+    /// Test that with_value() restores the value after the scope, including after a panic.
+    #[test]
+    fn test_with_value()
+    {
+        use std::panic::{self, AssertUnwindSafe};
+
+        let mut test1 = super::SharedU64::new(1);
+        let test2     = test1.dup();
+
+        assert_eq!(test1.with_value(2, || test2.get()), 2);
+        assert_eq!(test2.get(), 1);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            test1.with_value(2, || panic!("inside the scope"))
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(test2.get(), 1);
+    }
+
+    //*********************************************************************************************
+    /// Test compare_exchange() succeeding and failing with 1 and 2 instances.
+    #[test]
+    fn test_compare_exchange()
+    {
+        use std::sync::atomic::Ordering;
+
+        let mut test1 = super::SharedU64::new(5);
+
+        assert_eq!(test1.compare_exchange(6, 7, Ordering::AcqRel, Ordering::Acquire), Err(5));
+        assert_eq!(test1.compare_exchange(5, 7, Ordering::AcqRel, Ordering::Acquire), Ok(5));
+        assert_eq!(test1.get(), 7);
+
+        let test2 = test1.dup();
+
+        assert_eq!(test2.compare_exchange(5, 9, Ordering::SeqCst, Ordering::Relaxed), Err(7));
+        assert_eq!(test2.compare_exchange(7, 9, Ordering::SeqCst, Ordering::Relaxed), Ok(7));
+        assert_eq!(test1.get(), 9);
+    }
+
+    //*********************************************************************************************
+    /// Test that swap() returns the previous value with 1 and 2 instances.
+    #[test]
+    fn test_swap()
+    {
+        let mut test1 = super::SharedU64::new(1);
+
+        assert_eq!(test1.swap(2), 1);
+        assert_eq!(test1.get(), 2);
+
+        let mut test2 = test1.dup();
+
+        assert_eq!(test2.swap(3), 2);
+        assert_eq!(test1.swap(1), 3);
+        assert_eq!(test2.get(), 1);
+    }
+
+    //*********************************************************************************************
+    /// Test that clone() shares the value through a shared reference, like dup() does.
+    #[test]
+    fn test_clone()
+    {
+        let mut test1 = super::SharedU64::new(1);
+        let holder    = &test1;
+        let mut test2 = holder.clone();
+        let test3     = test2.clone();
+
+        assert!(test1.is_shared());
+        assert!(!test1.is_uniquely_owned());
+
+        test1.set(2);
+        assert_eq!(test2.get(), 2);
+        assert_eq!(test3.get(), 2);
+
+        test2.set(3);
+        assert_eq!(test1.get(), 3);
+
+        drop(test2);
+        drop(test3);
+        assert!(test1.is_uniquely_owned());
+    }
+
+    //*********************************************************************************************
+    /// Test that update_full() reports the old and new values and whether the value changed.
+    #[test]
+    fn test_update_full()
+    {
+        let mut test1 = super::SharedU64::new(5);
+        let test2     = test1.dup();
+        let result    = test1.update_full(|val| val + 2);
+
+        assert_eq!((result.old, result.new, result.changed), (5, 7, true));
+
+        let result = test2.update_full(|val| val);
+
+        assert_eq!(result, ::UpdateResult { old : 7, new : 7, changed : false });
+        assert_eq!(test2.update_full(|val| val - 7).new, 0);
+        assert_eq!(test1.get(), 0);
+    }
+
+    //*********************************************************************************************
+    /// Test that the value survives a round trip through an atomic.
+    #[test]
+    fn test_from_atomic()
+    {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        let mut test1 = super::SharedU64::from(AtomicU64::new(31));
+        let test2     = test1.dup();
+
+        test1.set(63);
+
+        let test3 = AtomicU64::from(test2);
+
+        assert_eq!(test3.load(Ordering::Relaxed), 63);
+        assert_eq!(test1.get(), 63);
+
+        let test4 = super::SharedU64::from(test3);
+
+        assert!(!test4.is_shared());
+        assert_eq!(test4.get(), 63);
+    }
 }
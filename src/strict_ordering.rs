@@ -0,0 +1,129 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+//! Checks for the strict-ordering feature, only compiled into debug builds.
+//!
+//! get() and set() on the atomic types use Relaxed, which is fine for counters but not for a
+//! flag that publishes other data: the reader can see the flag without the data.  With the
+//! feature, the objects sharing a value remember the threads that accessed it.  Once it has been
+//! accessed from more than one thread, a get() or set() with Relaxed ordering panics, so the
+//! code has to use get_with() with Acquire and set_with() with Release (or SeqCst).
+//!
+//! Code that orders Relaxed accesses with explicit fences, like a sequence lock, is correct but
+//! still panics, the check can't see the fences.
+use std::sync::{Arc, OnceLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, ThreadId};
+
+//*************************************************************************************************
+/// The threads that accessed a shared value.
+struct Seen
+{
+    //---------------------------------------------------------------------------------------------
+    /// The first thread that accessed the value.
+    first   : OnceLock<ThreadId>,
+
+    //---------------------------------------------------------------------------------------------
+    /// Set once another thread accessed the value.
+    several : AtomicBool
+}
+
+//*************************************************************************************************
+/// Tracks the threads that access a value, shared by all the objects sharing the value.
+#[derive(Clone)]
+pub(crate) struct ThreadTracker(Arc<Seen>);
+
+impl ThreadTracker
+{
+    //********************************************************************************************
+    /// Construct a new instance of the object that hasn't seen any thread.
+    pub(crate) fn new() -> ThreadTracker
+    {
+        ThreadTracker(Arc::new(Seen {
+            first   : OnceLock::new(),
+            several : AtomicBool::new(false)
+        }))
+    }
+
+    //********************************************************************************************
+    /// Records an access from the current thread, and panics if the value has been accessed from
+    /// several threads and order is Relaxed.  Unshared objects are only visible to one thread, so
+    /// they are not checked.
+    pub(crate) fn check(
+        &self,
+        shared : bool,
+        order  : Ordering
+        )
+    {
+        if !shared
+        {
+            return;
+        }
+
+        let current = thread::current().id();
+
+        if *self.0.first.get_or_init(|| current) != current
+        {
+            self.0.several.store(true, Ordering::Relaxed);
+        }
+
+        assert!(
+            order != Ordering::Relaxed || !self.0.several.load(Ordering::Relaxed),
+            "Relaxed access to a value used by several threads, use get_with(Ordering::Acquire) \
+             and set_with(_, Ordering::Release) (strict-ordering)"
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::atomic::Ordering;
+    use std::thread;
+    use {SharedBool, SharedU32};
+
+    //*********************************************************************************************
+    /// Test that Relaxed get() and set() panic once the value is used by several threads.
+    #[test]
+    fn test_relaxed_panics()
+    {
+        let mut ready   = SharedBool::new(false);
+        let mut payload = SharedU32::new(0);
+        let mut writer1 = ready.dup();
+        let mut writer2 = payload.dup();
+
+        thread::spawn(move || {
+            writer2.set(42);
+            writer1.set(true);
+        }).join().unwrap();
+
+        assert!(panic::catch_unwind(AssertUnwindSafe(|| ready.get())).is_err());
+        assert!(panic::catch_unwind(AssertUnwindSafe(|| payload.set(1))).is_err());
+    }
+
+    //*********************************************************************************************
+    /// Test that Acquire and Release are accepted, and that objects only used by one thread are
+    /// not checked.
+    #[test]
+    fn test_ordered_accepted()
+    {
+        let mut ready  = SharedBool::new(false);
+        let mut writer = ready.dup();
+
+        thread::spawn(move || writer.set_with(true, Ordering::Release)).join().unwrap();
+
+        assert!(ready.get_with(Ordering::Acquire));
+
+        let mut local = SharedU32::new(1);
+        let copy      = local.dup();
+
+        local.set(2);
+        assert_eq!(copy.get(), 2);
+    }
+}
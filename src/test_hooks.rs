@@ -0,0 +1,273 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+//! Hooks for testing the race windows, only available with the `test-hooks` feature.
+//!
+//! A hook set with set() is called on the current thread whenever it reaches one of the points
+//! listed in Point.  The hook can pause, yield, or change the value through another object so a
+//! test can force a specific interleaving instead of hoping the scheduler hits it.  Without the
+//! feature the points compile to nothing.
+//!
+//! The hook is not called while it is already running, so it can use the objects it is
+//! instrumenting.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "test-hooks")]
+//! # {
+//! use shareable::SharedU32;
+//! use shareable::test_hooks::{self, Point};
+//!
+//! let mut value1 = SharedU32::new(1);
+//! let mut value2 = value1.dup();
+//! let mut calls  = 0;
+//!
+//! // Change the value the first time replace_if() is about to swap, forcing a retry.
+//! test_hooks::set(move |point| {
+//!     if point == Point::CompareExchange && calls == 0
+//!     {
+//!         value2.set(2);
+//!     }
+//!     calls += 1;
+//! });
+//!
+//! assert_eq!(value1.replace_if(5, |old| old < 10), Ok(2));
+//! test_hooks::clear();
+//! # }
+//! ```
+#[cfg(feature = "test-hooks")]
+use std::cell::RefCell;
+#[cfg(feature = "test-hooks")]
+use std::mem;
+
+//*************************************************************************************************
+/// The points in the code where the hook is called.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Point
+{
+    //---------------------------------------------------------------------------------------------
+    /// In dup(), after the value of an unshared object has been read and before the shared
+    /// storage is installed.
+    Dup,
+
+    //---------------------------------------------------------------------------------------------
+    /// In a compare and swap loop, after the current value has been read and before the new
+    /// value is swapped in.  Changing the value here makes the swap fail and the loop retry.
+    /// For the 64 bit types on 32 bit targets the hook runs while the Mutex guarding the value
+    /// is held, so it must not use that value.
+    CompareExchange
+}
+
+//*************************************************************************************************
+/// State of the hook for the current thread.
+#[cfg(feature = "test-hooks")]
+enum Slot
+{
+    //---------------------------------------------------------------------------------------------
+    /// No hook is set.
+    Empty,
+
+    //---------------------------------------------------------------------------------------------
+    /// The hook to call.
+    Set(Box<dyn FnMut(Point)>),
+
+    //---------------------------------------------------------------------------------------------
+    /// The hook is running.
+    Running
+}
+
+#[cfg(feature = "test-hooks")]
+thread_local! {
+    //---------------------------------------------------------------------------------------------
+    /// The hook for the current thread.
+    static HOOK : RefCell<Slot> = const { RefCell::new(Slot::Empty) };
+}
+
+//*************************************************************************************************
+/// Sets the hook for the current thread, replacing any previous one.  Calling this from inside
+/// the hook replaces it once it returns.
+#[cfg(feature = "test-hooks")]
+pub fn set<F : FnMut(Point) + 'static>(hook : F)
+{
+    HOOK.with(|slot| *slot.borrow_mut() = Slot::Set(Box::new(hook)));
+}
+
+//*************************************************************************************************
+/// Removes the hook for the current thread.
+#[cfg(feature = "test-hooks")]
+pub fn clear()
+{
+    HOOK.with(|slot| *slot.borrow_mut() = Slot::Empty);
+}
+
+//*************************************************************************************************
+/// Called at each point, runs the hook for the current thread if there is one.
+#[inline]
+#[cfg_attr(not(feature = "test-hooks"), allow(unused_variables))]
+pub(crate) fn hit(point : Point)
+{
+    #[cfg(feature = "test-hooks")]
+    {
+        let hook = HOOK.with(|slot| {
+            let mut slot = slot.borrow_mut();
+
+            match mem::replace(&mut *slot, Slot::Running)
+            {
+                Slot::Set(hook) => Some(hook),
+                other           => { *slot = other; None }
+            }
+        });
+
+        if let Some(mut hook) = hook
+        {
+            hook(point);
+
+            HOOK.with(|slot| {
+                let mut slot = slot.borrow_mut();
+
+                if let Slot::Running = *slot
+                {
+                    *slot = Slot::Set(hook);
+                }
+            });
+        }
+    }
+}
+
+#[cfg(all(test, feature = "test-hooks"))]
+mod tests
+{
+    use std::panic::{self, AssertUnwindSafe};
+    use std::rc::Rc;
+    use std::cell::Cell;
+    use super::Point;
+    use {SharedObject, SharedU32};
+
+    //*********************************************************************************************
+    /// Test that replace_if() retries when the value changes before the swap.
+    #[test]
+    fn test_replace_if_retries()
+    {
+        let mut test1 = SharedU32::new(1);
+        let mut test2 = test1.dup();
+        let calls     = Rc::new(Cell::new(0));
+        let seen      = calls.clone();
+
+        super::set(move |point| {
+            if point == Point::CompareExchange && seen.get() == 0
+            {
+                test2.set(20);
+            }
+            seen.set(seen.get() + 1);
+        });
+
+        let mut values = Vec::new();
+        let result     = test1.replace_if(5, |old| { values.push(old); old < 10 });
+
+        super::clear();
+
+        assert_eq!(result, Err(20));
+        assert_eq!(values, vec![1, 20]);
+        assert_eq!(calls.get(), 2);
+        assert_eq!(test1.get(), 20);
+    }
+
+    //*********************************************************************************************
+    /// Test that a retried replace_if() that still passes stores the new value.
+    #[test]
+    fn test_replace_if_retry_replaces()
+    {
+        let mut test1 = SharedU32::new(1);
+        let mut test2 = test1.dup();
+        let mut once  = true;
+
+        super::set(move |_| {
+            if once
+            {
+                test2.set(2);
+                once = false;
+            }
+        });
+
+        let result = test1.replace_if(5, |old| old < 10);
+
+        super::clear();
+
+        assert_eq!(result, Ok(2));
+        assert_eq!(test1.get(), 5);
+    }
+
+    //*********************************************************************************************
+    /// Test that a panic between reading the value and installing the shared storage leaves the
+    /// object unshared and usable.
+    #[test]
+    fn test_dup_interrupted()
+    {
+        let mut test1 = SharedObject::new(1);
+
+        super::set(|point| if point == Point::Dup { panic!("interrupted") });
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| test1.dup()));
+
+        super::clear();
+
+        assert!(result.is_err());
+        assert!(!test1.is_shared());
+
+        let test2 = test1.dup();
+
+        test1.set(2);
+
+        assert_eq!(*test2.get(), 2);
+    }
+
+    //*********************************************************************************************
+    /// Test that the hook is only called when the object is upgraded, and not while it is already
+    /// running.
+    #[test]
+    fn test_dup_calls()
+    {
+        let calls     = Rc::new(Cell::new(0));
+        let seen      = calls.clone();
+        let mut test1 = SharedU32::new(1);
+
+        super::set(move |_| {
+            seen.set(seen.get() + 1);
+
+            let mut inner = SharedU32::new(2);
+            inner.dup();
+        });
+
+        let _test2 = test1.dup();
+        let _test3 = test1.dup();
+
+        super::clear();
+
+        assert_eq!(calls.get(), 1);
+    }
+
+    //*********************************************************************************************
+    /// Test that the hook can clear itself.
+    #[test]
+    fn test_clear_inside()
+    {
+        let calls = Rc::new(Cell::new(0));
+        let seen  = calls.clone();
+
+        super::set(move |_| {
+            seen.set(seen.get() + 1);
+            super::clear();
+        });
+
+        SharedU32::new(1).dup();
+        SharedU32::new(1).dup();
+
+        assert_eq!(calls.get(), 1);
+    }
+}
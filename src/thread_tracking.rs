@@ -0,0 +1,68 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, ThreadId};
+
+//*************************************************************************************************
+/// The threads that accessed a value, shared by all the objects sharing the value.  Only built
+/// with the thread-tracking feature.
+#[derive(Clone, Debug)]
+pub(crate) struct ThreadSet(Arc<Mutex<HashSet<ThreadId>>>);
+
+impl ThreadSet
+{
+    //********************************************************************************************
+    /// Construct a new instance of the object that hasn't seen any threads.
+    pub(crate) fn new() -> ThreadSet
+    {
+        ThreadSet(Arc::new(Mutex::new(HashSet::new())))
+    }
+
+    //********************************************************************************************
+    /// Records the current thread.
+    pub(crate) fn record(&self)
+    {
+        self.0.lock().unwrap().insert(thread::current().id());
+    }
+
+    //********************************************************************************************
+    /// Returns the number of distinct threads recorded.
+    pub(crate) fn count(&self) -> usize
+    {
+        self.0.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::thread;
+
+    //*********************************************************************************************
+    /// Test that each thread is only counted once.
+    #[test]
+    fn test_count()
+    {
+        let test = super::ThreadSet::new();
+
+        assert_eq!(test.count(), 0);
+
+        test.record();
+        test.record();
+
+        assert_eq!(test.count(), 1);
+
+        let copy = test.clone();
+
+        thread::spawn(move || copy.record()).join().unwrap();
+
+        assert_eq!(test.count(), 2);
+    }
+}
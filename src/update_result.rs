@@ -0,0 +1,26 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//*************************************************************************************************
+/// Result of update_full(), describes the update that was applied.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UpdateResult<T>
+{
+    //---------------------------------------------------------------------------------------------
+    /// The value before the update.
+    pub old     : T,
+
+    //---------------------------------------------------------------------------------------------
+    /// The value after the update.
+    pub new     : T,
+
+    //---------------------------------------------------------------------------------------------
+    /// True if the new value is different from the old value.
+    pub changed : bool
+}
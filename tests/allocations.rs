@@ -0,0 +1,104 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Tests that need to count heap allocations.  They live in their own test binary because the
+//! counting allocator replaces the global allocator.
+extern crate shareable;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use shareable::{SharedObject, SharedU64};
+
+//*************************************************************************************************
+/// Allocator that counts the allocations made by the current thread, so tests running in
+/// parallel don't see each other's allocations.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS : Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator
+{
+    unsafe fn alloc(&self, layout : Layout) -> *mut u8
+    {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr : *mut u8, layout : Layout)
+    {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR : CountingAllocator = CountingAllocator;
+
+//*************************************************************************************************
+/// Returns the number of allocations made by the current thread while running the closure.
+fn allocations<F : FnOnce()>(f : F) -> usize
+{
+    let before = ALLOCATIONS.with(|count| count.get());
+
+    f();
+
+    ALLOCATIONS.with(|count| count.get()) - before
+}
+
+//*************************************************************************************************
+/// Test that a defaulted object doesn't allocate until the value is needed.  The thread-tracking
+/// feature allocates the thread set up front, so the test is skipped with it.
+#[test]
+#[cfg(not(feature = "thread-tracking"))]
+fn default_is_lazy()
+{
+    assert_eq!(allocations(|| {
+        let test : SharedObject<Vec<u8>> = Default::default();
+
+        drop(test);
+    }), 0);
+
+    // The audit log allocates when the first change is recorded.
+    #[cfg(not(feature = "audit"))]
+    assert_eq!(allocations(|| {
+        let mut test : SharedObject<Vec<u8>> = Default::default();
+
+        test.set(vec![1]);
+    }), 2);
+
+    assert_eq!(allocations(|| {
+        let test : SharedObject<Vec<u8>> = Default::default();
+
+        assert!(test.get().is_empty());
+    }), 1);
+}
+
+//*************************************************************************************************
+/// Test that dup() doesn't allocate after pre_share().
+#[test]
+fn pre_share_allocates_once()
+{
+    let mut test = SharedObject::new(vec![1u8]);
+
+    // The audit log is also allocated by pre_share().
+    #[cfg(not(feature = "audit"))]
+    assert_eq!(allocations(|| test.pre_share()), 2);
+    #[cfg(feature = "audit")]
+    test.pre_share();
+
+    assert_eq!(allocations(|| drop(test.dup())), 0);
+
+    let mut test = SharedU64::new(1);
+
+    assert_eq!(allocations(|| test.pre_share()), 1);
+    assert_eq!(allocations(|| drop(test.dup())), 0);
+}
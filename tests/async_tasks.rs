@@ -0,0 +1,76 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Tests that the shareable elements can be moved into tasks of an async runtime.  The crate is
+//! edition 2015, which has no async blocks, so the tasks are written with poll_fn().  Each task
+//! suspends once before finishing, like a task stopping at an .await, so the runtime can resume
+//! it on another worker thread.
+extern crate shareable;
+extern crate tokio;
+
+use std::future::{self, Future};
+use std::task::Poll;
+
+use shareable::{SharedObject, SharedU64};
+
+//*************************************************************************************************
+/// Compiles only if T can be moved into a task spawned on a multi threaded runtime.
+fn assert_spawnable<T : Send + 'static>() {}
+
+//*************************************************************************************************
+/// Returns a task that calls step, suspends once, and then calls step again.
+fn task<F : FnMut() + Send + 'static>(mut step : F) -> impl Future<Output = ()> + Send + 'static
+{
+    let mut suspended = false;
+
+    future::poll_fn(move |cx| {
+        step();
+
+        if suspended
+        {
+            return Poll::Ready(());
+        }
+
+        suspended = true;
+        cx.waker().wake_by_ref();
+
+        Poll::Pending
+    })
+}
+
+//*************************************************************************************************
+/// Test that the cloned handles are Send and 'static.
+#[test]
+fn spawnable()
+{
+    assert_spawnable::<SharedU64>();
+    assert_spawnable::<SharedObject<String>>();
+}
+
+//*************************************************************************************************
+/// Test that clones used from several tasks update the same counter.
+#[test]
+fn fetch_add_from_tasks()
+{
+    let runtime = tokio::runtime::Builder::new_multi_thread().worker_threads(4).build().unwrap();
+    let counter = SharedU64::new(0);
+
+    let handles : Vec<_> = (0..16).map(|_| {
+        let handle = counter.clone();
+
+        runtime.spawn(task(move || { handle.fetch_add(1); }))
+    }).collect();
+
+    for handle in handles
+    {
+        runtime.block_on(handle).unwrap();
+    }
+
+    assert_eq!(counter.get(), 32);
+}
@@ -0,0 +1,14 @@
+extern crate shareable;
+
+use std::thread;
+use shareable::LocalObject;
+
+fn main()
+{
+    let mut value1 = LocalObject::new(String::from("abc"));
+    let value2     = value1.dup_local();
+
+    thread::spawn(move || {
+        println!("{}", value2.get());
+    });
+}
@@ -0,0 +1,11 @@
+extern crate shareable;
+
+use shareable::SharedObject;
+
+fn main()
+{
+    let mut value = SharedObject::new(String::from("abc"));
+    let reader    = value.reader();
+
+    reader.set(String::from("xyz"));
+}
@@ -0,0 +1,30 @@
+/* Copyright 2016 Joshua Gentry
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+//! Checks that the library still builds as no_std.  The build is for the host target, which is
+//! enough to catch a use of std in the modules compiled without the std feature.
+use std::path::Path;
+use std::process::Command;
+
+//*************************************************************************************************
+/// Build the library with --no-default-features, in its own target directory so it doesn't wait
+/// on the lock held by the cargo running this test.
+#[test]
+fn build()
+{
+    let root   = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let status = Command::new(env!("CARGO"))
+        .current_dir(root)
+        .args(["build", "--lib", "--no-default-features", "--target-dir"])
+        .arg(root.join("target").join("no_std"))
+        .status()
+        .unwrap();
+
+    assert!(status.success());
+}